@@ -0,0 +1,99 @@
+//! Serializes a [Sum](crate::Sum) or a [Balance](crate::Balance) as a map
+//! from units to amounts, with each amount as a string.
+//!
+//! This suits number types of arbitrary precision, such as big decimals,
+//! that a format's own numbers cannot represent exactly. Amounts are
+//! rendered using [Display](std::fmt::Display) and parsed using
+//! [FromStr].
+//!
+//! ```
+//! # use bookkeeping::Sum;
+//! # use serde::Serialize;
+//! #[derive(Serialize)]
+//! struct Snapshot {
+//!     #[serde(with = "bookkeeping::amount_as_string")]
+//!     sum: Sum<&'static str, f64>,
+//! }
+//! let mut sum = Sum::default();
+//! sum.set_amount_for_unit(0.1, "USD");
+//! let json = serde_json::to_string(&Snapshot { sum }).unwrap();
+//! assert_eq!(json, r#"{"sum":{"USD":"0.1"}}"#);
+//! ```
+use crate::unit_amounts::UnitAmounts;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{collections::BTreeMap, fmt, str::FromStr};
+/// Serializes as a map of amounts as strings.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: UnitAmounts,
+    T::Unit: Serialize,
+    T::Number: fmt::Display,
+    S: Serializer,
+{
+    serializer.collect_map(
+        value
+            .unit_amounts()
+            .iter()
+            .map(|(unit, amount)| (unit, amount.to_string())),
+    )
+}
+/// Deserializes from a map of amounts as strings.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: UnitAmounts,
+    T::Unit: Deserialize<'de>,
+    T::Number: FromStr,
+    <T::Number as FromStr>::Err: fmt::Display,
+    D: Deserializer<'de>,
+{
+    BTreeMap::<T::Unit, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(unit, amount)| {
+            amount
+                .parse()
+                .map(|amount| (unit, amount))
+                .map_err(de::Error::custom)
+        })
+        .collect::<Result<_, _>>()
+        .map(T::from_unit_amounts)
+}
+#[cfg(test)]
+mod test {
+    use crate::{Balance, Sum};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        #[serde(with = "super")]
+        sum: Sum<String, u128>,
+        #[serde(with = "super")]
+        balance: Balance<String, f64>,
+    }
+    #[test]
+    fn serialize_deserialize() {
+        let usd = "USD".to_string();
+        let btc = "BTC".to_string();
+        let mut sum = Sum::default();
+        sum.set_amount_for_unit(u128::MAX, btc.clone());
+        let mut balance = Balance::default();
+        balance -= &(usd.clone(), 0.1);
+        let record = Record { sum, balance };
+        let actual = serde_json::to_value(&record).unwrap();
+        let expected = json!({
+            "sum": { "BTC": "340282366920938463463374607431768211455" },
+            "balance": { "USD": "-0.1" },
+        });
+        assert_eq!(actual, expected);
+        let deserialized: Record = serde_json::from_value(actual).unwrap();
+        assert_eq!(deserialized, record);
+    }
+    #[test]
+    fn deserialize_invalid_amount() {
+        let error = serde_json::from_value::<Record>(json!({
+            "sum": { "BTC": "a lot" },
+            "balance": {},
+        }))
+        .unwrap_err();
+        assert_eq!(error.to_string(), "invalid digit found in string");
+    }
+}
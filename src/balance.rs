@@ -1,16 +1,93 @@
-use crate::sum::Sum;
+use crate::{
+    book::AccountKey,
+    format::FormatAmount,
+    move_::Side,
+    sum::{amounts_approx_eq, render_amounts, Sum},
+    transaction::Transaction,
+};
 use std::{
-    collections::BTreeMap,
+    collections::{btree_map, BTreeMap, HashMap},
     fmt,
-    ops::{Add, AddAssign, Sub, SubAssign},
+    hash::Hash,
+    iter::FromIterator,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
 };
 /// Represents a [balance](https://en.wikipedia.org/wiki/Balance_(accounting)), yet not necessarily the current balance.
-#[derive(PartialEq, Clone)]
+///
+/// With the `serde` feature, serializes as a map from units to amounts.
+/// See the `serde_entries` module for an alternative representation.
+#[derive(PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        deserialize = "Unit: Ord + serde::Deserialize<'de>, Number: serde::Deserialize<'de>"
+    ))
+)]
 pub struct Balance<Unit, Number>(pub(crate) BTreeMap<Unit, Number>);
 impl<Unit, Number> Balance<Unit, Number>
 where
     Unit: Ord + Clone,
 {
+    /// Applies the sums of moves of an account, each along with its
+    /// location and the side of the account in it.
+    ///
+    /// Sums of moves in which the account is the debit account are
+    /// subtracted and those in which it is the credit account are added.
+    /// The amount of a unit that is not in the balance starts from `zero`.
+    /// Returns the number of moves applied, or the unit and the location of
+    /// the first amount that `subtract` or `add` fails for.
+    pub(crate) fn try_apply_account_moves<'a, Location, SumNumber>(
+        &mut self,
+        moves: impl IntoIterator<Item = (Location, Side, &'a Sum<Unit, SumNumber>)>,
+        zero: impl Fn() -> Number,
+        subtract: impl Fn(&Number, Number) -> Option<Number>,
+        add: impl Fn(&Number, Number) -> Option<Number>,
+    ) -> Result<usize, (Unit, Location)>
+    where
+        Unit: 'a,
+        Location: Copy,
+        SumNumber: Clone + Into<Number> + 'a,
+    {
+        let mut count = 0;
+        moves.into_iter().try_for_each(|(location, side, sum)| {
+            count += 1;
+            sum.0.iter().try_for_each(|(unit, amount)| {
+                let balance = self.0.entry(unit.clone()).or_insert_with(&zero);
+                let amount = amount.clone().into();
+                *balance = match side {
+                    Side::Debit => subtract(balance, amount),
+                    Side::Credit => add(balance, amount),
+                }
+                .ok_or_else(|| (unit.clone(), location))?;
+                Ok(())
+            })
+        })?;
+        Ok(count)
+    }
+    /// Applies the sums of moves of an account, with amounts that do not
+    /// overflow. See [Balance::try_apply_account_moves].
+    pub(crate) fn apply_account_moves<'a, Location, SumNumber>(
+        &mut self,
+        moves: impl IntoIterator<Item = (Location, Side, &'a Sum<Unit, SumNumber>)>,
+        zero: impl Fn() -> Number,
+    ) -> usize
+    where
+        Unit: 'a,
+        Number: Add<Output = Number> + Sub<Output = Number> + Clone,
+        Location: Copy,
+        SumNumber: Clone + Into<Number> + 'a,
+    {
+        match self.try_apply_account_moves(
+            moves,
+            zero,
+            |balance, amount| Some(balance.clone() - amount),
+            |balance, amount| Some(balance.clone() + amount),
+        ) {
+            Ok(count) => count,
+            Err(_) => unreachable!(),
+        }
+    }
     fn apply_sum_operation<SumNumber>(
         &mut self,
         rhs: &Sum<Unit, SumNumber>,
@@ -49,6 +126,216 @@ where
     pub fn unit_amount(&self, unit: &Unit) -> Option<&Number> {
         self.0.get(unit)
     }
+    /// Copies the amounts of all units into a [HashMap].
+    pub fn to_hashmap(&self) -> HashMap<Unit, Number>
+    where
+        Unit: Hash,
+        Number: Clone,
+    {
+        self.0.clone().into_iter().collect()
+    }
+    /// Calculates the difference from an expected balance, per unit.
+    ///
+    /// Missing amounts are the default. Units of equal amounts result in
+    /// the default amount, such as zero.
+    pub fn diff(&self, expected: &Self) -> Self
+    where
+        Number: Default + Sub<Output = Number> + Clone,
+    {
+        let mut difference = self.clone();
+        expected.0.iter().for_each(|(unit, amount)| {
+            difference.apply_unit_operation(
+                &(unit.clone(), amount.clone()),
+                |balance, amount| balance - amount,
+            );
+        });
+        difference
+    }
+    /// Compares to another balance, allowing the amounts of each unit to
+    /// differ by up to `epsilon`.
+    ///
+    /// Missing amounts are the default, such as zero.
+    pub fn approx_eq(&self, other: &Self, epsilon: Number) -> bool
+    where
+        Number: Sub<Output = Number>
+            + Neg<Output = Number>
+            + PartialOrd
+            + Clone
+            + Default,
+    {
+        amounts_approx_eq(&self.0, &other.0, epsilon)
+    }
+    /// Totals the amounts of all units in a single unit.
+    ///
+    /// Each amount is converted into `N` using `combine`. Amounts of other
+    /// units than `target` are multiplied by the rate of their unit, as
+    /// provided by `rate`.
+    ///
+    /// Returns `None` if `rate` provides no rate for some unit.
+    pub fn total_in<N>(
+        &self,
+        target: Unit,
+        rate: impl Fn(&Unit) -> Option<N>,
+        combine: impl Fn(&Number) -> N,
+    ) -> Option<N>
+    where
+        N: Default + Add<Output = N> + Mul<Output = N>,
+    {
+        self.0
+            .iter()
+            .try_fold(N::default(), |total, (unit, amount)| {
+                let amount = combine(amount);
+                Some(if *unit == target {
+                    total + amount
+                } else {
+                    total + amount * rate(unit)?
+                })
+            })
+    }
+    /// Converts the units of the balance.
+    ///
+    /// Amounts of units that convert to the same unit are added.
+    pub fn map_units<U2, F>(self, mut f: F) -> Balance<U2, Number>
+    where
+        U2: Ord,
+        F: FnMut(Unit) -> U2,
+        Number: Add<Output = Number> + Default,
+    {
+        let mut map = BTreeMap::new();
+        self.0.into_iter().for_each(|(unit, amount)| {
+            let entry: &mut Number = map.entry(f(unit)).or_default();
+            *entry = std::mem::take(entry) + amount;
+        });
+        Balance(map)
+    }
+    /// Renders like [Display](fmt::Display), with each amount right-aligned
+    /// to a width.
+    pub fn fmt_aligned(&self, amount_width: usize) -> String
+    where
+        Unit: fmt::Display,
+        Number: fmt::Display,
+    {
+        render_amounts(&self.0, amount_width)
+    }
+    /// Formats the amounts of all units, in order of unit, separated by
+    /// commas.
+    pub fn format_with(
+        &self,
+        formatter: &impl FormatAmount<Unit, Number>,
+    ) -> String {
+        self.0
+            .iter()
+            .map(|(unit, amount)| formatter.format(unit, amount))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+    /// Splits the amounts into a debit column and a credit column, per unit
+    /// in order of unit.
+    ///
+    /// Since debiting an account subtracts from its balance, amounts less
+    /// than the default go in the debit column and amounts greater than the
+    /// default go in the credit column. Amounts are kept as they are.
+    /// Default amounts, such as zero, go in neither.
+    pub fn columns(&self) -> Vec<(Unit, Option<Number>, Option<Number>)>
+    where
+        Number: Default + PartialOrd + Clone,
+    {
+        let zero = Number::default();
+        self.0
+            .iter()
+            .map(|(unit, amount)| {
+                let (debit, credit) = if *amount < zero {
+                    (Some(amount.clone()), None)
+                } else if *amount > zero {
+                    (None, Some(amount.clone()))
+                } else {
+                    (None, None)
+                };
+                (unit.clone(), debit, credit)
+            })
+            .collect()
+    }
+    /// Removes the units of default amounts, such as zero.
+    ///
+    /// Equality and hashing consider units of default amounts, so balances
+    /// are only comparable this way once normalized.
+    pub fn normalize(&mut self)
+    where
+        Number: Default + PartialEq,
+    {
+        let zero = Number::default();
+        self.0.retain(|_, amount| *amount != zero);
+    }
+    /// Multiplies the amount of each unit by a factor.
+    ///
+    /// All units are kept, even if their amounts become zero.
+    pub fn scale<Factor>(self, factor: Factor) -> Self
+    where
+        Number: Mul<Factor, Output = Number>,
+        Factor: Clone,
+    {
+        Balance(
+            self.0
+                .into_iter()
+                .map(|(unit, amount)| (unit, amount * factor.clone()))
+                .collect(),
+        )
+    }
+    /// Gets the number of units in the balance.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Whether the balance has no units.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Whether the amounts of all units are the default, such as zero.
+    ///
+    /// An empty balance is zero.
+    pub fn is_zero(&self) -> bool
+    where
+        Number: Default + PartialEq,
+    {
+        let zero = Number::default();
+        self.0.values().all(|amount| *amount == zero)
+    }
+    /// Removes the amounts of all units.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+    /// Gets the amount of a provided unit or the default amount if absent.
+    pub fn get_or_default(&self, unit: &Unit) -> Number
+    where
+        Number: Default + Clone,
+    {
+        self.0.get(unit).cloned().unwrap_or_default()
+    }
+}
+impl<Unit, Number> Balance<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Add<Output = Number> + Sub<Output = Number> + Clone,
+{
+    /// Applies the moves of a transaction that involve an account.
+    ///
+    /// Moves in which the account is the debit account are subtracted and
+    /// moves in which it is the credit account are added.
+    pub fn apply_transaction<SumNumber, TransactionExtra, MoveExtra>(
+        &mut self,
+        transaction: &Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>,
+        account_key: AccountKey,
+    ) where
+        SumNumber: Clone + Into<Number>,
+    {
+        self.apply_account_moves(
+            transaction.moves.iter().filter_map(|move_| {
+                move_
+                    .account_side(account_key)
+                    .map(|side| ((), side, &move_.sum))
+            }),
+            Number::default,
+        );
+    }
 }
 impl<Unit, Number> Default for Balance<Unit, Number>
 where
@@ -58,6 +345,47 @@ where
         Self(Default::default())
     }
 }
+/// Collects units and amounts. Of repeated units, the last amount is kept.
+impl<Unit, Number> FromIterator<(Unit, Number)> for Balance<Unit, Number>
+where
+    Unit: Ord,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (Unit, Number)>,
+    {
+        Balance(iter.into_iter().collect())
+    }
+}
+/// Yields the units and amounts in order of unit.
+impl<Unit, Number> IntoIterator for Balance<Unit, Number> {
+    type Item = (Unit, Number);
+    type IntoIter = btree_map::IntoIter<Unit, Number>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+/// Yields the units and amounts in order of unit.
+impl<'a, Unit, Number> IntoIterator for &'a Balance<Unit, Number> {
+    type Item = (&'a Unit, &'a Number);
+    type IntoIter = btree_map::Iter<'a, Unit, Number>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+/// Displays as `<amount> <unit>` per unit, in order of unit, separated by
+/// commas.
+///
+/// The width, fill and alignment of the formatter apply to the whole.
+impl<Unit, Number> fmt::Display for Balance<Unit, Number>
+where
+    Unit: fmt::Display,
+    Number: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&render_amounts(&self.0, 0))
+    }
+}
 impl<Unit, Number> fmt::Debug for Balance<Unit, Number>
 where
     Unit: fmt::Debug,
@@ -171,11 +499,92 @@ where
         self
     }
 }
+impl<Unit, Number, SumNumber> SubAssign<Sum<Unit, SumNumber>>
+    for Balance<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Sub<Output = Number> + Clone,
+    SumNumber: Clone + Into<Number>,
+{
+    fn sub_assign(&mut self, sum: Sum<Unit, SumNumber>) {
+        *self -= &sum;
+    }
+}
+impl<Unit, Number, SumNumber> Sub<Sum<Unit, SumNumber>>
+    for Balance<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Sub<Output = Number> + Clone,
+    SumNumber: Clone + Into<Number>,
+{
+    type Output = Self;
+    fn sub(self, sum: Sum<Unit, SumNumber>) -> Self::Output {
+        self - &sum
+    }
+}
+impl<Unit, Number, SumNumber> AddAssign<Sum<Unit, SumNumber>>
+    for Balance<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Add<Output = Number> + Clone,
+    SumNumber: Clone + Into<Number>,
+{
+    fn add_assign(&mut self, sum: Sum<Unit, SumNumber>) {
+        *self += &sum;
+    }
+}
+impl<Unit, Number, SumNumber> Add<Sum<Unit, SumNumber>>
+    for Balance<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Add<Output = Number> + Clone,
+    SumNumber: Clone + Into<Number>,
+{
+    type Output = Self;
+    fn add(self, sum: Sum<Unit, SumNumber>) -> Self::Output {
+        self + &sum
+    }
+}
+/// Subtracts the amounts of each unit. Same as [Balance::diff].
+impl<Unit, Number> Sub<&Balance<Unit, Number>> for Balance<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Sub<Output = Number> + Clone,
+{
+    type Output = Self;
+    fn sub(self, rhs: &Balance<Unit, Number>) -> Self::Output {
+        self.diff(rhs)
+    }
+}
+/// Negates the amount of each unit.
+///
+/// All units are kept, even if their amounts are zero.
+impl<Unit, Number> Neg for Balance<Unit, Number>
+where
+    Unit: Ord,
+    Number: Neg<Output = Number>,
+{
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Balance(
+            self.0
+                .into_iter()
+                .map(|(unit, amount)| (unit, -amount))
+                .collect(),
+        )
+    }
+}
 #[cfg(test)]
 mod test {
     use super::Balance;
-    use crate::test_utils::TestBalance;
+    use crate::{
+        book::TransactionIndex,
+        format::FormatAmount,
+        test_utils::{TestBalance, TestBook},
+        transaction::MoveIndex,
+    };
     use maplit::btreemap;
+    use std::collections::HashSet;
     #[test]
     fn default() {
         let actual = TestBalance::default();
@@ -187,12 +596,12 @@ mod test {
         let mut actual: TestBalance = Default::default();
         let usd = "USD";
         let thb = "THB";
-        let sum = sum!(2, usd; 3, thb);
+        let sum = test_sum!(2, usd; 3, thb);
         actual.apply_sum_operation(&sum, |balance, amount| {
             let rhs: i128 = amount.into();
             balance + rhs
         });
-        let sum = sum!(2, usd; 3, thb);
+        let sum = test_sum!(2, usd; 3, thb);
         actual.apply_sum_operation(&sum, |balance, amount| {
             let rhs: i128 = amount.into();
             balance * rhs
@@ -204,12 +613,84 @@ mod test {
         assert_eq!(actual, expected);
     }
     #[test]
+    fn eq_hash() {
+        let usd = "USD";
+        let mut set = HashSet::new();
+        set.insert(TestBalance::default() - &test_sum!(3, usd));
+        assert!(set.contains(&(TestBalance::default() - &test_sum!(3, usd))));
+        assert!(!set.contains(&(TestBalance::default() + &test_sum!(3, usd))));
+    }
+    #[test]
+    fn apply_transaction() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_b_key,
+            account_c_key,
+            test_sum!(1, usd; 2, thb),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_c_key,
+            account_b_key,
+            test_sum!(5, thb),
+            "",
+        );
+        let mut balance = TestBalance::default();
+        let mut transactions = book.transactions();
+        let (_, transaction) = transactions.next().unwrap();
+        balance.apply_transaction(transaction, account_b_key);
+        assert_eq!(
+            balance,
+            book.account_balance_at_transaction(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+        );
+        let (_, transaction) = transactions.next().unwrap();
+        balance.apply_transaction(transaction, account_b_key);
+        assert_eq!(
+            balance,
+            book.account_balance_at_transaction(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+        );
+    }
+    #[test]
+    fn fmt_display() {
+        let usd = "USD";
+        let thb = "THB";
+        let balance = Balance(btreemap! { usd => 3, thb => -45 });
+        assert_eq!(format!("{}", balance), "-45 THB, 3 USD");
+        assert_eq!(format!("{:>16}", balance), "  -45 THB, 3 USD");
+        assert_eq!(balance.fmt_aligned(4), " -45 THB,    3 USD");
+    }
+    #[test]
     fn fmt_debug() {
         let usd = "USD";
         let amount_usd = 76;
         let thb = "THB";
         let amount_thb = 45;
-        let sum = sum!(amount_usd, usd; amount_thb, thb);
+        let sum = test_sum!(amount_usd, usd; amount_thb, thb);
         let balance = TestBalance::default() + &sum;
         let actual = format!("{:?}", balance);
         let expected = format!(
@@ -222,7 +703,7 @@ mod test {
     fn sub_assign_sum() {
         let usd = "USD";
         let mut actual: TestBalance = Default::default();
-        actual -= &sum!(9, usd);
+        actual -= &test_sum!(9, usd);
         let expected = Balance(btreemap! {
             usd => -9,
         });
@@ -232,7 +713,7 @@ mod test {
     fn sub_sum() {
         let usd = "USD";
         let immutable: TestBalance = Default::default();
-        let actual = immutable - &sum!(9, usd);
+        let actual = immutable - &test_sum!(9, usd);
         let expected = Balance(btreemap! {
             usd => -9,
         });
@@ -242,7 +723,7 @@ mod test {
     fn add_assign_sum() {
         let usd = "USD";
         let mut actual: TestBalance = Default::default();
-        actual += &sum!(9, usd);
+        actual += &test_sum!(9, usd);
         let expected = Balance(btreemap! {
             usd => 9,
         });
@@ -252,19 +733,40 @@ mod test {
     fn add_sum() {
         let usd = "USD";
         let immutable: TestBalance = Default::default();
-        let actual = immutable + &sum!(9, usd);
+        let actual = immutable + &test_sum!(9, usd);
         let expected = Balance(btreemap! {
             usd => 9,
         });
         assert_eq!(actual, expected);
     }
     #[test]
+    fn owned_sum() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut actual: TestBalance = Default::default();
+        actual += test_sum!(9, usd);
+        actual -= test_sum!(2, thb);
+        let actual = actual + test_sum!(1, usd) - test_sum!(3, usd);
+        let expected: TestBalance = Default::default();
+        let expected = expected + &test_sum!(9, usd) - &test_sum!(2, thb)
+            + &test_sum!(1, usd)
+            - &test_sum!(3, usd);
+        assert_eq!(actual, expected);
+        assert_eq!(
+            actual,
+            Balance(btreemap! {
+                usd => 7,
+                thb => -2,
+            }),
+        );
+    }
+    #[test]
     fn amounts() {
         let usd = "USD";
         let thb = "THB";
         let ils = "ILS";
         let balance = TestBalance::default()
-            + &sum! {
+            + &test_sum! {
                 100, usd; 200, thb; 300, ils
             };
         let actual = balance.amounts().collect::<Vec<_>>();
@@ -276,9 +778,204 @@ mod test {
         let usd = "USD";
         let thb = "THB";
         let ils = "ILS";
-        let balance = TestBalance::default() + &sum!(200, usd; 100, thb);
+        let balance = TestBalance::default() + &test_sum!(200, usd; 100, thb);
         assert_eq!(balance.unit_amount(&usd).unwrap(), &200);
         assert_eq!(balance.unit_amount(&thb).unwrap(), &100);
         assert_eq!(balance.unit_amount(&ils), None);
     }
+    #[test]
+    fn to_hashmap() {
+        let usd = "USD";
+        let thb = "THB";
+        let balance = TestBalance::default() - &test_sum!(3, usd; 4, thb);
+        let actual = balance.to_hashmap();
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual.get(&usd), Some(&-3));
+        assert_eq!(actual.get(&thb), Some(&-4));
+    }
+    #[test]
+    fn approx_eq() {
+        let usd = "USD";
+        let thb = "THB";
+        let balance = Balance(btreemap! { usd => -1.0, thb => 2.0 });
+        assert!(balance
+            .approx_eq(&Balance(btreemap! { usd => -1.05, thb => 1.98 }), 0.1));
+        assert!(!balance
+            .approx_eq(&Balance(btreemap! { usd => -1.0, thb => 2.2 }), 0.1));
+        assert!(Balance(btreemap! { usd => -0.05 })
+            .approx_eq(&Balance::default(), 0.1));
+        assert!(!Balance::default()
+            .approx_eq(&Balance(btreemap! { usd => -0.5 }), 0.1));
+    }
+    #[test]
+    fn map_units() {
+        let usd = "USD";
+        let dollar = "$";
+        let thb = "THB";
+        let balance = Balance(btreemap! { usd => 3, dollar => -4, thb => 5 });
+        let actual =
+            balance.map_units(|unit| if unit == dollar { usd } else { unit });
+        assert_eq!(actual, Balance(btreemap! { usd => -1, thb => 5 }));
+    }
+    #[test]
+    fn format_with() {
+        struct Symbols;
+        impl FormatAmount<&str, i32> for Symbols {
+            fn format(&self, unit: &&str, amount: &i32) -> String {
+                let symbol = match *unit {
+                    "USD" => "$",
+                    "THB" => "฿",
+                    _ => unit,
+                };
+                format!("{}{}", symbol, amount)
+            }
+        }
+        let balance = Balance(btreemap! { "USD" => 3, "THB" => -4 });
+        assert_eq!(balance.format_with(&Symbols), "฿-4, $3");
+        assert_eq!(Balance::default().format_with(&Symbols), "");
+    }
+    #[test]
+    fn into_iter() {
+        let usd = "USD";
+        let thb = "THB";
+        let balance = Balance(btreemap! { usd => 3, thb => -4 });
+        let mut borrowed = Vec::new();
+        for (unit, amount) in &balance {
+            borrowed.push((unit, amount));
+        }
+        assert_eq!(borrowed, vec![(&thb, &-4), (&usd, &3)]);
+        let owned = balance.into_iter().collect::<Vec<_>>();
+        assert_eq!(owned, vec![(thb, -4), (usd, 3)]);
+    }
+    #[test]
+    fn columns() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let balance = Balance(btreemap! { usd => 3, thb => -4, ils => 0 });
+        assert_eq!(
+            balance.columns(),
+            vec![
+                (ils, None, None),
+                (thb, Some(-4), None),
+                (usd, None, Some(3)),
+            ],
+        );
+    }
+    #[test]
+    fn normalize() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut balance =
+            Balance(btreemap! { usd => 3_i128 }) - &test_sum!(3, usd);
+        balance += &test_sum!(2, thb);
+        assert_ne!(balance, Balance(btreemap! { thb => 2 }));
+        balance.normalize();
+        assert_eq!(balance, Balance(btreemap! { thb => 2 }));
+    }
+    #[test]
+    fn scale() {
+        let usd = "USD";
+        let thb = "THB";
+        let balance = Balance(btreemap! { usd => 3, thb => -4 });
+        assert_eq!(
+            balance.clone().scale(5),
+            Balance(btreemap! { usd => 15, thb => -20 }),
+        );
+        assert_eq!(balance.scale(0), Balance(btreemap! { usd => 0, thb => 0 }));
+    }
+    #[test]
+    fn neg() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        assert_eq!(
+            -Balance(btreemap! { usd => 3, thb => -4, ils => 0 }),
+            Balance(btreemap! { usd => -3, thb => 4, ils => 0 }),
+        );
+        assert_eq!(-TestBalance::default(), TestBalance::default());
+    }
+    #[test]
+    fn diff() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let actual = TestBalance::default() + &test_sum!(10, usd; 5, thb);
+        let expected =
+            TestBalance::default() + &test_sum!(10, usd; 7, thb; 1, ils);
+        assert_eq!(
+            actual.diff(&expected),
+            Balance(btreemap! {
+                usd => 0,
+                thb => -2,
+                ils => -1,
+            }),
+        );
+    }
+    #[test]
+    fn sub_balance() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let earlier =
+            TestBalance::default() + &test_sum!(10, usd; 7, thb; 1, ils);
+        let later = TestBalance::default() + &test_sum!(10, usd; 5, thb);
+        assert_eq!(
+            later - &earlier,
+            Balance(btreemap! {
+                usd => 0,
+                thb => -2,
+                ils => -1,
+            }),
+        );
+    }
+    #[test]
+    fn is_empty_and_is_zero() {
+        let usd = "USD";
+        let thb = "THB";
+        let balance = TestBalance::default();
+        assert!(balance.is_empty());
+        assert_eq!(balance.len(), 0);
+        assert!(balance.is_zero());
+        let balance =
+            TestBalance::default() + &test_sum!(3, usd) - &test_sum!(3, usd);
+        assert!(!balance.is_empty());
+        assert_eq!(balance.len(), 1);
+        assert!(balance.is_zero());
+        let balance = balance + &test_sum!(1, thb);
+        assert!(!balance.is_empty());
+        assert!(!balance.is_zero());
+    }
+    #[test]
+    fn total_in() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let rate = |unit: &&str| match *unit {
+            "THB" => Some(0.03),
+            "ILS" => Some(0.25),
+            _ => None,
+        };
+        let balance = TestBalance::default() + &test_sum!(2, usd; 300, thb)
+            - &test_sum!(4, ils);
+        let actual = balance.total_in(usd, rate, |amount| *amount as f64);
+        assert_eq!(actual, Some(2.0 + 9.0 - 1.0));
+        let actual = balance.total_in(thb, rate, |amount| *amount as f64);
+        assert_eq!(actual, None);
+    }
+    #[test]
+    fn clear() {
+        let usd = "USD";
+        let mut balance = TestBalance::default() - &test_sum!(3, usd);
+        balance.clear();
+        assert_eq!(balance, TestBalance::default());
+    }
+    #[test]
+    fn get_or_default() {
+        let usd = "USD";
+        let thb = "THB";
+        let balance = TestBalance::default() - &test_sum!(200, usd);
+        assert_eq!(balance.get_or_default(&usd), -200);
+        assert_eq!(balance.get_or_default(&thb), 0);
+    }
 }
@@ -1,27 +1,595 @@
 use crate::{
     balance::Balance,
+    diff::BookDiff,
+    edit::Edit,
+    event::{BookEvent, BookEventHandler},
     move_::{Move, Side},
+    op::{BookOp, BookOpError},
+    snapshot::BookSnapshot,
     sum::Sum,
     transaction::{MoveIndex, Transaction},
+    validation::ValidationIssue,
 };
-use slotmap::{new_key_type, DenseSlotMap};
-use std::ops::{Add, AddAssign, Sub, SubAssign};
-new_key_type! {
-    /// A key type for referencing accounts.
-    pub struct AccountKey;
+use slotmap::{secondary, DenseSlotMap, Key, KeyData, SecondaryMap};
+#[cfg(any(test, feature = "testing"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{
+    borrow::Borrow,
+    collections::{
+        hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap, HashSet,
+    },
+    error::Error,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::{self, Enumerate},
+    ops::{Add, Range, Sub},
+    slice,
+    sync::{Arc, OnceLock},
+};
+/// A key type for referencing accounts.
+///
+/// Displays and debugs as `Account#<index>v<version>`.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[repr(transparent)]
+pub struct AccountKey(KeyData);
+impl From<KeyData> for AccountKey {
+    fn from(key_data: KeyData) -> Self {
+        Self(key_data)
+    }
+}
+unsafe impl Key for AccountKey {
+    fn data(&self) -> KeyData {
+        self.0
+    }
+}
+/// Serializes as an integer.
+#[cfg(feature = "serde")]
+impl serde::Serialize for AccountKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u64(self.0.as_ffi())
+    }
+}
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AccountKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        u64::deserialize(deserializer).map(|ffi| Self(KeyData::from_ffi(ffi)))
+    }
+}
+impl fmt::Display for AccountKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ffi = self.0.as_ffi();
+        write!(f, "Account#{}v{}", ffi & 0xffff_ffff, ffi >> 32)
+    }
+}
+impl fmt::Debug for AccountKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
 }
 /// Represents a book.
+///
+/// With the `serde` feature, serializes its accounts and transactions,
+/// preserving account keys. Event handlers and undo history are not
+/// serialized. Deserialization fails for a book that does not pass
+/// [Book::validate].
 pub struct Book<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
 where
     Unit: Ord,
 {
-    accounts: DenseSlotMap<AccountKey, AccountExtra>,
+    account_keys: DenseSlotMap<AccountKey, ()>,
+    accounts: SecondaryMap<AccountKey, AccountExtra>,
+    transactions:
+        Vec<Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>>,
+    handlers: Vec<BookEventHandler>,
+    undo_stack:
+        Vec<Edit<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>>,
+    redo_stack:
+        Vec<Edit<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>>,
+    /// The locations of the moves of each account, in order.
+    ///
+    /// Built on demand and reset by each edit that relocates moves. Setting
+    /// a side of a move updates the locations of the two accounts instead.
+    move_locations: OnceLock<MoveLocations>,
+    /// Whether moves with empty sums are rejected.
+    ///
+    /// See [Book::set_reject_empty_sums].
+    reject_empty_sums: bool,
+    /// The units that sums are limited to, unless empty.
+    ///
+    /// See [Book::insert_unit].
+    units: BTreeSet<Unit>,
+    /// Hits and misses of the move locations cache.
+    #[cfg(any(test, feature = "testing"))]
+    cache_stats: CacheStats,
+}
+/// Counts of hits and misses of the move locations cache.
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Default)]
+struct CacheStats {
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+/// The locations of the moves of each account, along with its side.
+///
+/// Running totals are not cached instead, because balances are of a number
+/// type chosen per call. Locations let each calculation visit only the moves
+/// of the account and stay valid through changes of sums.
+type MoveLocations =
+    SecondaryMap<AccountKey, Vec<(TransactionIndex, MoveIndex, Side)>>;
+#[cfg(feature = "serde")]
+impl<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+    serde::Serialize
+    for Book<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord + serde::Serialize,
+    SumNumber: serde::Serialize,
+    AccountExtra: serde::Serialize,
+    TransactionExtra: serde::Serialize,
+    MoveExtra: serde::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("Book", 5)?;
+        state.serialize_field(
+            "account_keys",
+            &self.account_keys.keys().collect::<Vec<_>>(),
+        )?;
+        state.serialize_field(
+            "accounts",
+            &self.accounts.iter().collect::<Vec<_>>(),
+        )?;
+        state.serialize_field("transactions", &self.transactions)?;
+        state.serialize_field("units", &self.units)?;
+        state.serialize_field("reject_empty_sums", &self.reject_empty_sums)?;
+        state.end()
+    }
+}
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct BookData<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+    account_keys: Vec<AccountKey>,
+    accounts: Vec<(AccountKey, AccountExtra)>,
     transactions:
         Vec<Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>>,
+    #[serde(default = "BTreeSet::new")]
+    units: BTreeSet<Unit>,
+    #[serde(default)]
+    reject_empty_sums: bool,
+}
+#[cfg(feature = "serde")]
+impl<'de, Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+    serde::Deserialize<'de>
+    for Book<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord + serde::Deserialize<'de>,
+    SumNumber: serde::Deserialize<'de>,
+    AccountExtra: serde::Deserialize<'de>,
+    TransactionExtra: serde::Deserialize<'de>,
+    MoveExtra: serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+        let data = BookData::deserialize(deserializer)?;
+        let mut account_keys = DenseSlotMap::with_key();
+        for account_key in data.account_keys {
+            if account_keys.insert(()) != account_key {
+                return Err(D::Error::custom(format!(
+                    "account key {} is out of sequence",
+                    account_key,
+                )));
+            }
+        }
+        let mut accounts = SecondaryMap::new();
+        for (account_key, extra) in data.accounts {
+            if !account_keys.contains_key(account_key) {
+                return Err(D::Error::custom(format!(
+                    "account {} has no key",
+                    account_key,
+                )));
+            }
+            accounts.insert(account_key, extra);
+        }
+        let book = Self {
+            account_keys,
+            accounts,
+            transactions: data.transactions,
+            handlers: Vec::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            move_locations: OnceLock::new(),
+            reject_empty_sums: data.reject_empty_sums,
+            units: data.units,
+            #[cfg(any(test, feature = "testing"))]
+            cache_stats: CacheStats::default(),
+        };
+        book.validate()
+            .map_err(|issues| D::Error::custom(issues[0]))?;
+        Ok(book)
+    }
 }
-
 /// Used to index transactions in the book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TransactionIndex(pub usize);
+/// The error of getting a negative balance as a [Sum].
+///
+/// See [Book::account_sum_at_transaction].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegativeBalanceError<Unit>(Vec<Unit>);
+impl<Unit> NegativeBalanceError<Unit> {
+    /// Gets the units of negative amounts, in order.
+    pub fn units(&self) -> &[Unit] {
+        &self.0
+    }
+}
+impl<Unit> fmt::Display for NegativeBalanceError<Unit>
+where
+    Unit: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "balance is negative in units {:?}", self.0)
+    }
+}
+impl<Unit> Error for NegativeBalanceError<Unit> where Unit: fmt::Debug {}
+/// The error of a balance overflowing its number type.
+///
+/// See [Book::checked_account_balance_at_transaction].
+/// Requires the `num-traits` feature.
+#[cfg(feature = "num-traits")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceOverflowError<Unit> {
+    unit: Unit,
+    transaction_index: TransactionIndex,
+}
+#[cfg(feature = "num-traits")]
+impl<Unit> BalanceOverflowError<Unit> {
+    /// Gets the unit whose amount overflowed.
+    pub fn unit(&self) -> &Unit {
+        &self.unit
+    }
+    /// Gets the transaction at which the amount overflowed.
+    pub fn transaction_index(&self) -> TransactionIndex {
+        self.transaction_index
+    }
+}
+#[cfg(feature = "num-traits")]
+impl<Unit> fmt::Display for BalanceOverflowError<Unit>
+where
+    Unit: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "balance overflowed in unit {:?} at transaction {}",
+            self.unit, self.transaction_index.0,
+        )
+    }
+}
+#[cfg(feature = "num-traits")]
+impl<Unit> Error for BalanceOverflowError<Unit> where Unit: fmt::Debug {}
+/// The reason that a move cannot be inserted.
+///
+/// See [Book::try_insert_move].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertMoveError<Unit> {
+    /// The transaction index is out of bounds.
+    TransactionIndexOutOfBounds(TransactionIndex),
+    /// The move index is out of bounds in its transaction.
+    MoveIndexOutOfBounds(MoveIndex),
+    /// The debit account is not in the book.
+    DebitAccountNotFound(AccountKey),
+    /// The credit account is not in the book.
+    CreditAccountNotFound(AccountKey),
+    /// The debit and credit accounts are the same.
+    DebitEqualsCredit(AccountKey),
+    /// A unit of the sum is not in the book, while other units are.
+    UnitNotFound(Unit),
+    /// The sum is empty and empty sums are rejected.
+    EmptySum,
+}
+impl<Unit> fmt::Display for InsertMoveError<Unit>
+where
+    Unit: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertMoveError::TransactionIndexOutOfBounds(transaction_index) => {
+                write!(
+                    f,
+                    "transaction index (is {}) out of bounds",
+                    transaction_index.0,
+                )
+            }
+            InsertMoveError::MoveIndexOutOfBounds(move_index) => {
+                write!(f, "move index (is {}) out of bounds", move_index.0)
+            }
+            InsertMoveError::DebitAccountNotFound(account_key) => {
+                write!(f, "No debit account found for key {}", account_key)
+            }
+            InsertMoveError::CreditAccountNotFound(account_key) => {
+                write!(f, "No credit account found for key {}", account_key)
+            }
+            InsertMoveError::DebitEqualsCredit(account_key) => write!(
+                f,
+                "Debit and credit accounts are the same ({}).",
+                account_key,
+            ),
+            InsertMoveError::UnitNotFound(unit) => {
+                write!(f, "Unit {:?} is not in the book.", unit)
+            }
+            InsertMoveError::EmptySum => write!(f, "Empty sums are rejected."),
+        }
+    }
+}
+impl<Unit> Error for InsertMoveError<Unit> where Unit: fmt::Debug {}
+/// The error of removing an account that moves are on either side of.
+///
+/// See [Book::try_remove_account].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoveAccountError(Vec<(TransactionIndex, MoveIndex)>);
+impl RemoveAccountError {
+    /// Gets the locations of the moves that the account is on either side
+    /// of, in order.
+    pub fn moves(&self) -> &[(TransactionIndex, MoveIndex)] {
+        &self.0
+    }
+}
+impl fmt::Display for RemoveAccountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Account is referenced by {} moves", self.0.len())
+    }
+}
+impl Error for RemoveAccountError {}
+/// Iterates over the accounts of a book in order of creation.
+///
+/// Obtained in [Book::accounts].
+pub struct Accounts<'a, AccountExtra> {
+    pub(crate) iter: secondary::Iter<'a, AccountKey, AccountExtra>,
+}
+impl<'a, AccountExtra> Iterator for Accounts<'a, AccountExtra> {
+    type Item = (AccountKey, &'a AccountExtra);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+/// Iterates over the transactions of a book in their order.
+///
+/// Obtained in [Book::transactions].
+pub struct Transactions<'a, Unit, SumNumber, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+    pub(crate) iter: Enumerate<
+        slice::Iter<
+            'a,
+            Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>,
+        >,
+    >,
+}
+impl<'a, Unit, SumNumber, TransactionExtra, MoveExtra> Iterator
+    for Transactions<'a, Unit, SumNumber, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+    type Item = (
+        TransactionIndex,
+        &'a Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>,
+    );
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(index, transaction)| (TransactionIndex(index), transaction))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+impl<'a, Unit, SumNumber, TransactionExtra, MoveExtra> DoubleEndedIterator
+    for Transactions<'a, Unit, SumNumber, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next_back()
+            .map(|(index, transaction)| (TransactionIndex(index), transaction))
+    }
+}
+impl<'a, Unit, SumNumber, TransactionExtra, MoveExtra> ExactSizeIterator
+    for Transactions<'a, Unit, SumNumber, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+}
+/// Inserts moves into a transaction that is being built.
+///
+/// Obtained in [Book::with_transaction].
+pub struct TransactionBuilder<
+    'a,
+    Unit,
+    SumNumber,
+    AccountExtra,
+    TransactionExtra,
+    MoveExtra,
+> where
+    Unit: Ord,
+{
+    book: &'a mut Book<
+        Unit,
+        SumNumber,
+        AccountExtra,
+        TransactionExtra,
+        MoveExtra,
+    >,
+    transaction_index: usize,
+}
+impl<'a, Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+    TransactionBuilder<
+        'a,
+        Unit,
+        SumNumber,
+        AccountExtra,
+        TransactionExtra,
+        MoveExtra,
+    >
+where
+    Unit: Ord,
+{
+    /// Appends a move to the transaction.
+    ///
+    /// ## Panics
+    ///
+    /// Same as [Book::insert_move].
+    pub fn push_move(
+        &mut self,
+        debit_account_key: impl Borrow<AccountKey>,
+        credit_account_key: impl Borrow<AccountKey>,
+        sum: Sum<Unit, SumNumber>,
+        extra: MoveExtra,
+    ) -> &mut Self {
+        let move_index = MoveIndex(
+            self.book.transactions[self.transaction_index].moves.len(),
+        );
+        self.book.insert_move(
+            TransactionIndex(self.transaction_index),
+            move_index,
+            debit_account_key,
+            credit_account_key,
+            sum,
+            extra,
+        );
+        self
+    }
+}
+/// Changes several parts of a move at once.
+///
+/// Obtained in [Book::modify_move]. The changes are applied once the
+/// closure returns.
+pub struct MoveEditor<'a, Unit, SumNumber, MoveExtra>
+where
+    Unit: Ord,
+{
+    move_: &'a Move<Unit, SumNumber, MoveExtra>,
+    debit_account_key: Option<AccountKey>,
+    credit_account_key: Option<AccountKey>,
+    sum: Option<Sum<Unit, SumNumber>>,
+    extra: Option<MoveExtra>,
+}
+impl<'a, Unit, SumNumber, MoveExtra> MoveEditor<'a, Unit, SumNumber, MoveExtra>
+where
+    Unit: Ord,
+{
+    /// Gets the move as it is before the changes.
+    pub fn move_(&self) -> &'a Move<Unit, SumNumber, MoveExtra> {
+        self.move_
+    }
+    /// Sets the account for one of the sides of the move.
+    pub fn set_side(
+        &mut self,
+        side: Side,
+        account_key: impl Borrow<AccountKey>,
+    ) -> &mut Self {
+        let account_key = Some(*account_key.borrow());
+        match side {
+            Side::Debit => self.debit_account_key = account_key,
+            Side::Credit => self.credit_account_key = account_key,
+        }
+        self
+    }
+    /// Sets the sum of the move.
+    pub fn set_sum(&mut self, sum: Sum<Unit, SumNumber>) -> &mut Self {
+        self.sum = Some(sum);
+        self
+    }
+    /// Sets the extra data of the move.
+    pub fn set_extra(&mut self, extra: MoveExtra) -> &mut Self {
+        self.extra = Some(extra);
+        self
+    }
+}
+/// Clones the accounts and the transactions.
+///
+/// Handlers of changes and the history of changes, for [Book::undo], are
+/// not cloned.
+impl<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra> Clone
+    for Book<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord + Clone,
+    SumNumber: Clone,
+    AccountExtra: Clone,
+    TransactionExtra: Clone,
+    MoveExtra: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            account_keys: self.account_keys.clone(),
+            accounts: self.accounts.clone(),
+            transactions: self.transactions.clone(),
+            handlers: Vec::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            move_locations: self.move_locations.clone(),
+            reject_empty_sums: self.reject_empty_sums,
+            units: self.units.clone(),
+            #[cfg(any(test, feature = "testing"))]
+            cache_stats: CacheStats::default(),
+        }
+    }
+}
+/// Compares the accounts, the transactions and the units.
+impl<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra> PartialEq
+    for Book<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+    SumNumber: PartialEq,
+    AccountExtra: PartialEq,
+    TransactionExtra: PartialEq,
+    MoveExtra: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.accounts == other.accounts
+            && self.transactions == other.transactions
+            && self.units == other.units
+    }
+}
+/// Debugs the accounts, the transactions and the units.
+impl<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra> fmt::Debug
+    for Book<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord + fmt::Debug,
+    SumNumber: fmt::Debug,
+    AccountExtra: fmt::Debug,
+    TransactionExtra: fmt::Debug,
+    MoveExtra: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Book")
+            .field(
+                "accounts",
+                &self.accounts.iter().collect::<BTreeMap<_, _>>(),
+            )
+            .field("transactions", &self.transactions)
+            .field("units", &self.units)
+            .finish()
+    }
+}
 impl<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra> Default
     for Book<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
 where
@@ -29,8 +597,17 @@ where
 {
     fn default() -> Self {
         Self {
-            accounts: DenseSlotMap::with_key(),
+            account_keys: DenseSlotMap::with_key(),
+            accounts: SecondaryMap::new(),
             transactions: Vec::new(),
+            handlers: Vec::new(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            move_locations: OnceLock::new(),
+            reject_empty_sums: false,
+            units: BTreeSet::new(),
+            #[cfg(any(test, feature = "testing"))]
+            cache_stats: CacheStats::default(),
         }
     }
 }
@@ -41,7 +618,107 @@ where
 {
     /// Inserts an account.
     pub fn insert_account(&mut self, extra: AccountExtra) -> AccountKey {
-        self.accounts.insert(extra)
+        let account_key = self.account_keys.insert(());
+        self.edit(Edit::InsertAccount(account_key, extra));
+        account_key
+    }
+    /// Removes an account.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - Moves are on either side of the account.
+    pub fn remove_account(&mut self, account_key: AccountKey) {
+        if let Err(error) = self.try_remove_account(account_key) {
+            panic!("{}", error);
+        }
+    }
+    /// Removes an account, unless moves are on either side of it.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    pub fn try_remove_account(
+        &mut self,
+        account_key: AccountKey,
+    ) -> Result<(), RemoveAccountError> {
+        self.assert_has_account(account_key);
+        let moves: Vec<_> = self
+            .account_move_locations(account_key)
+            .iter()
+            .map(|(transaction_index, move_index, _)| {
+                (*transaction_index, *move_index)
+            })
+            .collect();
+        if !moves.is_empty() {
+            return Err(RemoveAccountError(moves));
+        }
+        self.edit(Edit::RemoveAccount(account_key));
+        Ok(())
+    }
+    /// Inserts several accounts.
+    ///
+    /// Returns their keys in the order of `extras`.
+    pub fn insert_accounts(
+        &mut self,
+        extras: impl IntoIterator<Item = AccountExtra>,
+    ) -> Vec<AccountKey> {
+        extras
+            .into_iter()
+            .map(|extra| self.insert_account(extra))
+            .collect()
+    }
+    /// Appends the accounts and the transactions of another book.
+    ///
+    /// The accounts are inserted with new keys and the moves of the
+    /// transactions are changed accordingly. The transactions are inserted
+    /// after the existing ones, in their order. All of this is a single
+    /// change for [Book::undo].
+    ///
+    /// Returns a map from the keys of the accounts in `other` to their new
+    /// keys. The units of `other` are not inserted.
+    ///
+    /// ## Panics
+    ///
+    /// - Some unit of the sums of `other` is not in the book, once any unit
+    ///   is. See [Book::insert_unit].
+    /// - Some sum of `other` is empty and empty sums are rejected. See
+    ///   [Book::set_reject_empty_sums].
+    pub fn append(&mut self, other: Self) -> HashMap<AccountKey, AccountKey> {
+        other
+            .transactions
+            .iter()
+            .flat_map(|transaction| transaction.moves.iter())
+            .for_each(|move_| self.assert_sum_accepted(&move_.sum));
+        let mut account_keys = HashMap::with_capacity(other.accounts.len());
+        let mut edits: Vec<_> = other
+            .accounts
+            .into_iter()
+            .map(|(other_key, extra)| {
+                let account_key = self.account_keys.insert(());
+                account_keys.insert(other_key, account_key);
+                Edit::InsertAccount(account_key, extra)
+            })
+            .collect();
+        let transaction_count = self.transactions.len();
+        edits.extend(other.transactions.into_iter().enumerate().map(
+            |(index, mut transaction)| {
+                transaction.moves.iter_mut().for_each(|move_| {
+                    move_.debit_account_key =
+                        account_keys[&move_.debit_account_key];
+                    move_.credit_account_key =
+                        account_keys[&move_.credit_account_key];
+                });
+                Edit::InsertTransaction(
+                    TransactionIndex(transaction_count + index),
+                    transaction,
+                )
+            },
+        ));
+        if !edits.is_empty() {
+            self.edit(Edit::Batch(edits));
+        }
+        account_keys
     }
     /// Creates a transaction and inserts it at an index.
     ///
@@ -55,13 +732,231 @@ where
     ) where
         Unit: Ord,
     {
-        self.transactions.insert(
-            transaction_index.0,
+        self.edit(Edit::InsertTransaction(
+            transaction_index,
             Transaction {
                 extra,
                 moves: Vec::new(),
             },
-        )
+        ));
+    }
+    /// Creates a transaction and inserts it where the transactions stay
+    /// sorted by `key`, after any of an equal key. Returns the index of the
+    /// transaction.
+    ///
+    /// The transactions are expected to already be sorted by `key`.
+    pub fn insert_transaction_sorted_by<K: Ord>(
+        &mut self,
+        extra: TransactionExtra,
+        key: impl Fn(&TransactionExtra) -> K,
+    ) -> TransactionIndex
+    where
+        Unit: Ord,
+    {
+        let extra_key = key(&extra);
+        let transaction_index =
+            TransactionIndex(self.transactions.partition_point(
+                |transaction| key(&transaction.extra) <= extra_key,
+            ));
+        self.insert_transaction(transaction_index, extra);
+        transaction_index
+    }
+    /// Appends a transaction, builds its moves and returns the book.
+    ///
+    /// ## Panics
+    ///
+    /// Same as [TransactionBuilder::push_move].
+    pub fn with_transaction(
+        mut self,
+        extra: TransactionExtra,
+        build: impl FnOnce(
+            &mut TransactionBuilder<
+                Unit,
+                SumNumber,
+                AccountExtra,
+                TransactionExtra,
+                MoveExtra,
+            >,
+        ),
+    ) -> Self {
+        let transaction_index = self.transactions.len();
+        self.insert_transaction(TransactionIndex(transaction_index), extra);
+        build(&mut TransactionBuilder {
+            book: &mut self,
+            transaction_index,
+        });
+        self
+    }
+    /// Creates a transaction with a move from one account to each of several
+    /// accounts and inserts it at an index.
+    ///
+    /// The moves are in the order of `allocations` and the extra data of
+    /// each is made from its position. Returns the index of the
+    /// transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    /// - Some of `from` and the accounts of `allocations` are not in the
+    ///   book.
+    /// - Some account of `allocations` is `from`.
+    /// - Some unit of the sums is not in the book, once any unit is. See
+    ///   [Book::insert_unit].
+    /// - Some sum is empty and empty sums are rejected. See
+    ///   [Book::set_reject_empty_sums].
+    pub fn transfer_fan_out(
+        &mut self,
+        transaction_index: TransactionIndex,
+        from: AccountKey,
+        allocations: Vec<(AccountKey, Sum<Unit, SumNumber>)>,
+        extra: TransactionExtra,
+        move_extra: impl Fn(usize) -> MoveExtra,
+    ) -> TransactionIndex {
+        self.assert_has_account(from);
+        allocations.iter().for_each(|(account_key, sum)| {
+            self.assert_has_account(*account_key);
+            self.assert_sum_accepted(sum);
+        });
+        assert!(
+            transaction_index.0 <= self.transactions.len(),
+            "insertion index (is {}) should be <= len (is {})",
+            transaction_index.0,
+            self.transactions.len(),
+        );
+        let moves = allocations
+            .into_iter()
+            .enumerate()
+            .map(|(index, (account_key, sum))| {
+                Move::new(from, account_key, sum, move_extra(index))
+            })
+            .collect();
+        self.edit(Edit::InsertTransaction(
+            transaction_index,
+            Transaction { extra, moves },
+        ));
+        transaction_index
+    }
+    /// Creates a transaction with moves and inserts it at an index.
+    ///
+    /// Each of `moves` is of a debit account, a credit account, a sum and
+    /// extra data. The moves are in the order of `moves`. Nothing is
+    /// inserted unless all moves are valid. Returns the index of the
+    /// transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    /// - Some account of `moves` is not in the book.
+    /// - Some move of `moves` is of equal debit and credit accounts.
+    /// - Some unit of the sums is not in the book, once any unit is. See
+    ///   [Book::insert_unit].
+    /// - Some sum is empty and empty sums are rejected. See
+    ///   [Book::set_reject_empty_sums].
+    pub fn insert_transaction_with_moves(
+        &mut self,
+        transaction_index: TransactionIndex,
+        extra: TransactionExtra,
+        moves: impl IntoIterator<
+            Item = (AccountKey, AccountKey, Sum<Unit, SumNumber>, MoveExtra),
+        >,
+    ) -> TransactionIndex {
+        assert!(
+            transaction_index.0 <= self.transactions.len(),
+            "insertion index (is {}) should be <= len (is {})",
+            transaction_index.0,
+            self.transactions.len(),
+        );
+        let moves = moves
+            .into_iter()
+            .map(|(debit_account_key, credit_account_key, sum, extra)| {
+                self.assert_has_account(debit_account_key);
+                self.assert_has_account(credit_account_key);
+                self.assert_sum_accepted(&sum);
+                Move::new(debit_account_key, credit_account_key, sum, extra)
+            })
+            .collect();
+        self.edit(Edit::InsertTransaction(
+            transaction_index,
+            Transaction { extra, moves },
+        ));
+        transaction_index
+    }
+    /// Inserts a copy of an existing transaction at an index.
+    ///
+    /// Returns the index of the copy.
+    ///
+    /// ## Panics
+    ///
+    /// - `source` out of bounds.
+    /// - `transaction_index` out of bounds.
+    /// - Some unit of the sums is not in the book, once any unit is. See
+    ///   [Book::insert_unit].
+    /// - Some sum is empty and empty sums are rejected. See
+    ///   [Book::set_reject_empty_sums].
+    pub fn clone_transaction(
+        &mut self,
+        source: TransactionIndex,
+        transaction_index: TransactionIndex,
+    ) -> TransactionIndex
+    where
+        Unit: Clone,
+        SumNumber: Clone,
+        TransactionExtra: Clone,
+        MoveExtra: Clone,
+    {
+        let copy = self.transactions[source.0].clone();
+        copy.moves
+            .iter()
+            .for_each(|move_| self.assert_sum_accepted(&move_.sum));
+        self.edit(Edit::InsertTransaction(transaction_index, copy));
+        transaction_index
+    }
+    /// Inserts a transaction that reverses an existing transaction at an
+    /// index.
+    ///
+    /// Each move of the reversing transaction has the sides of the
+    /// respective move of the existing transaction swapped, the same sum
+    /// and a copy of its extra data. Returns the index of the reversing
+    /// transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `source` out of bounds.
+    /// - `transaction_index` out of bounds.
+    /// - Some unit of the sums is not in the book, once any unit is. See
+    ///   [Book::insert_unit].
+    /// - Some sum is empty and empty sums are rejected. See
+    ///   [Book::set_reject_empty_sums].
+    pub fn reverse_transaction(
+        &mut self,
+        source: TransactionIndex,
+        transaction_index: TransactionIndex,
+        extra: TransactionExtra,
+    ) -> TransactionIndex
+    where
+        Unit: Clone,
+        SumNumber: Clone,
+        MoveExtra: Clone,
+    {
+        self.assert_has_transaction(&source);
+        let moves = self.transactions[source.0]
+            .moves
+            .iter()
+            .map(|move_| {
+                self.assert_sum_accepted(&move_.sum);
+                Move::new(
+                    move_.credit_account_key,
+                    move_.debit_account_key,
+                    move_.sum.clone(),
+                    move_.extra.clone(),
+                )
+            })
+            .collect();
+        self.edit(Edit::InsertTransaction(
+            transaction_index,
+            Transaction { extra, moves },
+        ));
+        transaction_index
     }
     /// Creates a new move and inserts it into a transaction at an index.
     ///
@@ -71,18 +966,24 @@ where
     /// - `move_index` out of bounds.
     /// - Some of `debit_account_key` and `credit_account_key` are not in the book.
     /// - `debit_account_key` and `credit_account_key` are equal.
-    /// - Some unit keys in the `sum` are not in the book.
+    /// - Some unit of `sum` is not in the book, once any unit is. See
+    ///   [Book::insert_unit].
+    /// - `sum` is empty and empty sums are rejected. See
+    ///   [Book::set_reject_empty_sums].
     pub fn insert_move(
         &mut self,
         transaction_index: TransactionIndex,
         move_index: MoveIndex,
-        debit_account_key: AccountKey,
-        credit_account_key: AccountKey,
+        debit_account_key: impl Borrow<AccountKey>,
+        credit_account_key: impl Borrow<AccountKey>,
         sum: Sum<Unit, SumNumber>,
         extra: MoveExtra,
     ) where
         Unit: Ord,
     {
+        self.assert_sum_accepted(&sum);
+        let debit_account_key = *debit_account_key.borrow();
+        let credit_account_key = *credit_account_key.borrow();
         [debit_account_key, credit_account_key].iter().for_each(
             |account_key| {
                 self.assert_has_account(*account_key);
@@ -90,40 +991,488 @@ where
         );
         let move_ =
             Move::new(debit_account_key, credit_account_key, sum, extra);
-        let transaction = std::ops::IndexMut::index_mut(
-            &mut self.transactions,
-            transaction_index.0,
-        );
-        transaction.moves.insert(move_index.0, move_);
+        self.edit(Edit::InsertMove(transaction_index, move_index, move_));
     }
-    /// Gets an account using a key.
-    ///
-    /// ## Panics
+    /// Creates a new move and inserts it into a transaction at an index, or
+    /// returns an error if it cannot be inserted.
     ///
-    /// - `account_key` is not in the book.
-    pub fn get_account(&self, account_key: AccountKey) -> &AccountExtra {
-        self.assert_has_account(account_key);
+    /// Same as [Book::insert_move], for input that cannot be trusted.
+    pub fn try_insert_move(
+        &mut self,
+        transaction_index: TransactionIndex,
+        move_index: MoveIndex,
+        debit_account_key: impl Borrow<AccountKey>,
+        credit_account_key: impl Borrow<AccountKey>,
+        sum: Sum<Unit, SumNumber>,
+        extra: MoveExtra,
+    ) -> Result<(), InsertMoveError<Unit>>
+    where
+        Unit: Clone,
+    {
+        let debit_account_key = *debit_account_key.borrow();
+        let credit_account_key = *credit_account_key.borrow();
+        let transaction = self.transactions.get(transaction_index.0).ok_or(
+            InsertMoveError::TransactionIndexOutOfBounds(transaction_index),
+        )?;
+        if move_index.0 > transaction.moves.len() {
+            return Err(InsertMoveError::MoveIndexOutOfBounds(move_index));
+        }
+        if !self.accounts.contains_key(debit_account_key) {
+            return Err(InsertMoveError::DebitAccountNotFound(
+                debit_account_key,
+            ));
+        }
+        if !self.accounts.contains_key(credit_account_key) {
+            return Err(InsertMoveError::CreditAccountNotFound(
+                credit_account_key,
+            ));
+        }
+        if debit_account_key == credit_account_key {
+            return Err(InsertMoveError::DebitEqualsCredit(debit_account_key));
+        }
+        if let Some(unit) = self.unit_not_found(&sum) {
+            return Err(InsertMoveError::UnitNotFound(unit.clone()));
+        }
+        if self.reject_empty_sums && sum.0.is_empty() {
+            return Err(InsertMoveError::EmptySum);
+        }
+        self.insert_move(
+            transaction_index,
+            move_index,
+            debit_account_key,
+            credit_account_key,
+            sum,
+            extra,
+        );
+        Ok(())
+    }
+    /// Creates a new move from a pair of sided accounts and inserts it into
+    /// a transaction at an index.
+    ///
+    /// The pair may be in any order.
+    ///
+    /// ## Panics
+    ///
+    /// - Both of `sides` are of the same [Side].
+    /// - Any of the reasons that [Book::insert_move] panics.
+    pub fn insert_move_sided(
+        &mut self,
+        transaction_index: TransactionIndex,
+        move_index: MoveIndex,
+        sides: [(Side, AccountKey); 2],
+        sum: Sum<Unit, SumNumber>,
+        extra: MoveExtra,
+    ) {
+        let (debit_account_key, credit_account_key) = match sides {
+            [(Side::Debit, debit), (Side::Credit, credit)]
+            | [(Side::Credit, credit), (Side::Debit, debit)] => (debit, credit),
+            _ => panic!("Both accounts are on the same side."),
+        };
+        self.insert_move(
+            transaction_index,
+            move_index,
+            debit_account_key,
+            credit_account_key,
+            sum,
+            extra,
+        );
+    }
+    /// Inserts a move that reverses an existing move into a transaction at
+    /// an index.
+    ///
+    /// The reversing move has the sides of the existing move swapped and
+    /// the same sum. Returns the index of the reversing move.
+    ///
+    /// ## Panics
+    ///
+    /// - `source_transaction_index` out of bounds.
+    /// - `source_move_index` out of bounds.
+    /// - `transaction_index` out of bounds.
+    /// - `move_index` out of bounds.
+    /// - Some unit of the sum is not in the book, once any unit is. See
+    ///   [Book::insert_unit].
+    /// - The sum is empty and empty sums are rejected. See
+    ///   [Book::set_reject_empty_sums].
+    pub fn reverse_move(
+        &mut self,
+        source_transaction_index: TransactionIndex,
+        source_move_index: MoveIndex,
+        transaction_index: TransactionIndex,
+        move_index: MoveIndex,
+        extra: MoveExtra,
+    ) -> MoveIndex
+    where
+        Unit: Clone,
+        SumNumber: Clone,
+    {
+        let source = &self.transactions[source_transaction_index.0].moves
+            [source_move_index.0];
+        self.assert_sum_accepted(&source.sum);
+        let reversal = Move::new(
+            source.credit_account_key,
+            source.debit_account_key,
+            source.sum.clone(),
+            extra,
+        );
+        self.edit(Edit::InsertMove(transaction_index, move_index, reversal));
+        move_index
+    }
+    /// Reserves capacity for at least `additional` more moves in a transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn reserve_moves(
+        &mut self,
+        transaction_index: TransactionIndex,
+        additional: usize,
+    ) {
+        self.transactions[transaction_index.0]
+            .moves
+            .reserve(additional);
+    }
+    /// Shrinks the capacity of the transactions and of the moves of each
+    /// transaction as much as possible.
+    ///
+    /// The capacity of the accounts is not shrunk.
+    pub fn shrink_to_fit(&mut self) {
+        self.transactions.shrink_to_fit();
+        self.transactions
+            .iter_mut()
+            .for_each(|transaction| transaction.moves.shrink_to_fit());
+    }
+    /// Creates a deterministic book of a given size, for tests and
+    /// benchmarks.
+    ///
+    /// All moves are of a single, default unit. The move at a running
+    /// position `n` is from account `n % accounts` to account
+    /// `(n + 1) % accounts` and its amount is `n % 100 + 1`. The book has
+    /// no undo history.
+    ///
+    /// ## Panics
+    ///
+    /// - `moves_per_transaction` is not zero and `accounts` is less than 2.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn synthetic(
+        accounts: usize,
+        transactions: usize,
+        moves_per_transaction: usize,
+    ) -> Self
+    where
+        Unit: Default,
+        SumNumber: From<u8>,
+        AccountExtra: Default,
+        TransactionExtra: Default,
+        MoveExtra: Default,
+    {
+        assert!(
+            moves_per_transaction == 0 || accounts >= 2,
+            "At least 2 accounts are required for moves."
+        );
+        let mut book = Self::default();
+        let account_keys = book
+            .insert_accounts((0..accounts).map(|_| AccountExtra::default()));
+        (0..transactions).for_each(|transaction_index| {
+            book.insert_transaction(
+                TransactionIndex(transaction_index),
+                TransactionExtra::default(),
+            );
+            (0..moves_per_transaction).for_each(|move_index| {
+                let n = transaction_index * moves_per_transaction + move_index;
+                let mut sum = Sum(BTreeMap::new());
+                sum.set_amount_for_unit(
+                    SumNumber::from((n % 100 + 1) as u8),
+                    Unit::default(),
+                );
+                book.insert_move(
+                    TransactionIndex(transaction_index),
+                    MoveIndex(move_index),
+                    account_keys[n % accounts],
+                    account_keys[(n + 1) % accounts],
+                    sum,
+                    MoveExtra::default(),
+                );
+            });
+        });
+        book.undo_stack.clear();
+        book
+    }
+    /// Gets the hits and misses of the cache of the locations of the moves
+    /// of each account, as `(hits, misses)`.
+    ///
+    /// Queries of the moves of an account hit the cache unless an edit
+    /// relocated moves or changed their accounts since the last query.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn cache_stats(&self) -> (usize, usize) {
+        (
+            self.cache_stats.hits.load(Ordering::Relaxed),
+            self.cache_stats.misses.load(Ordering::Relaxed),
+        )
+    }
+    /// Gets an account using a key.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    pub fn get_account(&self, account_key: AccountKey) -> &AccountExtra {
+        self.assert_has_account(account_key);
         self.accounts.get(account_key).unwrap()
     }
+    /// Gets an account using a key, if it is in the book.
+    pub fn try_get_account(
+        &self,
+        account_key: AccountKey,
+    ) -> Option<&AccountExtra> {
+        self.accounts.get(account_key)
+    }
+    /// Gets a transaction, if `transaction_index` is in bounds.
+    pub fn get_transaction(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> Option<&Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>>
+    {
+        self.transactions.get(transaction_index.0)
+    }
+    /// Gets a move, if `transaction_index` and `move_index` are in bounds.
+    pub fn get_move(
+        &self,
+        transaction_index: TransactionIndex,
+        move_index: MoveIndex,
+    ) -> Option<&Move<Unit, SumNumber, MoveExtra>> {
+        self.get_transaction(transaction_index)?
+            .moves
+            .get(move_index.0)
+    }
+    /// Calls a closure with an account and returns its result.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    pub fn map_account<R>(
+        &self,
+        account_key: AccountKey,
+        f: impl FnOnce(&AccountExtra) -> R,
+    ) -> R {
+        f(self.get_account(account_key))
+    }
     /// Gets an iterator of existing accounts in order of creation.
-    pub fn accounts(
+    pub fn accounts(&self) -> Accounts<'_, AccountExtra> {
+        Accounts {
+            iter: self.accounts.iter(),
+        }
+    }
+    /// Gets the number of existing accounts.
+    pub fn account_count(&self) -> usize {
+        self.accounts.len()
+    }
+    /// Gets an iterator of existing accounts in order of creation, along with
+    /// the zero-based position of each.
+    pub fn accounts_enumerated(
         &self,
-    ) -> impl Iterator<Item = (AccountKey, &AccountExtra)> {
-        self.accounts.iter()
+    ) -> impl Iterator<Item = (usize, AccountKey, &AccountExtra)> {
+        self.accounts()
+            .enumerate()
+            .map(|(ordinal, (account_key, extra))| {
+                (ordinal, account_key, extra)
+            })
+    }
+    /// Gets the key of the account at a zero-based position in order of
+    /// creation, among existing accounts.
+    pub fn nth_account_key(&self, n: usize) -> Option<AccountKey> {
+        self.accounts().nth(n).map(|(account_key, _)| account_key)
+    }
+    /// Gets the zero-based position of an account in order of creation,
+    /// among existing accounts.
+    pub fn account_ordinal(&self, account_key: AccountKey) -> Option<usize> {
+        self.accounts()
+            .position(|(existing_key, _)| existing_key == account_key)
     }
     /// Gets an iterator of existing transactions in their order.
     pub fn transactions(
         &self,
+    ) -> Transactions<'_, Unit, SumNumber, TransactionExtra, MoveExtra> {
+        Transactions {
+            iter: self.transactions.iter().enumerate(),
+        }
+    }
+    /// Gets the number of transactions.
+    pub fn transaction_count(&self) -> usize {
+        self.transactions.len()
+    }
+    /// Copies the accounts and transactions into a snapshot, for read-only
+    /// queries from other threads.
+    pub fn snapshot(
+        &self,
+    ) -> Arc<
+        BookSnapshot<
+            Unit,
+            SumNumber,
+            AccountExtra,
+            TransactionExtra,
+            MoveExtra,
+        >,
+    >
+    where
+        Unit: Clone,
+        SumNumber: Clone,
+        AccountExtra: Clone,
+        TransactionExtra: Clone,
+        MoveExtra: Clone,
+    {
+        Arc::new(BookSnapshot {
+            accounts: self.accounts.clone(),
+            transactions: self.transactions.clone(),
+        })
+    }
+    /// Gets an iterator of the transactions whose extra data matches a
+    /// predicate, in their order.
+    ///
+    /// This allows filtering by data that is stored in the extra data, such
+    /// as a date. The indices are those of the transactions in the book, so
+    /// they can be passed to methods such as [Book::remove_transaction] for
+    /// as long as the book is not changed.
+    pub fn transactions_where<'a, F>(
+        &'a self,
+        predicate: F,
     ) -> impl Iterator<
         Item = (
             TransactionIndex,
-            &Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>,
+            &'a Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>,
+        ),
+    > + 'a
+    where
+        F: Fn(&TransactionExtra) -> bool + 'a,
+    {
+        self.transactions()
+            .filter(move |(_, transaction)| predicate(&transaction.extra))
+    }
+    /// Gets the index of the transaction with the least key of its extra
+    /// data, or `None` if there are no transactions.
+    ///
+    /// Of several transactions with the least key, the first is chosen.
+    pub fn min_transaction_by<K, F>(&self, key: F) -> Option<TransactionIndex>
+    where
+        K: Ord,
+        F: Fn(&TransactionExtra) -> K,
+    {
+        self.transactions()
+            .min_by_key(|(_, transaction)| key(&transaction.extra))
+            .map(|(transaction_index, _)| transaction_index)
+    }
+    /// Gets the index of the transaction with the greatest key of its extra
+    /// data, or `None` if there are no transactions.
+    ///
+    /// Of several transactions with the greatest key, the last is chosen.
+    pub fn max_transaction_by<K, F>(&self, key: F) -> Option<TransactionIndex>
+    where
+        K: Ord,
+        F: Fn(&TransactionExtra) -> K,
+    {
+        self.transactions()
+            .max_by_key(|(_, transaction)| key(&transaction.extra))
+            .map(|(transaction_index, _)| transaction_index)
+    }
+    /// Gets the indices of the transactions, in order, grouped by a key of
+    /// their extra data.
+    ///
+    /// This allows rollups by data that is stored in the extra data, such
+    /// as the month of a date.
+    pub fn group_transactions_by<K, F>(
+        &self,
+        key: F,
+    ) -> BTreeMap<K, Vec<TransactionIndex>>
+    where
+        K: Ord,
+        F: Fn(&TransactionExtra) -> K,
+    {
+        let mut groups = BTreeMap::new();
+        self.transactions()
+            .for_each(|(transaction_index, transaction)| {
+                groups
+                    .entry(key(&transaction.extra))
+                    .or_insert_with(Vec::new)
+                    .push(transaction_index);
+            });
+        groups
+    }
+    /// Gets an iterator of all of the moves, in order, along with their
+    /// indices.
+    pub fn moves(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            TransactionIndex,
+            MoveIndex,
+            &Move<Unit, SumNumber, MoveExtra>,
         ),
     > {
-        self.transactions
-            .iter()
-            .enumerate()
-            .map(|(index, transaction)| (TransactionIndex(index), transaction))
+        self.transactions()
+            .flat_map(|(transaction_index, transaction)| {
+                transaction.moves().map(move |(move_index, move_)| {
+                    (transaction_index, move_index, move_)
+                })
+            })
+    }
+    /// Gets an iterator of the moves that an account is on either side of,
+    /// in order, along with their indices and the side of the account.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    #[allow(clippy::type_complexity)]
+    pub fn account_moves(
+        &self,
+        account_key: AccountKey,
+    ) -> impl Iterator<
+        Item = (
+            TransactionIndex,
+            MoveIndex,
+            Side,
+            &Move<Unit, SumNumber, MoveExtra>,
+        ),
+    > {
+        self.assert_has_account(account_key);
+        self.account_move_locations(account_key).iter().map(
+            move |(transaction_index, move_index, side)| {
+                (
+                    *transaction_index,
+                    *move_index,
+                    *side,
+                    &self.transactions[transaction_index.0].moves[move_index.0],
+                )
+            },
+        )
+    }
+    /// Gets an iterator of the moves that have an amount of a unit, in
+    /// order, along with that amount.
+    #[allow(clippy::type_complexity)]
+    pub fn moves_for_unit<'a>(
+        &'a self,
+        unit: &'a Unit,
+    ) -> impl Iterator<
+        Item = (
+            TransactionIndex,
+            MoveIndex,
+            &'a Move<Unit, SumNumber, MoveExtra>,
+            &'a SumNumber,
+        ),
+    > + 'a {
+        self.transactions.iter().enumerate().flat_map(
+            move |(transaction_index, transaction)| {
+                transaction.moves.iter().enumerate().filter_map(
+                    move |(move_index, move_)| {
+                        move_.sum.unit_amount(unit).map(|amount| {
+                            (
+                                TransactionIndex(transaction_index),
+                                MoveIndex(move_index),
+                                move_,
+                                amount,
+                            )
+                        })
+                    },
+                )
+            },
+        )
     }
     /// Sets an existing account.
     ///
@@ -135,7 +1484,7 @@ where
         extra: AccountExtra,
     ) {
         self.assert_has_account(account_key);
-        *self.accounts.get_mut(account_key).unwrap() = extra;
+        self.edit(Edit::SetAccount(account_key, extra));
     }
     /// Sets extra data for a transaction.
     ///
@@ -146,10 +1495,7 @@ where
         transaction_index: TransactionIndex,
         extra: TransactionExtra,
     ) {
-        self.transactions
-            .get_mut(transaction_index.0)
-            .unwrap()
-            .extra = extra;
+        self.edit(Edit::SetTransactionExtra(transaction_index, extra));
     }
     /// Sets extra data for a move.
     ///
@@ -164,724 +1510,8292 @@ where
     ) where
         Unit: Ord,
     {
-        let transaction = std::ops::IndexMut::index_mut(
-            &mut self.transactions,
-            transaction_index.0,
-        );
-        let move_ = &mut transaction.moves[move_index.0];
-        move_.extra = extra;
+        self.edit(Edit::SetMoveExtra(transaction_index, move_index, extra));
     }
-    /// Calculates the balance of an account at a provided transaction.
+    /// Gets mutable access to the extra data of a move.
     ///
-    /// Providing an out of bounds `transaction_index` is undefined behavior.
+    /// The change is recorded for [Book::undo] and handlers are notified
+    /// of it once access is given, before the extra data is changed through
+    /// it. So the previous extra data is cloned and access is recorded as a
+    /// change even if the extra data is left as it was. For a change that is
+    /// recorded after it is made, see [Book::modify_move].
     ///
     /// ## Panics
-    ///
-    /// - `account_key` is not in the book.
-    #[allow(clippy::type_complexity)]
-    pub fn account_balance_at_transaction<'a, BalanceNumber>(
-        &'a self,
-        account_key: AccountKey,
+    /// - `transaction_index` out of bounds.
+    /// - `move_index` out of bounds.
+    pub fn move_extra_mut(
+        &mut self,
         transaction_index: TransactionIndex,
-    ) -> Balance<Unit, BalanceNumber>
+        move_index: MoveIndex,
+    ) -> &mut MoveExtra
     where
-        Unit: Ord + Clone,
-        BalanceNumber: Default
-            + Sub<Output = BalanceNumber>
-            + Add<Output = BalanceNumber>
-            + Clone,
-        SumNumber: Clone + Into<BalanceNumber>,
+        MoveExtra: Clone,
     {
-        self.assert_has_account(account_key);
-        self.transactions
-            .iter()
-            .take(transaction_index.0 + 1)
-            .flat_map(|transaction| transaction.moves.iter())
-            .filter_map(
-                |move_| -> Option<(
-                    fn(
-                        &mut Balance<Unit, BalanceNumber>,
-                        &'a Sum<Unit, SumNumber>,
-                    ),
-                    &Sum<Unit, SumNumber>,
-                )> {
-                    if move_.debit_account_key == account_key {
-                        Some((SubAssign::sub_assign, &move_.sum))
-                    } else if move_.credit_account_key == account_key {
-                        Some((AddAssign::add_assign, &move_.sum))
-                    } else {
-                        None
-                    }
-                },
-            )
-            .fold(
-                <Balance<Unit, BalanceNumber> as Default>::default(),
-                |mut balance, (operation, sum)| {
-                    operation(&mut balance, sum);
-                    balance
-                },
-            )
+        self.assert_has_transaction(&transaction_index);
+        let previous = self.transactions[transaction_index.0].moves
+            [move_index.0]
+            .extra
+            .clone();
+        self.record(Edit::SetMoveExtra(
+            transaction_index,
+            move_index,
+            previous,
+        ));
+        self.emit(BookEvent::MoveExtraSet(transaction_index, move_index));
+        &mut self.transactions[transaction_index.0].moves[move_index.0].extra
     }
-    /// Removes an existing transaction from the book.
-    ///
-    /// ## Panics
+    /// Updates the extra data of each move of a transaction.
     ///
-    /// - `transaction_index` out of bounds.
-    pub fn remove_transaction(&mut self, transaction_index: TransactionIndex) {
-        self.transactions.remove(transaction_index.0);
-    }
-    /// Removes an existing move from the book.
+    /// `f` is called with each move's index and a clone of its extra data.
+    /// All of the updates are recorded as a single step for [Book::undo].
     ///
     /// ## Panics
-    ///
     /// - `transaction_index` out of bounds.
-    /// - `move_index` out of bounds.
-    pub fn remove_move(
+    pub fn update_moves<F>(
         &mut self,
         transaction_index: TransactionIndex,
-        move_index: MoveIndex,
+        mut f: F,
     ) where
-        Unit: Ord,
+        F: FnMut(MoveIndex, &mut MoveExtra),
+        MoveExtra: Clone,
     {
-        self.transactions[transaction_index.0]
+        self.assert_has_transaction(&transaction_index);
+        let edits: Vec<_> = self.transactions[transaction_index.0]
             .moves
-            .remove(move_index.0);
+            .iter()
+            .enumerate()
+            .map(|(move_index, move_)| {
+                let mut extra = move_.extra.clone();
+                f(MoveIndex(move_index), &mut extra);
+                Edit::SetMoveExtra(
+                    transaction_index,
+                    MoveIndex(move_index),
+                    extra,
+                )
+            })
+            .collect();
+        if !edits.is_empty() {
+            self.edit(Edit::Batch(edits));
+        }
     }
-    /// Sets the sum of an existing move.
+    /// Changes several parts of an existing move with a single lookup.
+    ///
+    /// `f` is called with a [MoveEditor] of the move and its result is
+    /// returned. The changes are recorded as a single step for
+    /// [Book::undo].
     ///
     /// ## Panics
     ///
     /// - `transaction_index` out of bounds.
     /// - `move_index` out of bounds.
-    pub fn set_move_sum(
+    /// - Some set account is not in the book.
+    /// - The debit and credit accounts end up equal.
+    /// - Same as [Book::set_move_sum], for a set sum.
+    pub fn modify_move<R>(
         &mut self,
         transaction_index: TransactionIndex,
         move_index: MoveIndex,
-        sum: Sum<Unit, SumNumber>,
-    ) where
-        Unit: Ord,
-    {
-        self.transactions[transaction_index.0].moves[move_index.0].sum = sum;
+        f: impl FnOnce(&mut MoveEditor<'_, Unit, SumNumber, MoveExtra>) -> R,
+    ) -> R {
+        self.assert_has_transaction(&transaction_index);
+        let mut editor = MoveEditor {
+            move_: &self.transactions[transaction_index.0].moves[move_index.0],
+            debit_account_key: None,
+            credit_account_key: None,
+            sum: None,
+            extra: None,
+        };
+        let result = f(&mut editor);
+        let MoveEditor {
+            move_,
+            debit_account_key,
+            credit_account_key,
+            sum,
+            extra,
+        } = editor;
+        assert!(
+            debit_account_key.unwrap_or(move_.debit_account_key)
+                != credit_account_key.unwrap_or(move_.credit_account_key),
+            "Debit and credit accounts are the same."
+        );
+        let mut edits = Vec::new();
+        [
+            (Side::Debit, debit_account_key),
+            (Side::Credit, credit_account_key),
+        ]
+        .iter()
+        .filter_map(|(side, account_key)| Some((*side, (*account_key)?)))
+        .for_each(|(side, account_key)| {
+            self.assert_has_account(account_key);
+            edits.push(Edit::SetMoveSide(
+                transaction_index,
+                move_index,
+                side,
+                account_key,
+            ));
+        });
+        if let Some(sum) = sum {
+            self.assert_sum_accepted(&sum);
+            edits.push(Edit::SetMoveSum(transaction_index, move_index, sum));
+        }
+        if let Some(extra) = extra {
+            edits.push(Edit::SetMoveExtra(
+                transaction_index,
+                move_index,
+                extra,
+            ));
+        }
+        if !edits.is_empty() {
+            self.edit(Edit::Batch(edits));
+        }
+        result
     }
-    /// Sets the account for one of the sides of an existing move.
+    /// Calculates the balance of an account at a provided transaction.
+    ///
+    /// The provided transaction is included.
+    /// Same as [Book::account_balance_after].
+    ///
+    /// The amount of each unit starts from `BalanceNumber::default()`,
+    /// which is assumed to be zero. For number types whose default is not
+    /// zero, see [Book::account_balance_seeded].
     ///
     /// ## Panics
     ///
-    /// - `transaction_index` out of bounds.
-    /// - `move_index` out of bounds.
     /// - `account_key` is not in the book.
-    /// - `side` is same as other side.
-    pub fn set_move_side(
-        &mut self,
+    /// - `transaction_index` out of bounds. Therefore, this always panics
+    ///   for a book with no transactions.
+    pub fn account_balance_at_transaction<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
         transaction_index: TransactionIndex,
-        move_index: MoveIndex,
-        side: Side,
-        account_key: AccountKey,
-    ) where
-        Unit: Ord,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
     {
-        self.assert_has_account(account_key);
-        let move_ =
-            &mut self.transactions[transaction_index.0].moves[move_index.0];
-        match side {
-            Side::Debit => {
-                assert_ne!(account_key, move_.credit_account_key, "Provided debit account is same as existing credit account.");
-                move_.debit_account_key = account_key;
-            }
-            Side::Credit => {
-                assert_ne!(account_key, move_.debit_account_key, "Provided credit account is same as existing debit account.");
-                move_.credit_account_key = account_key;
+        self.account_balance_after(account_key, transaction_index)
+    }
+    /// Calculates the balance of an account at a provided transaction, or
+    /// returns an error if the amount of some unit overflows.
+    ///
+    /// The provided transaction is included.
+    /// Requires the `num-traits` feature.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds. Therefore, this always panics
+    ///   for a book with no transactions.
+    #[cfg(feature = "num-traits")]
+    pub fn checked_account_balance_at_transaction<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> Result<Balance<Unit, BalanceNumber>, BalanceOverflowError<Unit>>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber:
+            num_traits::CheckedAdd + num_traits::CheckedSub + Default + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        let mut balance = Balance::default();
+        balance
+            .try_apply_account_moves(
+                self.account_moves_in(account_key, 0..transaction_index.0 + 1),
+                BalanceNumber::default,
+                |balance, amount| balance.checked_sub(&amount),
+                |balance, amount| balance.checked_add(&amount),
+            )
+            .map_err(|(unit, transaction_index)| BalanceOverflowError {
+                unit,
+                transaction_index,
+            })?;
+        Ok(balance)
+    }
+    /// Calculates the balance of an account at a provided transaction, along
+    /// with the number of moves that contributed to it.
+    ///
+    /// The provided transaction is included.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds. Therefore, this always panics
+    ///   for a book with no transactions.
+    pub fn account_balance_at_transaction_counted<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> (Balance<Unit, BalanceNumber>, usize)
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        let mut balance = Balance::default();
+        let count = self.accumulate_account_balance_of_first_transactions(
+            account_key,
+            transaction_index.0 + 1,
+            &mut balance,
+        );
+        (balance, count)
+    }
+    /// Calculates the balance of an account at a provided transaction, in
+    /// the number type of the sums.
+    ///
+    /// Same as [Book::account_balance_at_transaction] without having to
+    /// specify the number type.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds. Therefore, this always panics
+    ///   for a book with no transactions.
+    pub fn balance(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> Balance<Unit, SumNumber>
+    where
+        Unit: Ord + Clone,
+        SumNumber:
+            Default + Sub<Output = SumNumber> + Add<Output = SumNumber> + Clone,
+    {
+        self.account_balance_at_transaction(account_key, transaction_index)
+    }
+    /// Calculates the balance of an account after a provided transaction.
+    ///
+    /// The provided transaction is included.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds. Therefore, this always panics
+    ///   for a book with no transactions.
+    pub fn account_balance_after<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        self.account_balance_of_first_transactions(
+            account_key,
+            transaction_index.0 + 1,
+        )
+    }
+    /// Calculates the balance of an account before a provided transaction.
+    ///
+    /// The provided transaction is excluded.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds. Therefore, this always panics
+    ///   for a book with no transactions.
+    pub fn account_balance_before<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        self.account_balance_of_first_transactions(
+            account_key,
+            transaction_index.0,
+        )
+    }
+    /// Calculates the change in the balance of an account over a range of
+    /// transactions.
+    ///
+    /// Both `from` and `to` are included.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `from` out of bounds.
+    /// - `to` out of bounds.
+    /// - `from` is after `to`.
+    pub fn account_balance_between<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        from: TransactionIndex,
+        to: TransactionIndex,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&from);
+        self.assert_has_transaction(&to);
+        assert!(
+            from.0 <= to.0,
+            "from (is {}) should be <= to (is {})",
+            from.0,
+            to.0,
+        );
+        let mut balance = Balance::default();
+        balance.apply_account_moves(
+            self.account_moves_in(account_key, from.0..to.0 + 1),
+            BalanceNumber::default,
+        );
+        balance
+    }
+    /// Calculates the balances of an account at several transactions.
+    ///
+    /// Walks the transactions once, so it is cheaper than a call to
+    /// [Book::account_balance_at_transaction] per transaction.
+    /// The balances are in the order of `transaction_indices`.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - Some of `transaction_indices` out of bounds.
+    /// - `transaction_indices` not sorted.
+    pub fn account_balance_samples<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_indices: &[TransactionIndex],
+    ) -> Vec<Balance<Unit, BalanceNumber>>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        transaction_indices.iter().for_each(|transaction_index| {
+            self.assert_has_transaction(transaction_index)
+        });
+        assert!(
+            transaction_indices
+                .windows(2)
+                .all(|pair| pair[0].0 <= pair[1].0),
+            "Transaction indices are not sorted."
+        );
+        let mut balance = Balance::default();
+        let mut applied_count = 0;
+        transaction_indices
+            .iter()
+            .map(|transaction_index| {
+                self.transactions[applied_count..=transaction_index.0]
+                    .iter()
+                    .for_each(|transaction| {
+                        balance.apply_transaction(transaction, account_key)
+                    });
+                applied_count = transaction_index.0 + 1;
+                balance.clone()
+            })
+            .collect()
+    }
+    /// Calculates the balance of an account at a provided transaction into
+    /// a provided balance.
+    ///
+    /// The balance of the account is added to `balance`, which allows
+    /// reusing a balance's allocation. To obtain the balance of the account
+    /// alone, provide a cleared balance.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds.
+    pub fn account_balance_at_transaction_into<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+        balance: &mut Balance<Unit, BalanceNumber>,
+    ) where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        self.accumulate_account_balance_of_first_transactions(
+            account_key,
+            transaction_index.0 + 1,
+            balance,
+        );
+    }
+    /// Calculates the balance of an account at a provided transaction,
+    /// starting the amount of each unit from a provided zero.
+    ///
+    /// The provided transaction is included. Unlike
+    /// [Book::account_balance_at_transaction], this does not rely on
+    /// `BalanceNumber::default()` being zero.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds.
+    pub fn account_balance_seeded<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+        zero: BalanceNumber,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber:
+            Sub<Output = BalanceNumber> + Add<Output = BalanceNumber> + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        let mut balance = Balance(BTreeMap::new());
+        balance.apply_account_moves(
+            self.account_moves_in(account_key, 0..transaction_index.0 + 1),
+            || zero.clone(),
+        );
+        balance
+    }
+    /// Calculates the amount of a single unit in the balance of an account
+    /// at a provided transaction.
+    ///
+    /// The provided transaction is included.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds.
+    pub fn account_unit_balance_at_transaction<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        unit: &Unit,
+        transaction_index: TransactionIndex,
+    ) -> BalanceNumber
+    where
+        BalanceNumber:
+            Default + Sub<Output = BalanceNumber> + Add<Output = BalanceNumber>,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        self.transactions
+            .iter()
+            .take(transaction_index.0 + 1)
+            .flat_map(|transaction| transaction.moves.iter())
+            .fold(BalanceNumber::default(), |balance, move_| {
+                match (
+                    move_.account_side(account_key),
+                    move_.sum.unit_amount(unit),
+                ) {
+                    (Some(Side::Debit), Some(amount)) => {
+                        balance - amount.clone().into()
+                    }
+                    (Some(Side::Credit), Some(amount)) => {
+                        balance + amount.clone().into()
+                    }
+                    _ => balance,
+                }
+            })
+    }
+    /// Calculates the amounts of several units in the balance of an account
+    /// at a provided transaction, in the order of `units`.
+    ///
+    /// The provided transaction is included.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds.
+    pub fn account_unit_balances_at_transaction<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        units: &[Unit],
+        transaction_index: TransactionIndex,
+    ) -> Vec<BalanceNumber>
+    where
+        BalanceNumber:
+            Default + Sub<Output = BalanceNumber> + Add<Output = BalanceNumber>,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        let mut balances: Vec<BalanceNumber> =
+            units.iter().map(|_| BalanceNumber::default()).collect();
+        self.transactions
+            .iter()
+            .take(transaction_index.0 + 1)
+            .flat_map(|transaction| transaction.moves.iter())
+            .for_each(|move_| {
+                let side = match move_.account_side(account_key) {
+                    Some(side) => side,
+                    None => return,
+                };
+                units.iter().zip(balances.iter_mut()).for_each(
+                    |(unit, balance)| {
+                        if let Some(amount) = move_.sum.unit_amount(unit) {
+                            let previous = std::mem::take(balance);
+                            *balance = match side {
+                                Side::Debit => previous - amount.clone().into(),
+                                Side::Credit => {
+                                    previous + amount.clone().into()
+                                }
+                            };
+                        }
+                    },
+                );
+            });
+        balances
+    }
+    /// Gets all accounts, sorted by the amount of a single unit in their
+    /// balances at a provided transaction, largest first.
+    ///
+    /// The provided transaction is included. Accounts of equal amounts are
+    /// in order of creation.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn accounts_sorted_by_unit_balance<BalanceNumber>(
+        &self,
+        unit: &Unit,
+        transaction_index: TransactionIndex,
+    ) -> Vec<(AccountKey, BalanceNumber)>
+    where
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Ord,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        self.assert_has_transaction(&transaction_index);
+        let mut balances: HashMap<AccountKey, BalanceNumber> = self
+            .accounts
+            .keys()
+            .map(|account_key| (account_key, BalanceNumber::default()))
+            .collect();
+        self.transactions
+            .iter()
+            .take(transaction_index.0 + 1)
+            .flat_map(|transaction| transaction.moves.iter())
+            .for_each(|move_| {
+                if let Some(amount) = move_.sum.unit_amount(unit) {
+                    let debit_balance =
+                        balances.get_mut(&move_.debit_account_key).unwrap();
+                    *debit_balance =
+                        std::mem::take(debit_balance) - amount.clone().into();
+                    let credit_balance =
+                        balances.get_mut(&move_.credit_account_key).unwrap();
+                    *credit_balance =
+                        std::mem::take(credit_balance) + amount.clone().into();
+                }
+            });
+        let mut sorted: Vec<_> = self
+            .accounts
+            .keys()
+            .map(|account_key| {
+                (account_key, balances.remove(&account_key).unwrap())
+            })
+            .collect();
+        sorted.sort_by(|(_, a), (_, b)| b.cmp(a));
+        sorted
+    }
+    /// Calculates the turnover of an account at a provided transaction.
+    ///
+    /// Returns the total of the sums debited from the account and the total
+    /// of the sums credited to the account, in that order. The balance of
+    /// the account is the credited total minus the debited total.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds.
+    pub fn account_turnover<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> (Balance<Unit, BalanceNumber>, Balance<Unit, BalanceNumber>)
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        let mut debits = Balance::default();
+        let mut credits = Balance::default();
+        self.transactions
+            .iter()
+            .take(transaction_index.0 + 1)
+            .flat_map(|transaction| transaction.moves.iter())
+            .for_each(|move_| {
+                if move_.debit_account_key == account_key {
+                    debits += &move_.sum;
+                } else if move_.credit_account_key == account_key {
+                    credits += &move_.sum;
+                }
+            });
+        (debits, credits)
+    }
+    /// Calculates the balance of an account at a provided transaction as a
+    /// [Sum], in the number type of the sums.
+    ///
+    /// The provided transaction is included. Returns an error with the
+    /// units of negative amounts, if any.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds.
+    pub fn account_sum_at_transaction(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> Result<Sum<Unit, SumNumber>, NegativeBalanceError<Unit>>
+    where
+        Unit: Ord + Clone,
+        SumNumber: Default
+            + Sub<Output = SumNumber>
+            + Add<Output = SumNumber>
+            + PartialOrd
+            + Clone,
+    {
+        let (debits, credits) =
+            self.account_turnover::<SumNumber>(account_key, transaction_index);
+        let mut sum = Sum(BTreeMap::new());
+        let mut negative = Vec::new();
+        let units: BTreeSet<&Unit> =
+            debits.0.keys().chain(credits.0.keys()).collect();
+        units.into_iter().for_each(|unit| {
+            let debited = debits.get_or_default(unit);
+            let credited = credits.get_or_default(unit);
+            if credited >= debited {
+                sum.set_amount_for_unit(credited - debited, unit.clone());
+            } else {
+                negative.push(unit.clone());
             }
+        });
+        if negative.is_empty() {
+            Ok(sum)
+        } else {
+            Err(NegativeBalanceError(negative))
         }
     }
-    fn assert_has_account(&self, key: AccountKey) {
-        assert!(
-            self.accounts.contains_key(key),
-            format!("No account found for key {:?}", key),
+    /// Calculates the flow from some accounts to other accounts at a
+    /// provided transaction.
+    ///
+    /// Sums the moves that debit an account in `from` and credit an account
+    /// in `to`, up to and including the provided transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn flow_between<BalanceNumber>(
+        &self,
+        from: &HashSet<AccountKey>,
+        to: &HashSet<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default + Add<Output = BalanceNumber> + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        self.assert_has_transaction(&transaction_index);
+        let mut flow = Balance::default();
+        self.transactions
+            .iter()
+            .take(transaction_index.0 + 1)
+            .flat_map(|transaction| transaction.moves.iter())
+            .filter(|move_| {
+                from.contains(&move_.debit_account_key)
+                    && to.contains(&move_.credit_account_key)
+            })
+            .for_each(|move_| flow += &move_.sum);
+        flow
+    }
+    /// Calculates the balances of all accounts at a transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn balances_at_transaction<BalanceNumber>(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> HashMap<AccountKey, Balance<Unit, BalanceNumber>>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        self.assert_has_transaction(&transaction_index);
+        let mut balances = self.zero_balances();
+        self.transactions[..=transaction_index.0].iter().for_each(
+            |transaction| Self::apply_to_balances(&mut balances, transaction),
+        );
+        balances
+    }
+    /// Calculates the total of the balances of all accounts at a
+    /// transaction.
+    ///
+    /// Each move is subtracted for its debit account and added for its
+    /// credit account, so the amount of each unit is expected to be zero.
+    /// Sides of moves whose account is not in the book are not totaled, so
+    /// they show up as a nonzero amount. This allows checking the integrity
+    /// of the book.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn total_balance<BalanceNumber>(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        self.assert_has_transaction(&transaction_index);
+        let mut balances = self.zero_balances();
+        self.transactions[..=transaction_index.0]
+            .iter()
+            .flat_map(|transaction| transaction.moves.iter())
+            .for_each(|move_| {
+                if let Some(balance) =
+                    balances.get_mut(&move_.debit_account_key)
+                {
+                    *balance -= &move_.sum;
+                }
+                if let Some(balance) =
+                    balances.get_mut(&move_.credit_account_key)
+                {
+                    *balance += &move_.sum;
+                }
+            });
+        balances
+            .into_values()
+            .flatten()
+            .fold(Balance::default(), |total, unit_amount| {
+                total + &unit_amount
+            })
+    }
+    /// Gets a fingerprint of everything that balances at a transaction
+    /// depend on.
+    ///
+    /// The fingerprint changes whenever a move up to and including the
+    /// provided transaction is inserted, removed, relocated or changed in
+    /// its accounts or sum. This allows caching balances externally.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn account_balance_fingerprint(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> u64
+    where
+        Unit: Hash,
+        SumNumber: Hash,
+    {
+        self.assert_has_transaction(&transaction_index);
+        let mut hasher = DefaultHasher::new();
+        transaction_index.0.hash(&mut hasher);
+        self.transactions[..=transaction_index.0]
+            .iter()
+            .enumerate()
+            .for_each(|(transaction_index, transaction)| {
+                transaction.moves.iter().enumerate().for_each(
+                    |(move_index, move_)| {
+                        (
+                            transaction_index,
+                            move_index,
+                            move_.debit_account_key,
+                            move_.credit_account_key,
+                            &move_.sum,
+                        )
+                            .hash(&mut hasher);
+                    },
+                );
+            });
+        hasher.finish()
+    }
+    /// Compares the balances of accounts at a transaction to expected
+    /// balances.
+    ///
+    /// Returns the difference from the expected balance (see
+    /// [Balance::diff]) of each account for which it is not zero. Accounts
+    /// that are missing from either the book or `expected` are compared
+    /// with a zero balance.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn reconcile<BalanceNumber>(
+        &self,
+        expected: &HashMap<AccountKey, Balance<Unit, BalanceNumber>>,
+        transaction_index: TransactionIndex,
+    ) -> HashMap<AccountKey, Balance<Unit, BalanceNumber>>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone
+            + PartialEq,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let mut balances = self.balances_at_transaction(transaction_index);
+        expected.keys().for_each(|account_key| {
+            balances.entry(*account_key).or_default();
+        });
+        let zero = BalanceNumber::default();
+        balances
+            .into_iter()
+            .map(|(account_key, balance)| {
+                let difference = match expected.get(&account_key) {
+                    Some(expected) => balance.diff(expected),
+                    None => balance,
+                };
+                (account_key, difference)
+            })
+            .filter(|(_, difference)| {
+                difference.amounts().any(|(_, amount)| *amount != zero)
+            })
+            .collect()
+    }
+    /// Gets an iterator of existing accounts in order of creation, along with
+    /// the balance of each at a provided transaction.
+    ///
+    /// The provided transaction is included. The balances are calculated
+    /// in a single pass before iterating.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    #[allow(clippy::type_complexity)]
+    pub fn accounts_with_balance_at<BalanceNumber>(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> impl Iterator<
+        Item = (AccountKey, &AccountExtra, Balance<Unit, BalanceNumber>),
+    >
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let mut balances = self.balances_at_transaction(transaction_index);
+        self.accounts().map(move |(account_key, extra)| {
+            let balance = balances.remove(&account_key).unwrap_or_default();
+            (account_key, extra, balance)
+        })
+    }
+    /// Calculates the balances of all accounts at a transaction, ordered by
+    /// account key.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn balance_matrix<BalanceNumber>(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> BTreeMap<AccountKey, Balance<Unit, BalanceNumber>>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        self.balances_at_transaction(transaction_index)
+            .into_iter()
+            .collect()
+    }
+    /// Calculates the balances of all accounts at a transaction, in
+    /// parallel.
+    ///
+    /// The transactions are split among threads and the resulting balances
+    /// are merged. Requires the `rayon` feature.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    #[cfg(feature = "rayon")]
+    pub fn balances_at_transaction_parallel<BalanceNumber>(
+        &self,
+        transaction_index: TransactionIndex,
+    ) -> HashMap<AccountKey, Balance<Unit, BalanceNumber>>
+    where
+        Unit: Ord + Clone + Send + Sync,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone
+            + Send,
+        SumNumber: Clone + Into<BalanceNumber> + Sync,
+        TransactionExtra: Sync,
+        MoveExtra: Sync,
+    {
+        use rayon::prelude::*;
+        self.assert_has_transaction(&transaction_index);
+        let mut balances = self.transactions[..=transaction_index.0]
+            .par_iter()
+            .fold(HashMap::new, |mut balances, transaction| {
+                Self::apply_to_balances(&mut balances, transaction);
+                balances
+            })
+            .reduce(HashMap::new, |mut balances, other| {
+                other.into_iter().for_each(|(account_key, other)| {
+                    let balance: &mut Balance<Unit, BalanceNumber> =
+                        balances.entry(account_key).or_default();
+                    other.0.into_iter().for_each(|unit_amount| {
+                        *balance += &unit_amount;
+                    });
+                });
+                balances
+            });
+        self.accounts.keys().for_each(|account_key| {
+            balances.entry(account_key).or_default();
+        });
+        balances
+    }
+    fn zero_balances<BalanceNumber>(
+        &self,
+    ) -> HashMap<AccountKey, Balance<Unit, BalanceNumber>> {
+        self.accounts
+            .keys()
+            .map(|account_key| (account_key, Balance::default()))
+            .collect()
+    }
+    fn apply_to_balances<BalanceNumber>(
+        balances: &mut HashMap<AccountKey, Balance<Unit, BalanceNumber>>,
+        transaction: &Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>,
+    ) where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        transaction.moves.iter().for_each(|move_| {
+            *balances.entry(move_.debit_account_key).or_default() -= &move_.sum;
+            *balances.entry(move_.credit_account_key).or_default() +=
+                &move_.sum;
+        });
+    }
+    /// Gets the accounts that are on either side of any of the moves after
+    /// `from` up to and including `to`.
+    ///
+    /// ## Panics
+    ///
+    /// - `from` out of bounds.
+    /// - `to` out of bounds.
+    pub fn accounts_touched_between(
+        &self,
+        from: TransactionIndex,
+        to: TransactionIndex,
+    ) -> BTreeSet<AccountKey> {
+        self.assert_has_transaction(&from);
+        self.assert_has_transaction(&to);
+        self.transactions
+            .iter()
+            .take(to.0 + 1)
+            .skip(from.0 + 1)
+            .flat_map(|transaction| transaction.moves.iter())
+            .flat_map(|move_| {
+                iter::once(move_.debit_account_key)
+                    .chain(iter::once(move_.credit_account_key))
+            })
+            .collect()
+    }
+    fn account_balance_of_first_transactions<BalanceNumber>(
+        &self,
+        account_key: AccountKey,
+        transaction_count: usize,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let mut balance = Balance::default();
+        self.accumulate_account_balance_of_first_transactions(
+            account_key,
+            transaction_count,
+            &mut balance,
+        );
+        balance
+    }
+    /// Applies the moves of an account in the first transactions to a
+    /// balance and returns the number of moves applied.
+    #[allow(clippy::type_complexity)]
+    fn accumulate_account_balance_of_first_transactions<BalanceNumber>(
+        &self,
+        account_key: AccountKey,
+        transaction_count: usize,
+        balance: &mut Balance<Unit, BalanceNumber>,
+    ) -> usize
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        balance.apply_account_moves(
+            self.account_moves_in(account_key, 0..transaction_count),
+            BalanceNumber::default,
+        )
+    }
+    /// Gets the moves of an account in a range of transactions, in order,
+    /// as the index of their transaction, the side of the account and
+    /// their sum.
+    fn account_moves_in(
+        &self,
+        account_key: AccountKey,
+        transactions: Range<usize>,
+    ) -> impl Iterator<Item = (TransactionIndex, Side, &Sum<Unit, SumNumber>)>
+    {
+        let locations = self.account_move_locations(account_key);
+        let start = locations.partition_point(|(transaction_index, _, _)| {
+            transaction_index.0 < transactions.start
+        });
+        locations[start..]
+            .iter()
+            .take_while(move |(transaction_index, _, _)| {
+                transaction_index.0 < transactions.end
+            })
+            .map(move |(transaction_index, move_index, side)| {
+                (
+                    *transaction_index,
+                    *side,
+                    &self.transactions[transaction_index.0].moves[move_index.0]
+                        .sum,
+                )
+            })
+    }
+    /// Gets the locations of the moves that an account is on either side
+    /// of, in order, along with the side.
+    fn account_move_locations(
+        &self,
+        account_key: AccountKey,
+    ) -> &[(TransactionIndex, MoveIndex, Side)] {
+        #[cfg(any(test, feature = "testing"))]
+        if self.move_locations.get().is_some() {
+            self.cache_stats.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.cache_stats.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        self.move_locations
+            .get_or_init(|| {
+                let mut locations = MoveLocations::new();
+                self.transactions().for_each(
+                    |(transaction_index, transaction)| {
+                        transaction.moves().for_each(|(move_index, move_)| {
+                            [Side::Debit, Side::Credit].iter().for_each(
+                                |side| {
+                                    if let Some(entry) =
+                                        locations.entry(move_.side_key(*side))
+                                    {
+                                        entry.or_insert_with(Vec::new).push((
+                                            transaction_index,
+                                            move_index,
+                                            *side,
+                                        ));
+                                    }
+                                },
+                            );
+                        });
+                    },
+                );
+                locations
+            })
+            .get(account_key)
+            .map_or(&[], Vec::as_slice)
+    }
+    /// Gets a statement of an account.
+    ///
+    /// Yields, for each move that the account is on either side of, in
+    /// order, the move's location, the side of the account, the move's sum
+    /// and the balance of the account after the move.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    #[allow(clippy::type_complexity)]
+    pub fn account_statement<'a, BalanceNumber>(
+        &'a self,
+        account_key: AccountKey,
+    ) -> impl Iterator<
+        Item = (
+            TransactionIndex,
+            MoveIndex,
+            Side,
+            &'a Sum<Unit, SumNumber>,
+            Balance<Unit, BalanceNumber>,
+        ),
+    > + 'a
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone
+            + 'a,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        self.assert_has_account(account_key);
+        self.transactions
+            .iter()
+            .enumerate()
+            .flat_map(|(transaction_index, transaction)| {
+                transaction.moves.iter().enumerate().map(
+                    move |(move_index, move_)| {
+                        (transaction_index, move_index, move_)
+                    },
+                )
+            })
+            .filter_map(move |(transaction_index, move_index, move_)| {
+                if move_.debit_account_key == account_key {
+                    Some((transaction_index, move_index, Side::Debit, move_))
+                } else if move_.credit_account_key == account_key {
+                    Some((transaction_index, move_index, Side::Credit, move_))
+                } else {
+                    None
+                }
+            })
+            .scan(
+                Balance::default(),
+                |balance, (transaction_index, move_index, side, move_)| {
+                    match side {
+                        Side::Debit => *balance -= &move_.sum,
+                        Side::Credit => *balance += &move_.sum,
+                    }
+                    Some((
+                        TransactionIndex(transaction_index),
+                        MoveIndex(move_index),
+                        side,
+                        &move_.sum,
+                        balance.clone(),
+                    ))
+                },
+            )
+    }
+    /// Calculates the running balance of an account in a single pass.
+    ///
+    /// Yields, for each transaction, in order, the transaction's index and
+    /// the balance of the account after the transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    pub fn account_running_balance<'a, BalanceNumber>(
+        &'a self,
+        account_key: impl Borrow<AccountKey>,
+    ) -> impl Iterator<Item = (TransactionIndex, Balance<Unit, BalanceNumber>)> + 'a
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone
+            + 'a,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        let mut locations =
+            self.account_move_locations(account_key).iter().peekable();
+        self.transactions().scan(
+            Balance::default(),
+            move |balance, (transaction_index, transaction)| {
+                while let Some((_, move_index, side)) = locations
+                    .next_if(|(location, _, _)| *location == transaction_index)
+                {
+                    let sum = &transaction.moves[move_index.0].sum;
+                    match side {
+                        Side::Debit => *balance -= sum,
+                        Side::Credit => *balance += sum,
+                    }
+                }
+                Some((transaction_index, balance.clone()))
+            },
+        )
+    }
+    /// Calculates the balance history of an account.
+    ///
+    /// Returns, for each transaction that the account is involved in, in
+    /// order, the transaction's index and the balance of the account after
+    /// the transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    pub fn account_balance_history<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+    ) -> Vec<(TransactionIndex, Balance<Unit, BalanceNumber>)>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        let mut balance = Balance::default();
+        self.transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, transaction)| {
+                transaction.moves.iter().any(|move_| {
+                    move_.debit_account_key == account_key
+                        || move_.credit_account_key == account_key
+                })
+            })
+            .map(|(transaction_index, transaction)| {
+                balance.apply_transaction(transaction, account_key);
+                (TransactionIndex(transaction_index), balance.clone())
+            })
+            .collect()
+    }
+    /// Calculates the running total of a single unit across a group of
+    /// accounts.
+    ///
+    /// Yields, for each transaction, in order, the transaction's index and
+    /// the total amount of the unit in the balances of the accounts after
+    /// the transaction. Moves between accounts of the group do not change
+    /// the total.
+    ///
+    /// ## Panics
+    ///
+    /// - Some of `account_keys` are not in the book.
+    pub fn unit_balance_history_for_accounts<'a, BalanceNumber>(
+        &'a self,
+        account_keys: &'a HashSet<AccountKey>,
+        unit: &'a Unit,
+    ) -> impl Iterator<Item = (TransactionIndex, BalanceNumber)> + 'a
+    where
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone
+            + 'a,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        account_keys
+            .iter()
+            .for_each(|account_key| self.assert_has_account(*account_key));
+        self.transactions().scan(
+            BalanceNumber::default(),
+            move |total, (transaction_index, transaction)| {
+                transaction.moves.iter().for_each(|move_| {
+                    let amount = match move_.sum.unit_amount(unit) {
+                        Some(amount) => amount,
+                        None => return,
+                    };
+                    if account_keys.contains(&move_.debit_account_key) {
+                        *total = std::mem::take(total) - amount.clone().into();
+                    }
+                    if account_keys.contains(&move_.credit_account_key) {
+                        *total = std::mem::take(total) + amount.clone().into();
+                    }
+                });
+                Some((transaction_index, total.clone()))
+            },
+        )
+    }
+    /// Renders the transactions as a journal.
+    ///
+    /// Each transaction is a block, separated from the next by an empty
+    /// line. A block starts with the transaction's extra data, followed by
+    /// an indented line for each side of each move, of the account as
+    /// rendered by `render`, the side and the sum.
+    pub fn export_journal(
+        &self,
+        render: impl Fn(AccountKey) -> String,
+    ) -> String
+    where
+        TransactionExtra: fmt::Display,
+        Unit: fmt::Display,
+        SumNumber: fmt::Display,
+    {
+        self.transactions
+            .iter()
+            .map(|transaction| {
+                let mut block = format!("{}\n", transaction.extra);
+                transaction.moves.iter().for_each(|move_| {
+                    [Side::Debit, Side::Credit].iter().for_each(|side| {
+                        block += &format!(
+                            "    {}  {}  {}\n",
+                            render(move_.side_key(*side)),
+                            side,
+                            move_.sum,
+                        );
+                    });
+                });
+                block
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    /// Writes the moves as CSV, for spreadsheets.
+    ///
+    /// Each move is a row of its transaction index, its move index, its
+    /// debit and credit accounts, as rendered by `render_account`, and a
+    /// column per unit that is in any of the sums, as rendered by
+    /// `render_unit`, in order. Amounts of units that are not in a sum are
+    /// empty. The first row is of the column names.
+    ///
+    /// Requires the `csv` feature.
+    ///
+    /// ## Panics
+    ///
+    /// - Some account of some move is not in the book.
+    #[cfg(feature = "csv")]
+    pub fn write_csv<W: std::io::Write>(
+        &self,
+        mut w: W,
+        render_account: impl Fn(&AccountExtra) -> String,
+        render_unit: impl Fn(&Unit) -> String,
+    ) -> std::io::Result<()>
+    where
+        SumNumber: fmt::Display,
+    {
+        let units: BTreeSet<&Unit> = self
+            .transactions
+            .iter()
+            .flat_map(|transaction| transaction.moves.iter())
+            .flat_map(|move_| move_.sum.0.keys())
+            .collect();
+        let mut header: Vec<String> =
+            ["transaction", "move", "debit", "credit"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect();
+        header.extend(units.iter().map(|unit| render_unit(unit)));
+        write_csv_row(&mut w, &header)?;
+        self.transactions()
+            .try_for_each(|(transaction_index, transaction)| {
+                transaction.moves().try_for_each(|(move_index, move_)| {
+                    let mut row = vec![
+                        transaction_index.0.to_string(),
+                        move_index.0.to_string(),
+                        render_account(&self.accounts[move_.debit_account_key]),
+                        render_account(
+                            &self.accounts[move_.credit_account_key],
+                        ),
+                    ];
+                    row.extend(units.iter().map(|unit| {
+                        move_
+                            .sum
+                            .unit_amount(unit)
+                            .map(ToString::to_string)
+                            .unwrap_or_default()
+                    }));
+                    write_csv_row(&mut w, &row)
+                })
+            })
+    }
+    /// Renders the flows between accounts as a Graphviz DOT graph.
+    ///
+    /// Each account is a node, labeled by `label`. Each pair of accounts
+    /// that moves were made between is an edge from the debit account to
+    /// the credit account, labeled with the total amount of each unit.
+    pub fn to_dot(
+        &self,
+        label: impl Fn(AccountKey, &AccountExtra) -> String,
+    ) -> String
+    where
+        Unit: fmt::Display,
+        SumNumber: Add<Output = SumNumber> + Clone + fmt::Display,
+    {
+        let mut flows: BTreeMap<_, BTreeMap<&Unit, SumNumber>> =
+            BTreeMap::new();
+        self.transactions
+            .iter()
+            .flat_map(|transaction| transaction.moves.iter())
+            .for_each(|move_| {
+                let flow = flows
+                    .entry((move_.debit_account_key, move_.credit_account_key))
+                    .or_default();
+                move_.sum.amounts().for_each(|(unit, amount)| {
+                    let total = match flow.remove(unit) {
+                        Some(total) => total + amount.clone(),
+                        None => amount.clone(),
+                    };
+                    flow.insert(unit, total);
+                });
+            });
+        let mut dot = String::from("digraph {\n");
+        self.accounts.iter().for_each(|(account_key, extra)| {
+            dot += &format!(
+                "    \"{}\" [label={}];\n",
+                account_key,
+                dot_string(&label(account_key, extra)),
+            );
+        });
+        flows.iter().for_each(|((debit, credit), flow)| {
+            let totals = flow
+                .iter()
+                .map(|(unit, amount)| format!("{} {}", amount, unit))
+                .collect::<Vec<_>>()
+                .join("\n");
+            dot += &format!(
+                "    \"{}\" -> \"{}\" [label={}];\n",
+                debit,
+                credit,
+                dot_string(&totals),
+            );
+        });
+        dot += "}\n";
+        dot
+    }
+    /// Converts the extra data of each of the moves in the book.
+    ///
+    /// The history of changes, for [Book::undo], is not kept.
+    pub fn map_move_extra<B>(
+        self,
+        mut f: impl FnMut(MoveExtra) -> B,
+    ) -> Book<Unit, SumNumber, AccountExtra, TransactionExtra, B> {
+        Book {
+            account_keys: self.account_keys,
+            accounts: self.accounts,
+            transactions: self
+                .transactions
+                .into_iter()
+                .map(|transaction| transaction.map_move_extra(&mut f))
+                .collect(),
+            handlers: self.handlers,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            move_locations: self.move_locations,
+            reject_empty_sums: self.reject_empty_sums,
+            units: self.units,
+            #[cfg(any(test, feature = "testing"))]
+            cache_stats: self.cache_stats,
+        }
+    }
+    /// Converts the extra data of the accounts, the transactions and the
+    /// moves in the book.
+    ///
+    /// The account keys remain valid for the resulting book.
+    /// The history of changes, for [Book::undo], is not kept.
+    pub fn map_extras<A, T, M>(
+        self,
+        mut account_f: impl FnMut(AccountExtra) -> A,
+        mut transaction_f: impl FnMut(TransactionExtra) -> T,
+        mut move_f: impl FnMut(MoveExtra) -> M,
+    ) -> Book<Unit, SumNumber, A, T, M> {
+        let mut accounts = SecondaryMap::with_capacity(self.accounts.len());
+        self.accounts.into_iter().for_each(|(account_key, extra)| {
+            accounts.insert(account_key, account_f(extra));
+        });
+        Book {
+            account_keys: self.account_keys,
+            accounts,
+            transactions: self
+                .transactions
+                .into_iter()
+                .map(|transaction| {
+                    transaction
+                        .map_extra(&mut transaction_f)
+                        .map_move_extra(&mut move_f)
+                })
+                .collect(),
+            handlers: self.handlers,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            move_locations: self.move_locations,
+            reject_empty_sums: self.reject_empty_sums,
+            units: self.units,
+            #[cfg(any(test, feature = "testing"))]
+            cache_stats: self.cache_stats,
+        }
+    }
+    /// Removes an existing transaction from the book.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn remove_transaction(&mut self, transaction_index: TransactionIndex) {
+        self.edit(Edit::RemoveTransaction(transaction_index));
+    }
+    /// Relocates a transaction from one index to another.
+    ///
+    /// The transactions in between are shifted accordingly.
+    ///
+    /// ## Panics
+    ///
+    /// - `from` out of bounds.
+    /// - `to` out of bounds.
+    pub fn move_transaction(
+        &mut self,
+        from: TransactionIndex,
+        to: TransactionIndex,
+    ) {
+        self.assert_has_transaction(&from);
+        self.assert_has_transaction(&to);
+        self.edit(Edit::SetTransactionIndex(from, to));
+    }
+    /// Counts the moves that an account is on either side of.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    pub fn move_count_for_account(&self, account_key: AccountKey) -> usize {
+        self.assert_has_account(account_key);
+        self.transactions
+            .iter()
+            .flat_map(|transaction| transaction.moves.iter())
+            .filter(|move_| {
+                move_.debit_account_key == account_key
+                    || move_.credit_account_key == account_key
+            })
+            .count()
+    }
+    /// Counts the transactions that have moves that the account is on
+    /// either side of.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    pub fn transaction_count_for_account(
+        &self,
+        account_key: AccountKey,
+    ) -> usize {
+        self.assert_has_account(account_key);
+        self.transactions
+            .iter()
+            .filter(|transaction| {
+                transaction.moves.iter().any(|move_| {
+                    move_.debit_account_key == account_key
+                        || move_.credit_account_key == account_key
+                })
+            })
+            .count()
+    }
+    /// Checks whether the account is on either side of any move up to a
+    /// provided transaction.
+    ///
+    /// The provided transaction is included. This distinguishes an account
+    /// with no activity from one whose balance nets to zero.
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the book.
+    /// - `transaction_index` out of bounds.
+    pub fn account_activity_at_transaction(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> bool {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        self.assert_has_transaction(&transaction_index);
+        self.transactions
+            .iter()
+            .take(transaction_index.0 + 1)
+            .flat_map(|transaction| transaction.moves.iter())
+            .any(|move_| move_.account_side(account_key).is_some())
+    }
+    /// Removes all transactions that match a predicate.
+    ///
+    /// The order of the remaining transactions is preserved.
+    /// Returns the number of removed transactions.
+    pub fn remove_transactions_matching<F>(&mut self, predicate: F) -> usize
+    where
+        F: Fn(
+            &Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>,
+        ) -> bool,
+    {
+        let indices = self
+            .transactions
+            .iter()
+            .enumerate()
+            .filter(|(_, transaction)| predicate(transaction))
+            .map(|(index, _)| index)
+            .collect();
+        self.remove_transactions_at(indices)
+    }
+    /// Removes consecutive equal transactions, keeping the first of each run.
+    ///
+    /// Returns the number of removed transactions.
+    pub fn dedup_adjacent_transactions(&mut self) -> usize
+    where
+        SumNumber: PartialEq,
+        TransactionExtra: PartialEq,
+        MoveExtra: PartialEq,
+    {
+        let mut kept = 0;
+        let indices = (1..self.transactions.len())
+            .filter(|&index| {
+                let duplicate =
+                    self.transactions[index] == self.transactions[kept];
+                if !duplicate {
+                    kept = index;
+                }
+                duplicate
+            })
+            .collect();
+        self.remove_transactions_at(indices)
+    }
+    /// Removes every move that refers to an account that is not in the
+    /// book.
+    ///
+    /// Returns the number of removed moves.
+    pub fn prune_orphan_moves(&mut self) -> usize {
+        let edits: Vec<_> = self
+            .transactions
+            .iter()
+            .enumerate()
+            .flat_map(|(transaction_index, transaction)| {
+                transaction
+                    .moves
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|(_, move_)| {
+                        !self.accounts.contains_key(move_.debit_account_key)
+                            || !self
+                                .accounts
+                                .contains_key(move_.credit_account_key)
+                    })
+                    .map(move |(move_index, _)| {
+                        Edit::RemoveMove(
+                            TransactionIndex(transaction_index),
+                            MoveIndex(move_index),
+                        )
+                    })
+            })
+            .collect();
+        let count = edits.len();
+        if count > 0 {
+            self.edit(Edit::Batch(edits));
+        }
+        count
+    }
+    /// Merges the moves of a transaction that have the same debit account
+    /// and the same credit account.
+    ///
+    /// The merged move is in the position of the first of the moves and
+    /// keeps its extra. Its sum is the total of the sums of the moves.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    pub fn compact_transaction(&mut self, transaction_index: TransactionIndex)
+    where
+        Unit: Clone,
+        SumNumber: Add<Output = SumNumber> + Default + Clone,
+        MoveExtra: Clone,
+    {
+        let moves = &self.transactions[transaction_index.0].moves;
+        let mut positions = HashMap::new();
+        let mut compacted = Vec::with_capacity(moves.len());
+        moves.iter().cloned().for_each(|move_| {
+            let accounts = (move_.debit_account_key, move_.credit_account_key);
+            match positions.get(&accounts) {
+                Some(&position) => {
+                    let merged: &mut Move<_, _, _> = &mut compacted[position];
+                    move_.sum.0.into_iter().for_each(|(unit, amount)| {
+                        let entry = merged.sum.0.entry(unit).or_default();
+                        *entry = std::mem::take(entry) + amount;
+                    });
+                }
+                None => {
+                    positions.insert(accounts, compacted.len());
+                    compacted.push(move_);
+                }
+            }
+        });
+        self.edit(Edit::SetMoves(transaction_index, compacted));
+    }
+    /// Removes an existing move from the book.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    /// - `move_index` out of bounds.
+    pub fn remove_move(
+        &mut self,
+        transaction_index: TransactionIndex,
+        move_index: MoveIndex,
+    ) where
+        Unit: Ord,
+    {
+        self.edit(Edit::RemoveMove(transaction_index, move_index));
+    }
+    /// Relocates an existing move to another index in its transaction.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    /// - `from` out of bounds.
+    /// - `to` out of bounds.
+    pub fn set_move_index(
+        &mut self,
+        transaction_index: TransactionIndex,
+        from: MoveIndex,
+        to: MoveIndex,
+    ) {
+        let moves = &self.transactions[transaction_index.0].moves;
+        [from.0, to.0].iter().for_each(|index| {
+            assert!(
+                *index < moves.len(),
+                "move index (is {}) should be < len (is {})",
+                index,
+                moves.len(),
+            );
+        });
+        self.edit(Edit::SetMoveIndex(transaction_index, from, to));
+    }
+    /// Relocates an existing move to an index in another transaction.
+    ///
+    /// The move is kept as it is, including its extra data. `to_move` is
+    /// the index of the move in `to_transaction` after it is removed from
+    /// `from_transaction`.
+    ///
+    /// ## Panics
+    ///
+    /// - `from_transaction` out of bounds.
+    /// - `from_move` out of bounds.
+    /// - `to_transaction` out of bounds.
+    /// - `to_move` out of bounds.
+    pub fn relocate_move(
+        &mut self,
+        from_transaction: TransactionIndex,
+        from_move: MoveIndex,
+        to_transaction: TransactionIndex,
+        to_move: MoveIndex,
+    ) {
+        self.assert_has_transaction(&from_transaction);
+        self.assert_has_transaction(&to_transaction);
+        let from_len = self.transactions[from_transaction.0].moves.len();
+        assert!(
+            from_move.0 < from_len,
+            "move index (is {}) should be < len (is {})",
+            from_move.0,
+            from_len,
+        );
+        let to_len = self.transactions[to_transaction.0].moves.len()
+            - usize::from(from_transaction == to_transaction);
+        assert!(
+            to_move.0 <= to_len,
+            "insertion index (is {}) should be <= len (is {})",
+            to_move.0,
+            to_len,
+        );
+        self.edit(Edit::RelocateMove(
+            (from_transaction, from_move),
+            (to_transaction, to_move),
+        ));
+    }
+    /// Gets the sum of an existing move.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    /// - `move_index` out of bounds.
+    pub fn move_sum(
+        &self,
+        transaction_index: TransactionIndex,
+        move_index: MoveIndex,
+    ) -> &Sum<Unit, SumNumber> {
+        &self.transactions[transaction_index.0].moves[move_index.0].sum
+    }
+    /// Sets the sum of an existing move.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    /// - `move_index` out of bounds.
+    /// - Some unit of `sum` is not in the book, once any unit is. See
+    ///   [Book::insert_unit].
+    /// - `sum` is empty and empty sums are rejected. See
+    ///   [Book::set_reject_empty_sums].
+    pub fn set_move_sum(
+        &mut self,
+        transaction_index: TransactionIndex,
+        move_index: MoveIndex,
+        sum: Sum<Unit, SumNumber>,
+    ) where
+        Unit: Ord,
+    {
+        self.assert_sum_accepted(&sum);
+        self.edit(Edit::SetMoveSum(transaction_index, move_index, sum));
+    }
+    /// Sets the account for one of the sides of an existing move.
+    ///
+    /// ## Panics
+    ///
+    /// - `transaction_index` out of bounds.
+    /// - `move_index` out of bounds.
+    /// - `account_key` is not in the book.
+    /// - `side` is same as other side.
+    pub fn set_move_side(
+        &mut self,
+        transaction_index: TransactionIndex,
+        move_index: MoveIndex,
+        side: Side,
+        account_key: impl Borrow<AccountKey>,
+    ) where
+        Unit: Ord,
+    {
+        let account_key = *account_key.borrow();
+        self.assert_has_account(account_key);
+        let move_ = &self.transactions[transaction_index.0].moves[move_index.0];
+        match side {
+            Side::Debit => {
+                assert_ne!(account_key, move_.credit_account_key, "Provided debit account is same as existing credit account.");
+            }
+            Side::Credit => {
+                assert_ne!(account_key, move_.debit_account_key, "Provided credit account is same as existing debit account.");
+            }
+        }
+        self.edit(Edit::SetMoveSide(
+            transaction_index,
+            move_index,
+            side,
+            account_key,
+        ));
+    }
+    /// Checks the integrity of the book.
+    ///
+    /// Returns all of the issues found, in order of transaction and move.
+    pub fn validate(&self) -> Result<(), Vec<ValidationIssue>> {
+        let mut issues = Vec::new();
+        self.transactions.iter().enumerate().for_each(
+            |(transaction_index, transaction)| {
+                let transaction_index = TransactionIndex(transaction_index);
+                transaction.moves.iter().enumerate().for_each(
+                    |(move_index, move_)| {
+                        let move_index = MoveIndex(move_index);
+                        if move_.debit_account_key == move_.credit_account_key {
+                            issues.push(ValidationIssue::SelfMove(
+                                transaction_index,
+                                move_index,
+                                move_.debit_account_key,
+                            ));
+                        }
+                        let mut account_keys = vec![
+                            move_.debit_account_key,
+                            move_.credit_account_key,
+                        ];
+                        account_keys.dedup();
+                        account_keys
+                            .into_iter()
+                            .filter(|account_key| {
+                                !self.accounts.contains_key(*account_key)
+                            })
+                            .for_each(|account_key| {
+                                issues.push(ValidationIssue::AccountNotFound(
+                                    transaction_index,
+                                    move_index,
+                                    account_key,
+                                ));
+                            });
+                    },
+                );
+            },
+        );
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+    /// Compares the accounts, transactions and moves to those of another
+    /// book.
+    pub fn diff(&self, other: &Self) -> BookDiff
+    where
+        SumNumber: PartialEq,
+        AccountExtra: PartialEq,
+        TransactionExtra: PartialEq,
+        MoveExtra: PartialEq,
+    {
+        let mut diff = BookDiff::default();
+        self.accounts.iter().for_each(|(account_key, extra)| {
+            match other.accounts.get(account_key) {
+                None => diff.removed_accounts.push(account_key),
+                Some(other_extra) if other_extra != extra => {
+                    diff.changed_accounts.push(account_key)
+                }
+                Some(_) => {}
+            }
+        });
+        diff.added_accounts = other
+            .accounts
+            .keys()
+            .filter(|account_key| !self.accounts.contains_key(*account_key))
+            .collect();
+        let common = self.transactions.len().min(other.transactions.len());
+        diff.removed_transactions = (common..self.transactions.len())
+            .map(TransactionIndex)
+            .collect();
+        diff.added_transactions = (common..other.transactions.len())
+            .map(TransactionIndex)
+            .collect();
+        self.transactions
+            .iter()
+            .zip(other.transactions.iter())
+            .enumerate()
+            .for_each(
+                |(transaction_index, (transaction, other_transaction))| {
+                    let transaction_index = TransactionIndex(transaction_index);
+                    if transaction.extra != other_transaction.extra {
+                        diff.changed_transactions.push(transaction_index);
+                    }
+                    let (moves, other_moves) =
+                        (&transaction.moves, &other_transaction.moves);
+                    let common = moves.len().min(other_moves.len());
+                    let coordinates =
+                        |move_index| (transaction_index, MoveIndex(move_index));
+                    diff.changed_moves.extend(
+                        (0..common)
+                            .filter(|&index| moves[index] != other_moves[index])
+                            .map(coordinates),
+                    );
+                    diff.removed_moves
+                        .extend((common..moves.len()).map(coordinates));
+                    diff.added_moves
+                        .extend((common..other_moves.len()).map(coordinates));
+                },
+            );
+        diff
+    }
+    /// Applies several changes at once, or none of them.
+    ///
+    /// The changes are checked in order, each against the book as it would
+    /// be after the changes before it. If any of them cannot be applied,
+    /// the book is left unchanged and the index of that change is returned
+    /// with the reason. Otherwise, all of them are applied and
+    /// [Book::undo] reverses them together.
+    #[allow(clippy::type_complexity)]
+    pub fn apply(
+        &mut self,
+        ops: Vec<
+            BookOp<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>,
+        >,
+    ) -> Result<(), (usize, BookOpError<Unit>)>
+    where
+        Unit: Clone,
+    {
+        let mut move_counts: Vec<usize> = self
+            .transactions
+            .iter()
+            .map(|transaction| transaction.moves.len())
+            .collect();
+        ops.iter().enumerate().try_for_each(|(index, op)| {
+            self.check_op(&mut move_counts, op)
+                .map_err(|error| (index, error))
+        })?;
+        if !ops.is_empty() {
+            self.edit(Edit::Batch(
+                ops.into_iter().map(BookOp::into_edit).collect(),
+            ));
+        }
+        Ok(())
+    }
+    /// Checks an op against the move counts of the transactions, updating
+    /// them as the op would.
+    fn check_op(
+        &self,
+        move_counts: &mut Vec<usize>,
+        op: &BookOp<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>,
+    ) -> Result<(), BookOpError<Unit>>
+    where
+        Unit: Clone,
+    {
+        let check_account = |account_key: AccountKey| {
+            if self.accounts.contains_key(account_key) {
+                Ok(())
+            } else {
+                Err(BookOpError::AccountNotFound(account_key))
+            }
+        };
+        let check_transaction =
+            |move_counts: &Vec<usize>, transaction_index: TransactionIndex| {
+                if transaction_index.0 < move_counts.len() {
+                    Ok(())
+                } else {
+                    Err(BookOpError::TransactionIndexOutOfBounds(
+                        transaction_index,
+                    ))
+                }
+            };
+        let check_move = |move_counts: &Vec<usize>,
+                          transaction_index: TransactionIndex,
+                          move_index: MoveIndex| {
+            check_transaction(move_counts, transaction_index)?;
+            if move_index.0 < move_counts[transaction_index.0] {
+                Ok(())
+            } else {
+                Err(BookOpError::MoveIndexOutOfBounds(
+                    transaction_index,
+                    move_index,
+                ))
+            }
+        };
+        let check_sum = |sum: &Sum<Unit, SumNumber>,
+                         transaction_index: TransactionIndex,
+                         move_index: MoveIndex| {
+            if let Some(unit) = self.unit_not_found(sum) {
+                Err(BookOpError::UnitNotFound(
+                    transaction_index,
+                    move_index,
+                    unit.clone(),
+                ))
+            } else if self.reject_empty_sums && sum.0.is_empty() {
+                Err(BookOpError::EmptySum(transaction_index, move_index))
+            } else {
+                Ok(())
+            }
+        };
+        match op {
+            BookOp::SetAccount(account_key, _) => check_account(*account_key),
+            BookOp::InsertTransaction(transaction_index, _) => {
+                if transaction_index.0 > move_counts.len() {
+                    return Err(BookOpError::TransactionIndexOutOfBounds(
+                        *transaction_index,
+                    ));
+                }
+                move_counts.insert(transaction_index.0, 0);
+                Ok(())
+            }
+            BookOp::RemoveTransaction(transaction_index) => {
+                check_transaction(move_counts, *transaction_index)?;
+                move_counts.remove(transaction_index.0);
+                Ok(())
+            }
+            BookOp::SetTransactionExtra(transaction_index, _) => {
+                check_transaction(move_counts, *transaction_index)
+            }
+            BookOp::InsertMove(
+                transaction_index,
+                move_index,
+                debit_account_key,
+                credit_account_key,
+                sum,
+                _,
+            ) => {
+                check_transaction(move_counts, *transaction_index)?;
+                let move_count = &mut move_counts[transaction_index.0];
+                if move_index.0 > *move_count {
+                    return Err(BookOpError::MoveIndexOutOfBounds(
+                        *transaction_index,
+                        *move_index,
+                    ));
+                }
+                check_account(*debit_account_key)?;
+                check_account(*credit_account_key)?;
+                if debit_account_key == credit_account_key {
+                    return Err(BookOpError::SameAccounts(*debit_account_key));
+                }
+                check_sum(sum, *transaction_index, *move_index)?;
+                *move_count += 1;
+                Ok(())
+            }
+            BookOp::RemoveMove(transaction_index, move_index) => {
+                check_move(move_counts, *transaction_index, *move_index)?;
+                move_counts[transaction_index.0] -= 1;
+                Ok(())
+            }
+            BookOp::SetMoveExtra(transaction_index, move_index, _) => {
+                check_move(move_counts, *transaction_index, *move_index)
+            }
+            BookOp::SetMoveSum(transaction_index, move_index, sum) => {
+                check_move(move_counts, *transaction_index, *move_index)?;
+                check_sum(sum, *transaction_index, *move_index)
+            }
+        }
+    }
+    /// Sets whether moves with empty sums are rejected.
+    ///
+    /// When rejected, [Book::insert_move] and [Book::set_move_sum] panic on
+    /// an empty sum and [Book::apply] returns [BookOpError::EmptySum].
+    /// Moves that are already in the book are not affected.
+    /// By default, empty sums are accepted.
+    pub fn set_reject_empty_sums(&mut self, reject_empty_sums: bool) {
+        self.reject_empty_sums = reject_empty_sums;
+    }
+    /// Inserts a unit.
+    ///
+    /// Once any unit is in the book, the sums of new moves are limited to
+    /// the units in the book and [Book::insert_move] and similar methods
+    /// panic on other units. Moves that are already in the book are not
+    /// affected, nor reported by [Book::validate]. Inserting a unit is not
+    /// undoable.
+    pub fn insert_unit(&mut self, unit: Unit) {
+        self.units.insert(unit);
+    }
+    /// Gets an iterator of the units in the book, in order.
+    pub fn units(&self) -> impl Iterator<Item = &Unit> {
+        self.units.iter()
+    }
+    /// Registers a handler that is called with each change to the book.
+    ///
+    /// Handlers are called in order of registration.
+    pub fn on_change(&mut self, handler: BookEventHandler) {
+        self.handlers.push(handler);
+    }
+    /// Reverses the latest change that was not reversed.
+    ///
+    /// Returns whether there was such a change.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(edit) => {
+                let inverse = self.apply_edit(edit);
+                self.redo_stack.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Reapplies the latest change that was reversed using [Book::undo].
+    ///
+    /// Returns whether there was such a change. Any other change to the
+    /// book discards the changes that can be reapplied.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(edit) => {
+                let inverse = self.apply_edit(edit);
+                self.undo_stack.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+    fn emit(&mut self, event: BookEvent) {
+        self.handlers.iter_mut().for_each(|handler| handler(&event));
+    }
+    fn edit(
+        &mut self,
+        edit: Edit<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>,
+    ) {
+        let inverse = self.apply_edit(edit);
+        self.record(inverse);
+    }
+    fn record(
+        &mut self,
+        inverse: Edit<
+            Unit,
+            SumNumber,
+            AccountExtra,
+            TransactionExtra,
+            MoveExtra,
+        >,
+    ) {
+        self.undo_stack.push(inverse);
+        self.redo_stack.clear();
+    }
+    /// Applies an edit and returns the edit that reverses it.
+    fn apply_edit(
+        &mut self,
+        edit: Edit<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>,
+    ) -> Edit<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra> {
+        match edit {
+            Edit::InsertTransaction(..)
+            | Edit::RemoveTransaction(_)
+            | Edit::SetMoves(..)
+            | Edit::InsertMove(..)
+            | Edit::RemoveMove(..)
+            | Edit::SetTransactionIndex(..)
+            | Edit::SetMoveIndex(..)
+            | Edit::RelocateMove(..) => {
+                self.move_locations.take();
+            }
+            _ => {}
+        }
+        match edit {
+            Edit::InsertAccount(account_key, extra) => {
+                self.accounts.insert(account_key, extra);
+                self.emit(BookEvent::AccountInserted(account_key));
+                Edit::RemoveAccount(account_key)
+            }
+            Edit::RemoveAccount(account_key) => {
+                let extra = self.accounts.remove(account_key).unwrap();
+                self.emit(BookEvent::AccountRemoved(account_key));
+                Edit::InsertAccount(account_key, extra)
+            }
+            Edit::SetAccount(account_key, extra) => {
+                let previous =
+                    std::mem::replace(&mut self.accounts[account_key], extra);
+                self.emit(BookEvent::AccountSet(account_key));
+                Edit::SetAccount(account_key, previous)
+            }
+            Edit::InsertTransaction(transaction_index, transaction) => {
+                self.transactions.insert(transaction_index.0, transaction);
+                self.emit(BookEvent::TransactionInserted(transaction_index));
+                Edit::RemoveTransaction(transaction_index)
+            }
+            Edit::RemoveTransaction(transaction_index) => {
+                let transaction = self.transactions.remove(transaction_index.0);
+                self.emit(BookEvent::TransactionRemoved(transaction_index));
+                Edit::InsertTransaction(transaction_index, transaction)
+            }
+            Edit::SetTransactionExtra(transaction_index, extra) => {
+                let previous = std::mem::replace(
+                    &mut self.transactions[transaction_index.0].extra,
+                    extra,
+                );
+                self.emit(BookEvent::TransactionExtraSet(transaction_index));
+                Edit::SetTransactionExtra(transaction_index, previous)
+            }
+            Edit::SetMoves(transaction_index, moves) => {
+                let previous = std::mem::replace(
+                    &mut self.transactions[transaction_index.0].moves,
+                    moves,
+                );
+                self.emit(BookEvent::MovesSet(transaction_index));
+                Edit::SetMoves(transaction_index, previous)
+            }
+            Edit::InsertMove(transaction_index, move_index, move_) => {
+                self.transactions[transaction_index.0]
+                    .moves
+                    .insert(move_index.0, move_);
+                self.emit(BookEvent::MoveInserted(
+                    transaction_index,
+                    move_index,
+                ));
+                Edit::RemoveMove(transaction_index, move_index)
+            }
+            Edit::RemoveMove(transaction_index, move_index) => {
+                let move_ = self.transactions[transaction_index.0]
+                    .moves
+                    .remove(move_index.0);
+                self.emit(BookEvent::MoveRemoved(
+                    transaction_index,
+                    move_index,
+                ));
+                Edit::InsertMove(transaction_index, move_index, move_)
+            }
+            Edit::SetTransactionIndex(from, to) => {
+                let transaction = self.transactions.remove(from.0);
+                self.transactions.insert(to.0, transaction);
+                self.emit(BookEvent::TransactionIndexSet(from, to));
+                Edit::SetTransactionIndex(to, from)
+            }
+            Edit::SetMoveIndex(transaction_index, from, to) => {
+                let moves = &mut self.transactions[transaction_index.0].moves;
+                let move_ = moves.remove(from.0);
+                moves.insert(to.0, move_);
+                self.emit(BookEvent::MoveIndexSet(transaction_index, from, to));
+                Edit::SetMoveIndex(transaction_index, to, from)
+            }
+            Edit::RelocateMove(from, to) => {
+                let move_ =
+                    self.transactions[(from.0).0].moves.remove((from.1).0);
+                self.emit(BookEvent::MoveRemoved(from.0, from.1));
+                self.transactions[(to.0).0].moves.insert((to.1).0, move_);
+                self.emit(BookEvent::MoveInserted(to.0, to.1));
+                Edit::RelocateMove(to, from)
+            }
+            Edit::SetMoveExtra(transaction_index, move_index, extra) => {
+                let previous = std::mem::replace(
+                    &mut self.transactions[transaction_index.0].moves
+                        [move_index.0]
+                        .extra,
+                    extra,
+                );
+                self.emit(BookEvent::MoveExtraSet(
+                    transaction_index,
+                    move_index,
+                ));
+                Edit::SetMoveExtra(transaction_index, move_index, previous)
+            }
+            Edit::SetMoveSum(transaction_index, move_index, sum) => {
+                let previous = std::mem::replace(
+                    &mut self.transactions[transaction_index.0].moves
+                        [move_index.0]
+                        .sum,
+                    sum,
+                );
+                self.emit(BookEvent::MoveSumSet(transaction_index, move_index));
+                Edit::SetMoveSum(transaction_index, move_index, previous)
+            }
+            Edit::SetMoveSide(
+                transaction_index,
+                move_index,
+                side,
+                account_key,
+            ) => {
+                let move_ = &mut self.transactions[transaction_index.0].moves
+                    [move_index.0];
+                let side_key = match side {
+                    Side::Debit => &mut move_.debit_account_key,
+                    Side::Credit => &mut move_.credit_account_key,
+                };
+                let previous = std::mem::replace(side_key, account_key);
+                if let Some(move_locations) = self.move_locations.get_mut() {
+                    Self::move_location_between_accounts(
+                        move_locations,
+                        (transaction_index, move_index, side),
+                        previous,
+                        account_key,
+                    );
+                }
+                self.emit(BookEvent::MoveSideSet(
+                    transaction_index,
+                    move_index,
+                    side,
+                ));
+                Edit::SetMoveSide(transaction_index, move_index, side, previous)
+            }
+            Edit::Batch(edits) => {
+                let mut inverses = edits
+                    .into_iter()
+                    .map(|edit| self.apply_edit(edit))
+                    .collect::<Vec<_>>();
+                inverses.reverse();
+                Edit::Batch(inverses)
+            }
+        }
+    }
+    /// Moves the location of a move from the locations of the moves of one
+    /// account to those of another, keeping both in order.
+    fn move_location_between_accounts(
+        move_locations: &mut MoveLocations,
+        location: (TransactionIndex, MoveIndex, Side),
+        from: AccountKey,
+        to: AccountKey,
+    ) {
+        let order = |(transaction_index, move_index, _): &(
+            TransactionIndex,
+            MoveIndex,
+            Side,
+        )| (transaction_index.0, move_index.0);
+        if let Some(locations) = move_locations.get_mut(from) {
+            let start = locations
+                .partition_point(|other| order(other) < order(&location));
+            if let Some(offset) = locations[start..]
+                .iter()
+                .take_while(|other| order(other) == order(&location))
+                .position(|other| *other == location)
+            {
+                locations.remove(start + offset);
+            }
+        }
+        if let Some(entry) = move_locations.entry(to) {
+            let locations = entry.or_insert_with(Vec::new);
+            let index = locations
+                .partition_point(|other| order(other) < order(&location));
+            locations.insert(index, location);
+        }
+    }
+    /// Removes transactions at ascending indices, emitting an event per
+    /// transaction, from the last to the first.
+    ///
+    /// Bypasses [Book::apply_edit] to remove in linear time, so the move
+    /// locations are reset here.
+    fn remove_transactions_at(&mut self, indices: Vec<usize>) -> usize {
+        if indices.is_empty() {
+            return 0;
+        }
+        self.move_locations.take();
+        let mut remaining = indices.iter().peekable();
+        let mut removed = Vec::with_capacity(indices.len());
+        let transactions = std::mem::take(&mut self.transactions);
+        transactions.into_iter().enumerate().for_each(
+            |(index, transaction)| {
+                if remaining.next_if_eq(&&index).is_some() {
+                    removed.push(Edit::InsertTransaction(
+                        TransactionIndex(index),
+                        transaction,
+                    ));
+                } else {
+                    self.transactions.push(transaction);
+                }
+            },
+        );
+        indices.iter().rev().for_each(|&index| {
+            self.emit(BookEvent::TransactionRemoved(TransactionIndex(index)))
+        });
+        self.record(Edit::Batch(removed));
+        indices.len()
+    }
+    fn assert_has_transaction(&self, transaction_index: &TransactionIndex) {
+        assert!(
+            transaction_index.0 < self.transactions.len(),
+            "transaction index (is {}) should be < len (is {})",
+            transaction_index.0,
+            self.transactions.len(),
+        );
+    }
+    fn assert_sum_accepted(&self, sum: &Sum<Unit, SumNumber>) {
+        assert!(
+            self.unit_not_found(sum).is_none(),
+            "A unit of the sum is not in the book.",
+        );
+        assert!(
+            !(self.reject_empty_sums && sum.0.is_empty()),
+            "Empty sums are rejected.",
+        );
+    }
+    /// Gets the first unit of a sum that is not in the book, unless no
+    /// units are.
+    fn unit_not_found<'a>(
+        &self,
+        sum: &'a Sum<Unit, SumNumber>,
+    ) -> Option<&'a Unit> {
+        if self.units.is_empty() {
+            None
+        } else {
+            sum.0.keys().find(|unit| !self.units.contains(unit))
+        }
+    }
+    fn assert_has_account(&self, key: AccountKey) {
+        assert!(
+            self.accounts.contains_key(key),
+            "No account found for key {:?}",
+            key,
+        );
+    }
+}
+/// Writes a row of CSV fields, quoting those that need it.
+#[cfg(feature = "csv")]
+fn write_csv_row(
+    w: &mut impl std::io::Write,
+    fields: &[String],
+) -> std::io::Result<()> {
+    let row = fields
+        .iter()
+        .map(|field| {
+            if field.contains(&[',', '"', '\n', '\r'][..]) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(w, "{}", row)
+}
+/// Quotes a DOT string, escaping quotes, backslashes and newlines.
+fn dot_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    value.chars().for_each(|character| match character {
+        '"' => quoted.push_str("\\\""),
+        '\\' => quoted.push_str("\\\\"),
+        '\n' => quoted.push_str("\\n"),
+        _ => quoted.push(character),
+    });
+    quoted.push('"');
+    quoted
+}
+#[cfg(test)]
+mod test {
+    use super::{
+        AccountKey, Accounts,
+        Side::{Credit, Debit},
+        TransactionIndex, Transactions,
+    };
+    use crate::{
+        balance::Balance,
+        test_utils::{TestBalance, TestBook},
+        transaction::{MoveIndex, Moves, Transaction},
+    };
+    use crate::{
+        diff::BookDiff,
+        op::{BookOp, BookOpError},
+        validation::ValidationIssue,
+    };
+    use std::{
+        collections::HashSet,
+        sync::{Arc, Mutex},
+    };
+    #[test]
+    fn clone_and_eq() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        let mut clone = book.clone();
+        assert_eq!(clone, book);
+        assert!(!clone.undo());
+        clone.set_move_sum(
+            TransactionIndex(0),
+            MoveIndex(0),
+            test_sum!(4, usd),
+        );
+        assert_ne!(clone, book);
+        let mut clone = book.clone();
+        clone.set_account(bank_key, "savings");
+        assert_ne!(clone, book);
+        let mut clone = book.clone();
+        clone.set_transaction_extra(TransactionIndex(0), "!");
+        assert_ne!(clone, book);
+    }
+    #[test]
+    fn insert_transaction_sorted_by() {
+        let mut book = TestBook::default();
+        let key = |extra: &&str| extra.chars().next();
+        ["b1", "a1", "c1", "b2", "a2"].iter().for_each(|extra| {
+            book.insert_transaction_sorted_by(*extra, key);
+        });
+        assert_eq!(
+            book.insert_transaction_sorted_by("b3", key),
+            TransactionIndex(4),
+        );
+        assert_eq!(
+            book.transactions
+                .iter()
+                .map(|transaction| transaction.extra)
+                .collect::<Vec<_>>(),
+            ["a1", "a2", "b1", "b2", "b3", "c1"],
+        );
+        book.undo();
+        assert_eq!(book.transactions.len(), 5);
+    }
+    #[test]
+    fn debug() {
+        let mut book = TestBook::default();
+        book.insert_account("wallet");
+        book.insert_transaction(TransactionIndex(0), "!");
+        assert_eq!(
+            format!("{:?}", book),
+            "Book { accounts: {Account#1v1: \"wallet\"}, transactions: \
+             [Transaction { extra: \"!\", moves: [] }], units: {} }",
+        );
+    }
+    #[test]
+    fn append() {
+        let usd = "USD";
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        book.insert_transaction(TransactionIndex(0), "a");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        let mut other = TestBook::default();
+        let other_cash_key = other.insert_account("cash");
+        let other_shop_key = other.insert_account("shop");
+        other.insert_transaction(TransactionIndex(0), "b");
+        other.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            other_cash_key,
+            other_shop_key,
+            test_sum!(2, usd),
+            "",
+        );
+        let original = book.clone();
+        let account_keys = book.append(other);
+        assert_eq!(account_keys.len(), 2);
+        let cash_key = account_keys[&other_cash_key];
+        let shop_key = account_keys[&other_shop_key];
+        assert_eq!(
+            book.accounts().map(|(_, extra)| *extra).collect::<Vec<_>>(),
+            vec!["wallet", "bank", "cash", "shop"],
+        );
+        assert_eq!(*book.get_account(cash_key), "cash");
+        assert_eq!(*book.get_account(shop_key), "shop");
+        assert_eq!(
+            book.transactions()
+                .map(|(_, transaction)| *transaction.extra())
+                .collect::<Vec<_>>(),
+            vec!["a", "b"],
+        );
+        let move_ = &book.transactions[1].moves[0];
+        assert_eq!(move_.side_key(Debit), cash_key);
+        assert_eq!(move_.side_key(Credit), shop_key);
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                shop_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(2, usd),
+        );
+        assert!(book.validate().is_ok());
+        assert!(book.undo());
+        assert_eq!(book, original);
+    }
+    #[test]
+    fn account_key_display_and_debug() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        assert_eq!(account_key.to_string(), "Account#1v1");
+        assert_eq!(format!("{:?}", account_key), "Account#1v1");
+        book.account_keys.remove(account_key);
+        book.accounts.remove(account_key);
+        let account_key = book.insert_account("");
+        assert_eq!(account_key.to_string(), "Account#1v3");
+        let account_key = book.insert_account("");
+        assert_eq!(account_key.to_string(), "Account#2v1");
+    }
+    #[test]
+    fn default() {
+        let book = TestBook::default();
+        assert!(book.accounts.is_empty());
+        assert!(book.transactions.is_empty());
+    }
+    #[test]
+    fn insert_account() {
+        let mut book = TestBook::default();
+        book.insert_account("");
+        assert_eq!(book.accounts.len(), 1);
+    }
+    #[test]
+    fn insert_accounts() {
+        let mut book = TestBook::default();
+        let keys = book.insert_accounts(vec!["a", "b", "c"]);
+        assert_eq!(keys.len(), 3);
+        assert_eq!(
+            keys.iter()
+                .map(|key| *book.get_account(*key))
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c"],
+        );
+    }
+    #[test]
+    #[should_panic(expected = "insertion index (is 1) should be <= len (is 0)")]
+    fn insert_transaction_panic_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(1), "");
+    }
+    #[test]
+    fn insert_transaction() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "a");
+        book.insert_transaction(TransactionIndex(1), "b");
+        book.insert_transaction(TransactionIndex(0), "c");
+        book.insert_transaction(TransactionIndex(2), "d");
+        assert_eq!(
+            book.transactions
+                .iter()
+                .map(|transaction| transaction.extra())
+                .collect::<Vec<_>>(),
+            [&"c", &"a", &"d", &"b"],
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn clone_transaction_panic_source_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.clone_transaction(TransactionIndex(0), TransactionIndex(0));
+    }
+    #[test]
+    #[should_panic(expected = "insertion index (is 2) should be <= len (is 1)")]
+    fn clone_transaction_panic_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        book.clone_transaction(TransactionIndex(0), TransactionIndex(2));
+    }
+    #[test]
+    fn modify_move() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "a",
+        );
+        let previous_sum =
+            book.modify_move(TransactionIndex(0), MoveIndex(0), |editor| {
+                let previous_sum = editor.move_().sum().clone();
+                editor
+                    .set_side(Debit, bank_key)
+                    .set_side(Credit, wallet_key)
+                    .set_sum(test_sum!(4, usd))
+                    .set_extra("b");
+                previous_sum
+            });
+        assert_eq!(previous_sum, test_sum!(3, usd));
+        let move_ = &book.transactions[0].moves[0];
+        assert_eq!(move_.debit_account_key, bank_key);
+        assert_eq!(move_.credit_account_key, wallet_key);
+        assert_eq!(move_.sum, test_sum!(4, usd));
+        assert_eq!(move_.extra, "b");
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                wallet_key,
+                TransactionIndex(0),
+            ),
+            TestBalance::default() + &test_sum!(4, usd),
+        );
+        book.modify_move(TransactionIndex(0), MoveIndex(0), |_| ());
+        book.undo();
+        let move_ = &book.transactions[0].moves[0];
+        assert_eq!(move_.debit_account_key, wallet_key);
+        assert_eq!(move_.credit_account_key, bank_key);
+        assert_eq!(move_.sum, test_sum!(3, usd));
+        assert_eq!(move_.extra, "a");
+    }
+    #[test]
+    #[should_panic(expected = "Debit and credit accounts are the same.")]
+    fn modify_move_panic_same_accounts() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(),
+            "",
+        );
+        book.modify_move(TransactionIndex(0), MoveIndex(0), |editor| {
+            editor.set_side(Credit, wallet_key);
+        });
+    }
+    #[test]
+    fn relocate_move() {
+        use super::Book;
+        struct NotClone(&'static str);
+        type NotCloneBook = Book<&'static str, u64, (), (), NotClone>;
+        let mut book = NotCloneBook::default();
+        let wallet_key = book.insert_account(());
+        let bank_key = book.insert_account(());
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), ());
+        book.insert_transaction(TransactionIndex(1), ());
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            NotClone("a"),
+        );
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(1, usd),
+            NotClone("b"),
+        );
+        let extras = |book: &NotCloneBook| {
+            book.transactions
+                .iter()
+                .map(|transaction| {
+                    transaction
+                        .moves
+                        .iter()
+                        .map(|move_| move_.extra.0)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let balance = |book: &NotCloneBook, index| {
+            book.account_balance_at_transaction::<i128>(
+                bank_key,
+                TransactionIndex(index),
+            )
+        };
+        assert_eq!(
+            balance(&book, 0),
+            TestBalance::default() + &test_sum!(3, usd)
+        );
+        book.relocate_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            TransactionIndex(1),
+            MoveIndex(1),
+        );
+        assert_eq!(extras(&book), [vec![], vec!["b", "a"]]);
+        let move_ = &book.transactions[1].moves[1];
+        assert_eq!(move_.debit_account_key, wallet_key);
+        assert_eq!(move_.credit_account_key, bank_key);
+        assert_eq!(move_.sum, test_sum!(3, usd));
+        assert!(balance(&book, 0).is_empty());
+        assert_eq!(
+            balance(&book, 1),
+            TestBalance::default() + &test_sum!(2, usd)
+        );
+        book.relocate_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            TransactionIndex(1),
+            MoveIndex(1),
+        );
+        assert_eq!(extras(&book), [vec![], vec!["a", "b"]]);
+        book.undo();
+        book.undo();
+        assert_eq!(extras(&book), [vec!["a"], vec!["b"]]);
+        assert_eq!(
+            balance(&book, 0),
+            TestBalance::default() + &test_sum!(3, usd)
+        );
+    }
+    #[test]
+    #[should_panic(expected = "insertion index (is 1) should be <= len (is 0)")]
+    fn relocate_move_panic_to_move_out_of_bounds() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(),
+            "",
+        );
+        book.relocate_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            TransactionIndex(0),
+            MoveIndex(1),
+        );
+    }
+    #[test]
+    fn reverse_transaction() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "opening");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_c_key,
+            account_a_key,
+            test_sum!(10, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "rent");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd),
+            "a",
+        );
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(1),
+            account_a_key,
+            account_c_key,
+            test_sum!(4, usd; 1, thb),
+            "b",
+        );
+        let index = book.reverse_transaction(
+            TransactionIndex(1),
+            TransactionIndex(2),
+            "rent reversal",
+        );
+        assert_eq!(index, TransactionIndex(2));
+        assert_eq!(book.transactions[2].extra, "rent reversal");
+        let reversal = &book.transactions[2].moves;
+        assert_eq!(reversal.len(), 2);
+        assert_eq!(reversal[0].debit_account_key, account_b_key);
+        assert_eq!(reversal[0].credit_account_key, account_a_key);
+        assert_eq!(reversal[0].sum, test_sum!(3, usd));
+        assert_eq!(reversal[0].extra, "a");
+        assert_eq!(reversal[1].debit_account_key, account_c_key);
+        assert_eq!(reversal[1].credit_account_key, account_a_key);
+        assert_eq!(reversal[1].sum, test_sum!(4, usd; 1, thb));
+        assert_eq!(reversal[1].extra, "b");
+        [account_a_key, account_b_key, account_c_key]
+            .iter()
+            .for_each(|account_key| {
+                assert!(book
+                    .account_balance_at_transaction::<i128>(
+                        account_key,
+                        TransactionIndex(2),
+                    )
+                    .approx_eq(
+                        &book.account_balance_at_transaction(
+                            account_key,
+                            TransactionIndex(0),
+                        ),
+                        0,
+                    ));
+            });
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 1) should be < len (is 1)"
+    )]
+    fn reverse_transaction_panic_source_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        book.reverse_transaction(TransactionIndex(1), TransactionIndex(0), "");
+    }
+    #[test]
+    fn clone_transaction() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "rent");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd),
+            "a",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_a_key,
+            account_c_key,
+            test_sum!(4, usd),
+            "b",
+        );
+        let index =
+            book.clone_transaction(TransactionIndex(0), TransactionIndex(1));
+        assert_eq!(index.0, 1);
+        assert_eq!(book.transactions.len(), 2);
+        assert_eq!(book.transactions[1].extra, "rent");
+        assert_eq!(
+            book.transactions[1]
+                .moves
+                .iter()
+                .map(|move_| move_.extra)
+                .collect::<Vec<_>>(),
+            vec!["a", "b"],
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_a_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() - &test_sum!(14, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(6, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_c_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(8, usd),
+        );
+    }
+    #[test]
+    #[should_panic(expected = "insertion index (is 1) should be <= len (is 0)")]
+    fn insert_move_panic_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "",
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn insert_move_panic_debit_account_not_found() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        book.accounts.remove(debit_key);
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "",
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn insert_move_panic_credit_account_not_found() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.accounts.remove(credit_key);
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "",
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Debit and credit accounts are the same.")]
+    fn transfer_fan_out_panic_same_account() {
+        let mut book = TestBook::default();
+        let payroll_key = book.insert_account("");
+        let alice_key = book.insert_account("");
+        book.transfer_fan_out(
+            TransactionIndex(0),
+            payroll_key,
+            vec![(alice_key, test_sum!()), (payroll_key, test_sum!())],
+            "",
+            |_| "",
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Empty sums are rejected.")]
+    fn transfer_fan_out_panic_empty_sum() {
+        let mut book = TestBook::default();
+        let payroll_key = book.insert_account("");
+        let alice_key = book.insert_account("");
+        let bob_key = book.insert_account("");
+        let usd = "USD";
+        book.set_reject_empty_sums(true);
+        book.transfer_fan_out(
+            TransactionIndex(0),
+            payroll_key,
+            vec![(alice_key, test_sum!(1, usd)), (bob_key, test_sum!())],
+            "",
+            |_| "",
+        );
+    }
+    #[test]
+    fn transfer_fan_out() {
+        let mut book = TestBook::default();
+        let payroll_key = book.insert_account("");
+        let recipient_keys = book.insert_accounts(vec![""; 3]);
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "earlier");
+        let extras = ["a", "b", "c"];
+        let actual = book.transfer_fan_out(
+            TransactionIndex(0),
+            payroll_key,
+            vec![
+                (recipient_keys[0], test_sum!(1, usd)),
+                (recipient_keys[1], test_sum!(2, usd; 5, thb)),
+                (recipient_keys[2], test_sum!(4, usd)),
+            ],
+            "payday",
+            |index| extras[index],
+        );
+        assert_eq!(actual, TransactionIndex(0));
+        assert_eq!(book.transactions[0].extra, "payday");
+        assert_eq!(
+            book.transactions[0]
+                .moves
+                .iter()
+                .map(|move_| move_.extra)
+                .collect::<Vec<_>>(),
+            extras,
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                payroll_key,
+                TransactionIndex(0),
+            ),
+            TestBalance::default() - &test_sum!(7, usd; 5, thb),
+        );
+        [
+            test_sum!(1, usd),
+            test_sum!(2, usd; 5, thb),
+            test_sum!(4, usd),
+        ]
+        .iter()
+        .zip(recipient_keys.iter())
+        .for_each(|(sum, account_key)| {
+            assert_eq!(
+                book.account_balance_at_transaction::<i128>(
+                    account_key,
+                    TransactionIndex(0),
+                ),
+                TestBalance::default() + sum,
+            );
+        });
+    }
+    #[test]
+    fn insert_transaction_with_moves() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "earlier");
+        let actual = book.insert_transaction_with_moves(
+            TransactionIndex(0),
+            "posted",
+            vec![
+                (account_keys[0], account_keys[1], test_sum!(3, usd), "a"),
+                (account_keys[1], account_keys[2], test_sum!(1, usd), "b"),
+            ],
+        );
+        assert_eq!(actual, TransactionIndex(0));
+        assert_eq!(book.transactions[0].extra, "posted");
+        assert_eq!(book.transactions[1].extra, "earlier");
+        assert_eq!(
+            book.transactions[0]
+                .moves
+                .iter()
+                .map(|move_| move_.extra)
+                .collect::<Vec<_>>(),
+            ["a", "b"],
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_keys[1],
+                TransactionIndex(0),
+            ),
+            TestBalance::default() + &test_sum!(2, usd),
+        );
+        assert!(book.undo());
+        assert_eq!(book.transactions.len(), 1);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn insert_transaction_with_moves_panic_account_not_found() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        book.accounts.remove(account_keys[2]);
+        book.insert_transaction_with_moves(
+            TransactionIndex(0),
+            "",
+            vec![
+                (account_keys[0], account_keys[1], test_sum!(), ""),
+                (account_keys[1], account_keys[2], test_sum!(), ""),
+            ],
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Empty sums are rejected.")]
+    fn insert_transaction_with_moves_panic_empty_sum() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        let usd = "USD";
+        book.set_reject_empty_sums(true);
+        book.insert_transaction_with_moves(
+            TransactionIndex(0),
+            "",
+            vec![
+                (account_keys[0], account_keys[1], test_sum!(1, usd), ""),
+                (account_keys[1], account_keys[2], test_sum!(), ""),
+            ],
+        );
+    }
+    #[test]
+    fn insert_move() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "a",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "b",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "c",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(2),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "d",
+        );
+        assert_eq!(
+            book.transactions[0]
+                .moves
+                .iter()
+                .map(|move_| move_.extra)
+                .collect::<Vec<_>>(),
+            vec!["b", "c", "d", "a"],
+        );
+    }
+    #[test]
+    fn with_transaction() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        let mut expected = TestBook {
+            account_keys: book.account_keys.clone(),
+            accounts: book.accounts.clone(),
+            ..TestBook::default()
+        };
+        expected.insert_transaction(TransactionIndex(0), "a");
+        expected.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(1, usd),
+            "a0",
+        );
+        expected.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_b_key,
+            account_c_key,
+            test_sum!(2, usd),
+            "a1",
+        );
+        expected.insert_transaction(TransactionIndex(1), "b");
+        let actual = book
+            .with_transaction("a", |transaction| {
+                transaction
+                    .push_move(
+                        account_a_key,
+                        account_b_key,
+                        test_sum!(1, usd),
+                        "a0",
+                    )
+                    .push_move(
+                        account_b_key,
+                        account_c_key,
+                        test_sum!(2, usd),
+                        "a1",
+                    );
+            })
+            .with_transaction("b", |_| {});
+        assert!(actual
+            .transactions()
+            .map(|(_, transaction)| transaction)
+            .eq(expected.transactions().map(|(_, transaction)| transaction)));
+    }
+    #[test]
+    fn insert_move_borrowed_keys() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let debit_key_ref = &debit_key;
+        let credit_key_ref = &credit_key;
+        let usd = "USD";
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key_ref,
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            debit_key_ref,
+            credit_key,
+            test_sum!(2, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(2),
+            debit_key_ref,
+            credit_key_ref,
+            test_sum!(4, usd),
+            "",
+        );
+        assert!(book.transactions[0].moves.iter().all(|move_| {
+            move_.debit_account_key == debit_key
+                && move_.credit_account_key == credit_key
+        }));
+        assert_eq!(
+            book.account_balance_after::<i128>(
+                credit_key_ref,
+                TransactionIndex(0)
+            ),
+            book.account_balance_after::<i128>(credit_key, TransactionIndex(0)),
+        );
+    }
+    #[test]
+    fn insert_move_sided() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_move_sided(
+            TransactionIndex(0),
+            MoveIndex(0),
+            [(Debit, debit_key), (Credit, credit_key)],
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_move_sided(
+            TransactionIndex(0),
+            MoveIndex(1),
+            [(Credit, credit_key), (Debit, debit_key)],
+            test_sum!(2, usd),
+            "",
+        );
+        assert!(book.transactions[0].moves.iter().all(|move_| {
+            move_.debit_account_key == debit_key
+                && move_.credit_account_key == credit_key
+        }));
+    }
+    #[test]
+    #[should_panic(expected = "Both accounts are on the same side.")]
+    fn insert_move_sided_panic_same_side() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        book.insert_move_sided(
+            TransactionIndex(0),
+            MoveIndex(0),
+            [(Debit, account_a_key), (Debit, account_b_key)],
+            test_sum!(),
+            "",
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "index out of bounds: the len is 1 but the index is 1"
+    )]
+    fn reverse_move_panic_source_move_out_of_bounds() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "",
+        );
+        book.reverse_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            TransactionIndex(0),
+            MoveIndex(0),
+            "",
+        );
+    }
+    #[test]
+    fn reverse_move() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let other_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            other_key,
+            credit_key,
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            debit_key,
+            credit_key,
+            test_sum!(3, usd; 2, thb),
+            "wrong",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            debit_key,
+            other_key,
+            test_sum!(5, usd),
+            "",
+        );
+        let actual = book.reverse_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            TransactionIndex(1),
+            MoveIndex(0),
+            "reversal",
+        );
+        assert_eq!(actual, MoveIndex(0));
+        let reversal = &book.transactions[1].moves[0];
+        assert_eq!(reversal.debit_account_key, credit_key);
+        assert_eq!(reversal.credit_account_key, debit_key);
+        assert_eq!(reversal.sum, test_sum!(3, usd; 2, thb));
+        assert_eq!(reversal.extra, "reversal");
+        let net = |book: &TestBook, account_key| {
+            let mut net = TestBalance::default();
+            [(0, 1), (1, 0)].iter().for_each(|(transaction, move_)| {
+                let move_ = &book.transactions[*transaction].moves[*move_];
+                if move_.debit_account_key == account_key {
+                    net -= &move_.sum;
+                } else if move_.credit_account_key == account_key {
+                    net += &move_.sum;
+                }
+            });
+            net
+        };
+        assert_eq!(
+            net(&book, debit_key),
+            TestBalance::default() + &test_sum!(0, usd; 0, thb)
+        );
+        assert_eq!(
+            net(&book, credit_key),
+            TestBalance::default() + &test_sum!(0, usd; 0, thb)
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn reserve_moves_panic_transaction_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.reserve_moves(TransactionIndex(0), 1);
+    }
+    #[test]
+    fn reserve_moves() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.reserve_moves(TransactionIndex(0), 1000);
+        assert!(book.transactions[0].moves.capacity() >= 1000);
+        (0..1000).for_each(|index| {
+            book.insert_move(
+                TransactionIndex(0),
+                MoveIndex(index),
+                debit_key,
+                credit_key,
+                test_sum!(),
+                "",
+            );
+        });
+        assert_eq!(book.transactions[0].moves.len(), 1000);
+    }
+    #[test]
+    fn shrink_to_fit() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let usd = "USD";
+        (0..100).for_each(|index| {
+            book.insert_transaction(TransactionIndex(index), "");
+            (0..10).for_each(|move_index| {
+                book.insert_move(
+                    TransactionIndex(index),
+                    MoveIndex(move_index),
+                    debit_key,
+                    credit_key,
+                    test_sum!(1, usd),
+                    "",
+                );
+            });
+        });
+        book.remove_transactions_matching(|_| true);
+        book.insert_transaction(TransactionIndex(0), "");
+        (0..3).for_each(|move_index| {
+            book.insert_move(
+                TransactionIndex(0),
+                MoveIndex(move_index),
+                debit_key,
+                credit_key,
+                test_sum!(1, usd),
+                "",
+            );
+        });
+        book.reserve_moves(TransactionIndex(0), 100);
+        book.shrink_to_fit();
+        assert!(book.transactions.capacity() < 100);
+        assert!(book.transactions[0].moves.capacity() < 100);
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                credit_key,
+                TransactionIndex(0),
+            ),
+            TestBalance::default() + &test_sum!(3, usd),
+        );
+    }
+    #[test]
+    fn balance_variants_agree_on_random_books() {
+        let units = ["a", "b", "c"];
+        let mut state = 0x2545_f491_u64;
+        let mut random = |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+        (0..20).for_each(|_| {
+            let mut book = TestBook::default();
+            let account_keys = book.insert_accounts(vec![""; 4]);
+            let mut expected = vec![Vec::new(); account_keys.len()];
+            (0..10).for_each(|transaction_index| {
+                book.insert_transaction(TransactionIndex(transaction_index), "");
+                (0..random(4)).for_each(|move_index| {
+                    let debit = random(account_keys.len());
+                    let credit = (debit + 1 + random(account_keys.len() - 1))
+                        % account_keys.len();
+                    let mut sum = test_sum!();
+                    units.iter().for_each(|unit| {
+                        if random(2) == 0 {
+                            sum.set_amount_for_unit(random(100) as u64, unit);
+                        }
+                    });
+                    book.insert_move(
+                        TransactionIndex(transaction_index),
+                        MoveIndex(move_index),
+                        account_keys[debit],
+                        account_keys[credit],
+                        sum,
+                        "",
+                    );
+                });
+                account_keys.iter().enumerate().for_each(|(index, key)| {
+                    let amounts = units
+                        .iter()
+                        .map(|unit| {
+                            book.transactions()
+                                .take(transaction_index + 1)
+                                .flat_map(|(_, transaction)| transaction.moves())
+                                .map(|(_, move_)| {
+                                    let amount = move_
+                                        .sum()
+                                        .unit_amount(unit)
+                                        .map_or(0, |amount| *amount as i128);
+                                    if move_.side_key(Debit) == *key {
+                                        -amount
+                                    } else if move_.side_key(Credit) == *key {
+                                        amount
+                                    } else {
+                                        0
+                                    }
+                                })
+                                .sum::<i128>()
+                        })
+                        .collect::<Vec<_>>();
+                    expected[index].push(amounts);
+                });
+            });
+            account_keys.iter().enumerate().for_each(|(index, key)| {
+                expected[index].iter().enumerate().for_each(
+                    |(transaction_index, amounts)| {
+                        let transaction_index = TransactionIndex(transaction_index);
+                        let balance = book.account_balance_at_transaction::<i128>(
+                            key,
+                            transaction_index,
+                        );
+                        let actual = units
+                            .iter()
+                            .map(|unit| balance.get_or_default(unit))
+                            .collect::<Vec<_>>();
+                        assert_eq!(&actual, amounts);
+                        let actual = units
+                            .iter()
+                            .map(|unit| {
+                                book.account_unit_balance_at_transaction::<i128>(
+                                    key,
+                                    unit,
+                                    transaction_index,
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        assert_eq!(&actual, amounts);
+                        let actual = book
+                            .account_unit_balances_at_transaction::<i128>(
+                                key,
+                                &units,
+                                transaction_index,
+                            );
+                        assert_eq!(&actual, amounts);
+                        let mut balance = TestBalance::default();
+                        book.account_balance_at_transaction_into(
+                            key,
+                            transaction_index,
+                            &mut balance,
+                        );
+                        let actual = units
+                            .iter()
+                            .map(|unit| balance.get_or_default(unit))
+                            .collect::<Vec<_>>();
+                        assert_eq!(&actual, amounts);
+                    },
+                );
+            });
+        });
+    }
+    #[test]
+    fn account_balance_seeded() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Number(i128);
+        impl Default for Number {
+            fn default() -> Self {
+                Number(1)
+            }
+        }
+        impl From<u64> for Number {
+            fn from(amount: u64) -> Self {
+                Number(amount.into())
+            }
+        }
+        impl std::ops::Add for Number {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                Number(self.0 + rhs.0)
+            }
+        }
+        impl std::ops::Sub for Number {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                Number(self.0 - rhs.0)
+            }
+        }
+        let usd = "USD";
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 2]);
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_keys[0],
+            account_keys[1],
+            test_sum!(3, usd),
+            "",
+        );
+        let pitfall = book.account_balance_at_transaction::<Number>(
+            account_keys[1],
+            TransactionIndex(0),
+        );
+        assert_eq!(pitfall.unit_amount(&usd), Some(&Number(4)));
+        let seeded = book.account_balance_seeded(
+            account_keys[1],
+            TransactionIndex(0),
+            Number(0),
+        );
+        assert_eq!(seeded.unit_amount(&usd), Some(&Number(3)));
+        let seeded = book.account_balance_seeded(
+            account_keys[0],
+            TransactionIndex(0),
+            Number(0),
+        );
+        assert_eq!(seeded.unit_amount(&usd), Some(&Number(-3)));
+        assert_eq!(
+            book.account_balance_seeded(
+                account_keys[1],
+                TransactionIndex(0),
+                0_i128,
+            ),
+            book.account_balance_at_transaction(
+                account_keys[1],
+                TransactionIndex(0),
+            ),
+        );
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut book = TestBook::default();
+        let removed_key = book.insert_account("removed");
+        book.undo();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        book.insert_transaction(TransactionIndex(0), "a");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(3, usd; 4, thb),
+            "withdrawal",
+        );
+        book.insert_transaction(TransactionIndex(1), "b");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(1, usd),
+            "deposit",
+        );
+        book.set_reject_empty_sums(true);
+        let json = serde_json::to_string(&book).unwrap();
+        let deserialized: super::Book<String, u64, String, String, String> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+        assert!(deserialized.reject_empty_sums);
+        let without_flag = json.replace(",\"reject_empty_sums\":true", "");
+        assert_ne!(without_flag, json);
+        assert!(
+            !serde_json::from_str::<
+                super::Book<String, u64, String, String, String>,
+            >(&without_flag)
+            .unwrap()
+            .reject_empty_sums
+        );
+        assert_eq!(deserialized.get_account(wallet_key), "wallet");
+        assert_eq!(deserialized.get_account(bank_key), "bank");
+        assert_eq!(
+            deserialized
+                .accounts()
+                .map(|(account_key, _)| account_key)
+                .collect::<Vec<_>>(),
+            [wallet_key, bank_key],
+        );
+        [wallet_key, bank_key].iter().for_each(|account_key| {
+            [TransactionIndex(0), TransactionIndex(1)].iter().for_each(
+                |transaction_index| {
+                    let expected = book
+                        .account_balance_at_transaction::<i128>(
+                            account_key,
+                            *transaction_index,
+                        )
+                        .map_units(|unit| unit.to_string());
+                    let actual = deserialized
+                        .account_balance_at_transaction::<i128>(
+                            account_key,
+                            *transaction_index,
+                        );
+                    assert_eq!(actual, expected);
+                },
+            );
+        });
+        let mut deserialized = deserialized;
+        let new_key = deserialized.insert_account("new".to_string());
+        assert!(![removed_key, wallet_key, bank_key].contains(&new_key));
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_units_inserted_after_moves() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 2]);
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_keys[0],
+            account_keys[1],
+            test_sum!(1, thb),
+            "",
+        );
+        book.insert_unit(usd);
+        let json = serde_json::to_string(&book).unwrap();
+        let deserialized: super::Book<String, u64, String, String, String> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+        assert_eq!(deserialized.units().collect::<Vec<_>>(), ["USD"]);
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_dangling_account() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 2]);
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_keys[0],
+            account_keys[1],
+            test_sum!(),
+            "",
+        );
+        book.accounts.remove(account_keys[1]);
+        let json = serde_json::to_string(&book).unwrap();
+        let error = serde_json::from_str::<
+            super::Book<String, u64, String, String, String>,
+        >(&json)
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "move 0 of transaction 0 refers to missing account {}",
+                account_keys[1],
+            ),
+        );
+    }
+    #[test]
+    fn synthetic() {
+        let book = TestBook::synthetic(3, 4, 2);
+        assert_eq!(book.accounts().count(), 3);
+        assert_eq!(book.transactions().len(), 4);
+        assert!(book
+            .transactions()
+            .all(|(_, transaction)| transaction.moves().count() == 2));
+        let (_, transaction) = book.transactions().nth(1).unwrap();
+        let (_, move_) = transaction.moves().nth(1).unwrap();
+        let unit = "";
+        assert_eq!(move_.sum(), &test_sum!(4, unit));
+        let total = book
+            .balances_at_transaction::<i128>(TransactionIndex(3))
+            .values()
+            .map(|balance| balance.get_or_default(&""))
+            .sum::<i128>();
+        assert_eq!(total, 0);
+        assert!(book.undo_stack.is_empty());
+        assert!(TestBook::synthetic(3, 4, 2).diff(&book).is_empty());
+    }
+    #[test]
+    #[should_panic(expected = "At least 2 accounts are required for moves.")]
+    fn synthetic_panic_without_enough_accounts() {
+        TestBook::synthetic(1, 1, 1);
+    }
+    #[test]
+    fn accounts() {
+        let mut book = TestBook::default();
+        assert!(book.accounts().next().is_none());
+        let account_a_key = book.insert_account("a");
+        let account_b_key = book.insert_account("b");
+        let expected = vec![(account_a_key, &"a"), (account_b_key, &"b")];
+        let actual = book.accounts().collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn account_count_and_transaction_count() {
+        let mut book = TestBook::default();
+        assert_eq!(book.account_count(), 0);
+        assert_eq!(book.transaction_count(), 0);
+        let account_keys = book.insert_accounts(vec!["a", "b", "c"]);
+        book.remove_account(account_keys[1]);
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_transaction(TransactionIndex(1), "");
+        assert_eq!(book.account_count(), 2);
+        assert_eq!(book.transaction_count(), 2);
+    }
+    #[test]
+    fn accounts_enumerated() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec!["a", "b", "c"]);
+        let expected = vec![
+            (0, account_keys[0], &"a"),
+            (1, account_keys[1], &"b"),
+            (2, account_keys[2], &"c"),
+        ];
+        let actual = book.accounts_enumerated().collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn nth_account_key_and_account_ordinal() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec!["a", "b", "c", "d"]);
+        account_keys
+            .iter()
+            .enumerate()
+            .for_each(|(n, account_key)| {
+                assert_eq!(book.nth_account_key(n), Some(*account_key));
+                assert_eq!(book.account_ordinal(*account_key), Some(n));
+            });
+        assert_eq!(book.nth_account_key(4), None);
+        book.remove_account(account_keys[1]);
+        assert_eq!(book.account_ordinal(account_keys[1]), None);
+        [account_keys[0], account_keys[2], account_keys[3]]
+            .iter()
+            .enumerate()
+            .for_each(|(n, account_key)| {
+                assert_eq!(book.nth_account_key(n), Some(*account_key));
+                assert_eq!(book.account_ordinal(*account_key), Some(n));
+            });
+        assert_eq!(book.nth_account_key(3), None);
+    }
+    #[test]
+    fn transactions() {
+        let mut book = TestBook::default();
+        assert_eq!(book.transactions().len(), 0);
+        book.insert_transaction(TransactionIndex(0), "a");
+        book.insert_transaction(TransactionIndex(1), "b");
+        book.insert_transaction(TransactionIndex(2), "c");
+        assert_eq!(book.transactions().len(), 3);
+        assert_eq!(
+            book.transactions()
+                .map(|(index, transaction)| (index.0, *transaction.extra()))
+                .collect::<Vec<_>>(),
+            vec![(0, "a"), (1, "b"), (2, "c")],
+        );
+        assert_eq!(
+            book.transactions()
+                .rev()
+                .map(|(index, transaction)| (index.0, *transaction.extra()))
+                .collect::<Vec<_>>(),
+            vec![(2, "c"), (1, "b"), (0, "a")],
+        );
+        let (index, transaction) = book.transactions().nth_back(0).unwrap();
+        assert_eq!((index.0, *transaction.extra()), (2, "c"));
+        let mut transactions = book.transactions();
+        transactions.next();
+        assert_eq!(transactions.len(), 2);
+    }
+    #[test]
+    fn named_iterators() {
+        struct Cursor<'a> {
+            transactions:
+                Transactions<'a, &'static str, u64, &'static str, &'static str>,
+        }
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec!["a", "b"]);
+        book.insert_transaction(TransactionIndex(0), "x");
+        book.insert_transaction(TransactionIndex(1), "y");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_keys[0],
+            account_keys[1],
+            test_sum!(),
+            "m",
+        );
+        let mut cursor = Cursor {
+            transactions: book.transactions(),
+        };
+        let (index, transaction) = cursor.transactions.next().unwrap();
+        assert_eq!((index.0, *transaction.extra()), (0, "x"));
+        let (index, transaction) = cursor.transactions.next().unwrap();
+        assert_eq!((index.0, *transaction.extra()), (1, "y"));
+        assert!(cursor.transactions.next().is_none());
+        let mut moves: Moves<&'static str, u64, &'static str> =
+            transaction.moves();
+        assert_eq!(moves.next().unwrap().0, MoveIndex(0));
+        assert!(moves.next().is_none());
+        let accounts: Accounts<&'static str> = book.accounts();
+        assert_eq!(
+            accounts.map(|(_, extra)| *extra).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+    }
+    #[test]
+    fn transactions_where() {
+        let mut book = TestBook::default();
+        ["2020-12-30", "2021-01-02", "2021-01-15", "2021-02-01"]
+            .iter()
+            .enumerate()
+            .for_each(|(index, extra)| {
+                book.insert_transaction(TransactionIndex(index), extra);
+            });
+        let actual = book
+            .transactions_where(|date| date.starts_with("2021-01"))
+            .map(|(index, transaction)| (index.0, *transaction.extra()))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, vec![(1, "2021-01-02"), (2, "2021-01-15")]);
+    }
+    #[test]
+    fn min_and_max_transaction_by() {
+        let mut book = TestBook::default();
+        assert_eq!(book.min_transaction_by(|date| *date), None);
+        assert_eq!(book.max_transaction_by(|date| *date), None);
+        ["2021-01-15", "2020-12-30", "2021-02-01", "2021-01-02"]
+            .iter()
+            .enumerate()
+            .for_each(|(index, extra)| {
+                book.insert_transaction(TransactionIndex(index), extra);
+            });
+        assert_eq!(
+            book.min_transaction_by(|date| *date),
+            Some(TransactionIndex(1)),
+        );
+        assert_eq!(
+            book.max_transaction_by(|date| *date),
+            Some(TransactionIndex(2)),
+        );
+        assert_eq!(
+            book.max_transaction_by(|date| &date[..4]),
+            Some(TransactionIndex(3)),
+        );
+    }
+    #[test]
+    fn group_transactions_by() {
+        let mut book = TestBook::default();
+        assert!(book.group_transactions_by(|date| *date).is_empty());
+        ["2021-01-15", "2020-12-30", "2021-02-01", "2021-01-02"]
+            .iter()
+            .enumerate()
+            .for_each(|(index, extra)| {
+                book.insert_transaction(TransactionIndex(index), extra);
+            });
+        let actual = book.group_transactions_by(|date| &date[..7]);
+        let expected = maplit::btreemap! {
+            "2020-12" => vec![TransactionIndex(1)],
+            "2021-01" => vec![TransactionIndex(0), TransactionIndex(3)],
+            "2021-02" => vec![TransactionIndex(2)],
+        };
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn moves() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_transaction(TransactionIndex(2), "");
+        [(0, 0, "a"), (0, 1, "b"), (2, 0, "c")].iter().for_each(
+            |(transaction_index, move_index, extra)| {
+                book.insert_move(
+                    TransactionIndex(*transaction_index),
+                    MoveIndex(*move_index),
+                    wallet_key,
+                    bank_key,
+                    test_sum!(),
+                    extra,
+                );
+            },
+        );
+        let actual = book
+            .moves()
+            .map(|(transaction_index, move_index, move_)| {
+                (transaction_index, move_index, *move_.extra())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (TransactionIndex(0), MoveIndex(0), "a"),
+                (TransactionIndex(0), MoveIndex(1), "b"),
+                (TransactionIndex(2), MoveIndex(0), "c"),
+            ],
+        );
+        let usd = "USD";
+        let locations = book
+            .moves()
+            .map(|(transaction_index, move_index, _)| {
+                (transaction_index, move_index)
+            })
+            .collect::<Vec<_>>();
+        locations
+            .iter()
+            .for_each(|(transaction_index, move_index)| {
+                book.set_move_sum(
+                    *transaction_index,
+                    *move_index,
+                    test_sum!(1, usd),
+                );
+            });
+        assert!(book
+            .moves()
+            .all(|(_, _, move_)| *move_.sum() == test_sum!(1, usd)));
+    }
+    #[test]
+    fn account_moves() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let shop_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_transaction(TransactionIndex(1), "");
+        [
+            (0, 0, wallet_key, bank_key, "a"),
+            (0, 1, bank_key, shop_key, "b"),
+            (1, 0, shop_key, wallet_key, "c"),
+        ]
+        .iter()
+        .for_each(
+            |(transaction_index, move_index, debit, credit, extra)| {
+                book.insert_move(
+                    TransactionIndex(*transaction_index),
+                    MoveIndex(*move_index),
+                    debit,
+                    credit,
+                    test_sum!(),
+                    extra,
+                );
+            },
+        );
+        let actual = book
+            .account_moves(wallet_key)
+            .map(|(transaction_index, move_index, side, move_)| {
+                (transaction_index, move_index, side, *move_.extra())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (TransactionIndex(0), MoveIndex(0), Debit, "a"),
+                (TransactionIndex(1), MoveIndex(0), Credit, "c"),
+            ],
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_moves_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        let _ = book.account_moves(account_key);
+    }
+    #[test]
+    fn moves_for_unit() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        [
+            test_sum!(1, usd),
+            test_sum!(2, thb),
+            test_sum!(3, usd; 4, thb),
+        ]
+        .iter()
+        .enumerate()
+        .for_each(|(index, sum)| {
+            book.insert_move(
+                TransactionIndex(0),
+                MoveIndex(index),
+                wallet_key,
+                bank_key,
+                sum.clone(),
+                "",
+            );
+        });
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(5, usd),
+            "",
+        );
+        let actual = book
+            .moves_for_unit(&usd)
+            .map(|(transaction_index, move_index, move_, amount)| {
+                (
+                    transaction_index,
+                    move_index,
+                    move_.side_key(Debit),
+                    *amount,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (TransactionIndex(0), MoveIndex(0), wallet_key, 1),
+                (TransactionIndex(0), MoveIndex(2), wallet_key, 3),
+                (TransactionIndex(1), MoveIndex(0), bank_key, 5),
+            ],
+        );
+    }
+    #[test]
+    fn get_account() {
+        let mut book = TestBook::default();
+        book.insert_account("");
+        let account_key = book.insert_account("!");
+        book.insert_account("");
+        let account = book.get_account(account_key);
+        assert_eq!(*account, "!");
+    }
+    #[test]
+    fn try_get_account() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("!");
+        assert_eq!(book.try_get_account(account_key), Some(&"!"));
+        book.accounts.remove(account_key);
+        assert_eq!(book.try_get_account(account_key), None);
+    }
+    #[test]
+    fn get_transaction_and_move() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "!");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "?",
+        );
+        assert_eq!(
+            book.get_transaction(TransactionIndex(0))
+                .map(|transaction| *transaction.extra()),
+            Some("!"),
+        );
+        assert!(book.get_transaction(TransactionIndex(1)).is_none());
+        assert_eq!(
+            book.get_move(TransactionIndex(0), MoveIndex(0))
+                .map(|move_| *move_.extra()),
+            Some("?"),
+        );
+        assert!(book.get_move(TransactionIndex(0), MoveIndex(1)).is_none());
+        assert!(book.get_move(TransactionIndex(1), MoveIndex(0)).is_none());
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn map_account_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.map_account(account_key, |_| ());
+    }
+    #[test]
+    fn map_account() {
+        let mut book = TestBook::default();
+        book.insert_account("");
+        let account_key = book.insert_account("savings");
+        assert_eq!(book.map_account(account_key, |account| account.len()), 7);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn assert_has_account() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.assert_has_account(account_key);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_balance_at_transaction_account_not_found() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_balance_at_transaction::<i128>(
+            account_key,
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn account_balance_after_panic_empty_book() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.account_balance_after::<i128>(account_key, TransactionIndex(0));
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn account_balance_before_panic_empty_book() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.account_balance_before::<i128>(account_key, TransactionIndex(0));
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn balance_panic_empty_book() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.balance(account_key, TransactionIndex(0));
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn account_balance_at_transaction_panic_empty_book() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.account_balance_at_transaction::<i128>(
+            account_key,
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 2) should be < len (is 2)"
+    )]
+    fn account_balance_at_transaction_panic_transaction_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_transaction(TransactionIndex(1), "");
+        book.account_balance_at_transaction::<i128>(
+            account_key,
+            TransactionIndex(2),
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 1) should be < len (is 1)"
+    )]
+    fn account_balance_before_panic_transaction_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.account_balance_before::<i128>(account_key, TransactionIndex(1));
+    }
+    #[cfg(feature = "num-traits")]
+    #[test]
+    fn checked_account_balance_at_transaction() {
+        use super::{BalanceOverflowError, Book, Sum};
+        use maplit::btreemap;
+        let mut book = Book::<&str, i8, (), (), ()>::default();
+        let debit_key = book.insert_account(());
+        let credit_key = book.insert_account(());
+        let usd = "USD";
+        let thb = "THB";
+        (0..3).for_each(|transaction_index| {
+            book.insert_transaction(TransactionIndex(transaction_index), ());
+            book.insert_move(
+                TransactionIndex(transaction_index),
+                MoveIndex(0),
+                debit_key,
+                credit_key,
+                Sum(btreemap! { usd => 1, thb => 50 }),
+                (),
+            );
+        });
+        assert_eq!(
+            book.checked_account_balance_at_transaction::<i8>(
+                credit_key,
+                TransactionIndex(1),
+            ),
+            Ok(Balance(btreemap! { usd => 2, thb => 100 })),
+        );
+        let error = book
+            .checked_account_balance_at_transaction::<i8>(
+                credit_key,
+                TransactionIndex(2),
+            )
+            .unwrap_err();
+        assert_eq!(*error.unit(), thb);
+        assert_eq!(error.transaction_index(), TransactionIndex(2));
+        assert_eq!(
+            error.to_string(),
+            "balance overflowed in unit \"THB\" at transaction 2",
+        );
+        assert_eq!(
+            book.checked_account_balance_at_transaction::<i8>(
+                debit_key,
+                TransactionIndex(2),
+            ),
+            Err(BalanceOverflowError {
+                unit: thb,
+                transaction_index: TransactionIndex(2),
+            }),
+        );
+        assert_eq!(
+            book.checked_account_balance_at_transaction::<i16>(
+                debit_key,
+                TransactionIndex(2),
+            ),
+            Ok(Balance(btreemap! { usd => -3, thb => -150 })),
+        );
+    }
+    #[test]
+    fn account_balance_at_transaction_counted() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_b_key,
+            account_c_key,
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_c_key,
+            account_a_key,
+            test_sum!(2, usd),
+            "",
+        );
+        assert_eq!(
+            book.account_balance_at_transaction_counted::<i128>(
+                account_a_key,
+                TransactionIndex(0)
+            ),
+            (TestBalance::default() - &test_sum!(3, usd), 1),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction_counted::<i128>(
+                account_a_key,
+                TransactionIndex(1)
+            ),
+            (TestBalance::default() - &test_sum!(1, usd), 2),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction_counted::<i128>(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+            (TestBalance::default() + &test_sum!(2, usd), 2),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction_counted::<i128>(
+                account_c_key,
+                TransactionIndex(0)
+            ),
+            (TestBalance::default() + &test_sum!(1, usd), 1),
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_balance_at_transaction_counted_account_not_found() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_balance_at_transaction_counted::<i128>(
+            account_key,
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    fn account_balance_at_transaction() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd),
+            "",
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_a_key,
+                TransactionIndex(0)
+            ),
+            TestBalance::default() - &test_sum!(3, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+            TestBalance::default() + &test_sum!(3, usd),
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(4, usd),
+            "",
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_a_key,
+                TransactionIndex(0)
+            ),
+            TestBalance::default() - &test_sum!(3, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+            TestBalance::default() + &test_sum!(3, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_a_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() - &test_sum!(7, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(7, usd),
+        );
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(1, usd),
+            "",
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_a_key,
+                TransactionIndex(0)
+            ),
+            TestBalance::default() - &test_sum!(1, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+            TestBalance::default() + &test_sum!(1, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_a_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() - &test_sum!(4, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(4, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_a_key,
+                TransactionIndex(2)
+            ),
+            TestBalance::default() - &test_sum!(8, usd),
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_b_key,
+                TransactionIndex(2)
+            ),
+            TestBalance::default() + &test_sum!(8, usd),
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_balance_after_account_not_found() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_balance_after::<i128>(account_key, TransactionIndex(0));
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_balance_before_account_not_found() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_balance_before::<i128>(account_key, TransactionIndex(0));
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key")]
+    fn account_unit_balance_at_transaction_account_not_found() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_unit_balance_at_transaction::<i128>(
+            account_key,
+            &"USD",
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn account_unit_balance_at_transaction_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.account_unit_balance_at_transaction::<i128>(
+            account_key,
+            &"USD",
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    fn account_unit_balance_at_transaction() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd; 2, thb),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_b_key,
+            account_a_key,
+            test_sum!(5, usd),
+            "",
+        );
+        [usd, thb].iter().for_each(|unit| {
+            [account_a_key, account_b_key]
+                .iter()
+                .for_each(|account_key| {
+                    (0..2).for_each(|transaction_index| {
+                        assert_eq!(
+                            book.account_unit_balance_at_transaction::<i128>(
+                                account_key,
+                                unit,
+                                TransactionIndex(transaction_index),
+                            ),
+                            book.account_balance_at_transaction::<i128>(
+                                account_key,
+                                TransactionIndex(transaction_index),
+                            )
+                            .get_or_default(unit),
+                        );
+                    });
+                });
+        });
+        assert_eq!(
+            book.account_unit_balance_at_transaction::<i128>(
+                account_b_key,
+                &usd,
+                TransactionIndex(1),
+            ),
+            -2,
+        );
+        assert_eq!(
+            book.account_unit_balance_at_transaction::<i128>(
+                account_b_key,
+                &ils,
+                TransactionIndex(1),
+            ),
+            0,
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn accounts_sorted_by_unit_balance_transaction_out_of_bounds() {
+        let book = TestBook::default();
+        book.accounts_sorted_by_unit_balance::<i128>(
+            &"USD",
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_unit_balances_at_transaction_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.insert_transaction(TransactionIndex(0), "");
+        book.account_unit_balances_at_transaction::<i128>(
+            account_key,
+            &[],
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    fn account_unit_balances_at_transaction() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd; 4, thb; 5, ils),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(1, thb),
+            "",
+        );
+        let units = [thb, usd];
+        let actual = book.account_unit_balances_at_transaction::<i128>(
+            wallet_key,
+            &units,
+            TransactionIndex(1),
+        );
+        let balance = book.account_balance_at_transaction::<i128>(
+            wallet_key,
+            TransactionIndex(1),
+        );
+        assert_eq!(
+            actual,
+            units
+                .iter()
+                .map(|unit| balance.get_or_default(unit))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(actual, vec![-3, -3]);
+    }
+    #[test]
+    fn accounts_sorted_by_unit_balance() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let account_d_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd; 100, thb),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_a_key,
+            account_c_key,
+            test_sum!(5, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_c_key,
+            account_a_key,
+            test_sum!(20, usd),
+            "",
+        );
+        assert_eq!(
+            book.accounts_sorted_by_unit_balance::<i128>(
+                &usd,
+                TransactionIndex(0)
+            ),
+            vec![
+                (account_c_key, 5),
+                (account_b_key, 3),
+                (account_d_key, 0),
+                (account_a_key, -8),
+            ],
+        );
+        assert_eq!(
+            book.accounts_sorted_by_unit_balance::<i128>(
+                &usd,
+                TransactionIndex(1)
+            ),
+            vec![
+                (account_a_key, 12),
+                (account_b_key, 3),
+                (account_d_key, 0),
+                (account_c_key, -15),
+            ],
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key")]
+    fn account_turnover_account_not_found() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_turnover::<i128>(account_key, TransactionIndex(0));
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn account_turnover_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.account_turnover::<i128>(account_key, TransactionIndex(0));
+    }
+    #[test]
+    fn account_turnover() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd; 2, thb),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_b_key,
+            account_a_key,
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(2), "");
+        book.insert_move(
+            TransactionIndex(2),
+            MoveIndex(0),
+            account_b_key,
+            account_a_key,
+            test_sum!(5, usd),
+            "",
+        );
+        let (debits, credits) =
+            book.account_turnover::<i128>(account_b_key, TransactionIndex(1));
+        assert_eq!(debits, TestBalance::default() + &test_sum!(1, usd));
+        assert_eq!(
+            credits,
+            TestBalance::default() + &test_sum!(3, usd; 2, thb)
+        );
+        assert_eq!(
+            credits.diff(&debits),
+            book.account_balance_after::<i128>(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn flow_between_transaction_out_of_bounds() {
+        let book = TestBook::default();
+        book.flow_between::<i128>(
+            &HashSet::new(),
+            &HashSet::new(),
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    fn account_sum_at_transaction() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd; 4, thb),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            bank_key,
+            wallet_key,
+            test_sum!(1, usd; 4, thb),
+            "",
+        );
+        assert_eq!(
+            book.account_sum_at_transaction(bank_key, TransactionIndex(0)),
+            Ok(test_sum!(2, usd; 0, thb)),
+        );
+        let error = book
+            .account_sum_at_transaction(wallet_key, TransactionIndex(0))
+            .unwrap_err();
+        assert_eq!(error.units(), &[usd]);
+        assert_eq!(error.to_string(), "balance is negative in units [\"USD\"]");
+    }
+    #[test]
+    fn unit_balance_history_for_accounts() {
+        let mut book = TestBook::default();
+        let cash_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let expenses_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        [
+            (cash_key, expenses_key, test_sum!(3, usd)),
+            (bank_key, cash_key, test_sum!(10, usd)),
+            (expenses_key, bank_key, test_sum!(2, usd; 7, thb)),
+            (expenses_key, cash_key, test_sum!(7, thb)),
+        ]
+        .iter()
+        .enumerate()
+        .for_each(|(index, (debit_key, credit_key, sum))| {
+            book.insert_transaction(TransactionIndex(index), "");
+            book.insert_move(
+                TransactionIndex(index),
+                MoveIndex(0),
+                debit_key,
+                credit_key,
+                sum.clone(),
+                "",
+            );
+        });
+        let cash: HashSet<_> = vec![cash_key].into_iter().collect();
+        let actual = book
+            .unit_balance_history_for_accounts::<i128>(&cash, &usd)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            [
+                (TransactionIndex(0), -3),
+                (TransactionIndex(1), 7),
+                (TransactionIndex(2), 7),
+                (TransactionIndex(3), 7),
+            ],
+        );
+        let liquid: HashSet<_> = vec![cash_key, bank_key].into_iter().collect();
+        let actual = book
+            .unit_balance_history_for_accounts::<i128>(&liquid, &usd)
+            .map(|(_, total)| total)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, [-3, -3, -1, -1]);
+        let all: HashSet<_> =
+            vec![cash_key, bank_key, expenses_key].into_iter().collect();
+        assert!(book
+            .unit_balance_history_for_accounts::<i128>(&all, &thb)
+            .all(|(_, total)| total == 0));
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn unit_balance_history_for_accounts_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        let account_keys: HashSet<_> = vec![account_key].into_iter().collect();
+        let usd = "USD";
+        let _ =
+            book.unit_balance_history_for_accounts::<i128>(&account_keys, &usd);
+    }
+    #[test]
+    fn flow_between() {
+        let mut book = TestBook::default();
+        let operating_a_key = book.insert_account("");
+        let operating_b_key = book.insert_account("");
+        let savings_key = book.insert_account("");
+        let other_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            operating_a_key,
+            savings_key,
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            operating_a_key,
+            operating_b_key,
+            test_sum!(5, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(2),
+            savings_key,
+            operating_b_key,
+            test_sum!(7, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            operating_b_key,
+            savings_key,
+            test_sum!(2, usd; 4, thb),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(1),
+            other_key,
+            savings_key,
+            test_sum!(11, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(2), "");
+        book.insert_move(
+            TransactionIndex(2),
+            MoveIndex(0),
+            operating_a_key,
+            savings_key,
+            test_sum!(13, usd),
+            "",
+        );
+        let operating: HashSet<_> =
+            vec![operating_a_key, operating_b_key].into_iter().collect();
+        let savings: HashSet<_> = vec![savings_key].into_iter().collect();
+        assert_eq!(
+            book.flow_between::<i128>(
+                &operating,
+                &savings,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(5, usd; 4, thb),
+        );
+        assert_eq!(
+            book.flow_between::<i128>(
+                &savings,
+                &operating,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(7, usd),
+        );
+    }
+    #[test]
+    fn account_balance_after_and_before() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(4, usd),
+            "",
+        );
+        assert_eq!(
+            book.account_balance_before::<i128>(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+            TestBalance::default(),
+        );
+        assert_eq!(
+            book.account_balance_after::<i128>(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+            TestBalance::default() + &test_sum!(3, usd),
+        );
+        assert_eq!(
+            book.account_balance_before::<i128>(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(3, usd),
+        );
+        assert_eq!(
+            book.account_balance_after::<i128>(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(7, usd),
+        );
+        assert_eq!(
+            book.account_balance_after::<i128>(
+                account_a_key,
+                TransactionIndex(1)
+            ),
+            book.account_balance_at_transaction::<i128>(
+                account_a_key,
+                TransactionIndex(1)
+            ),
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_balance_samples_panic_account_not_found() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_balance_samples::<i128>(
+            account_key,
+            &[TransactionIndex(0)],
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 1) should be < len (is 1)"
+    )]
+    fn account_balance_samples_panic_transaction_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.account_balance_samples::<i128>(
+            account_key,
+            &[TransactionIndex(0), TransactionIndex(1)],
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Transaction indices are not sorted.")]
+    fn account_balance_samples_panic_not_sorted() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_transaction(TransactionIndex(1), "");
+        let account_key = book.insert_account("");
+        book.account_balance_samples::<i128>(
+            account_key,
+            &[TransactionIndex(1), TransactionIndex(0)],
+        );
+    }
+    #[test]
+    fn account_balance_samples() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        (0..5).for_each(|index| {
+            book.insert_transaction(TransactionIndex(index), "");
+            book.insert_move(
+                TransactionIndex(index),
+                MoveIndex(0),
+                account_a_key,
+                account_b_key,
+                test_sum!(10u64.pow(index as u32), usd),
+                "",
+            );
+        });
+        let actual = book.account_balance_samples::<i128>(
+            account_b_key,
+            &[
+                TransactionIndex(0),
+                TransactionIndex(2),
+                TransactionIndex(4),
+            ],
+        );
+        let expected = [0, 2, 4]
+            .iter()
+            .map(|index| {
+                book.account_balance_at_transaction(
+                    account_b_key,
+                    TransactionIndex(*index),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+        assert_eq!(actual[2], TestBalance::default() + &test_sum!(11111, usd),);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_balance_at_transaction_into_account_not_found() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_balance_at_transaction_into(
+            account_key,
+            TransactionIndex(0),
+            &mut TestBalance::default(),
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn account_balance_at_transaction_into_panic_transaction_index_out_of_bounds(
+    ) {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.account_balance_at_transaction_into(
+            account_key,
+            TransactionIndex(0),
+            &mut TestBalance::default(),
+        );
+    }
+    #[test]
+    fn account_balance_at_transaction_into() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd; 4, thb),
+            "",
+        );
+        let mut balance = TestBalance::default();
+        book.account_balance_at_transaction_into(
+            account_a_key,
+            TransactionIndex(0),
+            &mut balance,
+        );
+        assert_eq!(
+            balance,
+            book.account_balance_at_transaction(
+                account_a_key,
+                TransactionIndex(0)
+            ),
+        );
+        balance.clear();
+        book.account_balance_at_transaction_into(
+            account_b_key,
+            TransactionIndex(0),
+            &mut balance,
+        );
+        assert_eq!(
+            balance,
+            book.account_balance_at_transaction(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+        );
+        book.account_balance_at_transaction_into(
+            account_b_key,
+            TransactionIndex(0),
+            &mut balance,
+        );
+        assert_eq!(
+            balance,
+            TestBalance::default() + &test_sum!(6, usd; 8, thb)
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_statement_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        let _ = book.account_statement::<i128>(account_key);
+    }
+    #[cfg(feature = "csv")]
+    #[test]
+    fn write_csv() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank, main");
+        let shop_key = book.insert_account("\"shop\"");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(3, usd; 5, thb),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            wallet_key,
+            shop_key,
+            test_sum!(1, usd),
+            "",
+        );
+        let mut csv = Vec::new();
+        book.write_csv(
+            &mut csv,
+            |extra| extra.to_string(),
+            |unit| unit.to_lowercase(),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            [
+                "transaction,move,debit,credit,thb,usd",
+                "0,0,\"bank, main\",wallet,5,3",
+                "1,0,wallet,\"\"\"shop\"\"\",,1",
+                "",
+            ]
+            .join("\n"),
+        );
+    }
+    #[test]
+    fn export_journal() {
+        let mut book = TestBook::default();
+        assert_eq!(book.export_journal(|_| String::new()), "");
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        let shop_key = book.insert_account("shop");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "2021-01-01 withdrawal");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(3, usd; 5, thb),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "2021-01-02 groceries");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            wallet_key,
+            shop_key,
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(1),
+            bank_key,
+            shop_key,
+            test_sum!(2, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(2), "2021-01-03 nothing");
+        let actual = book.export_journal(|account_key| {
+            book.get_account(account_key).to_string()
+        });
+        let expected = [
+            "2021-01-01 withdrawal",
+            "    bank  debit  5 THB, 3 USD",
+            "    wallet  credit  5 THB, 3 USD",
+            "",
+            "2021-01-02 groceries",
+            "    wallet  debit  1 USD",
+            "    shop  credit  1 USD",
+            "    bank  debit  2 USD",
+            "    shop  credit  2 USD",
+            "",
+            "2021-01-03 nothing",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn to_dot() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        let shop_key = book.insert_account("\"shop\" \\\tcafé's");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            shop_key,
+            wallet_key,
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(2, usd; 5, thb),
+            "",
+        );
+        let actual = book.to_dot(|_, name| name.to_string());
+        let expected = [
+            "digraph {",
+            "    \"Account#1v1\" [label=\"wallet\"];",
+            "    \"Account#2v1\" [label=\"bank\"];",
+            "    \"Account#3v1\" [label=\"\\\"shop\\\" \\\\\tcafé's\"];",
+            "    \"Account#1v1\" -> \"Account#2v1\" [label=\"5 THB\\n5 USD\"];",
+            "    \"Account#3v1\" -> \"Account#1v1\" [label=\"1 USD\"];",
+            "}",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn balances_at_transaction_out_of_bounds() {
+        TestBook::default()
+            .balances_at_transaction::<i128>(TransactionIndex(0));
+    }
+    #[test]
+    fn balances_at_transaction() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let idle_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(1, usd),
+            "",
+        );
+        let actual = book.balances_at_transaction(TransactionIndex(0));
+        let expected = [
+            (wallet_key, TestBalance::default() - &test_sum!(3, usd)),
+            (bank_key, TestBalance::default() + &test_sum!(3, usd)),
+            (idle_key, TestBalance::default()),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert_eq!(actual, expected);
+        let actual = book.balances_at_transaction(TransactionIndex(1));
+        let expected = [
+            (wallet_key, TestBalance::default() - &test_sum!(2, usd)),
+            (bank_key, TestBalance::default() + &test_sum!(2, usd)),
+            (idle_key, TestBalance::default()),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn account_balance_fingerprint_out_of_bounds() {
+        TestBook::default().account_balance_fingerprint(TransactionIndex(0));
+    }
+    #[test]
+    fn account_balance_fingerprint() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let savings_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        let fingerprint = book.account_balance_fingerprint(TransactionIndex(0));
+        assert_eq!(
+            book.account_balance_fingerprint(TransactionIndex(0)),
+            fingerprint,
+        );
+        book.set_transaction_extra(TransactionIndex(0), "deposit");
+        book.set_move_extra(TransactionIndex(0), MoveIndex(0), "cash");
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(1, usd),
+            "",
+        );
+        assert_eq!(
+            book.account_balance_fingerprint(TransactionIndex(0)),
+            fingerprint,
+        );
+        assert_ne!(
+            book.account_balance_fingerprint(TransactionIndex(1)),
+            fingerprint,
+        );
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), test_sum!(4, usd));
+        let changed = book.account_balance_fingerprint(TransactionIndex(0));
+        assert_ne!(changed, fingerprint);
+        book.set_move_side(
+            TransactionIndex(0),
+            MoveIndex(0),
+            Credit,
+            savings_key,
+        );
+        assert_ne!(
+            book.account_balance_fingerprint(TransactionIndex(0)),
+            changed,
+        );
+    }
+    #[test]
+    fn balance() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(1, usd),
+            "",
+        );
+        let actual = book.balance(bank_key, TransactionIndex(1));
+        assert_eq!(actual, Balance::default() + &test_sum!(2, usd));
+    }
+    #[test]
+    fn reconcile() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        book.insert_account("");
+        let closed_key = book.insert_account("");
+        book.accounts.remove(closed_key);
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        let expected = [
+            (wallet_key, TestBalance::default() - &test_sum!(3, usd)),
+            (
+                bank_key,
+                TestBalance::default() + &test_sum!(2, usd; 1, thb),
+            ),
+            (closed_key, TestBalance::default() + &test_sum!(5, usd)),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let actual = book.reconcile(&expected, TransactionIndex(0));
+        let expected = [
+            (
+                bank_key,
+                TestBalance::default() + &test_sum!(1, usd)
+                    - &test_sum!(1, thb),
+            ),
+            (closed_key, TestBalance::default() - &test_sum!(5, usd)),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 1) should be < len (is 1)"
+    )]
+    fn accounts_touched_between_to_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        book.accounts_touched_between(TransactionIndex(0), TransactionIndex(1));
+    }
+    #[test]
+    fn accounts_touched_between() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 5]);
+        [(0, 1), (1, 2), (2, 3), (3, 4)]
+            .iter()
+            .enumerate()
+            .for_each(|(index, (debit, credit))| {
+                book.insert_transaction(TransactionIndex(index), "");
+                book.insert_move(
+                    TransactionIndex(index),
+                    MoveIndex(0),
+                    account_keys[*debit],
+                    account_keys[*credit],
+                    test_sum!(),
+                    "",
+                );
+            });
+        let actual = book
+            .accounts_touched_between(TransactionIndex(0), TransactionIndex(2));
+        let expected = account_keys[1..4].iter().cloned().collect();
+        assert_eq!(actual, expected);
+        assert!(book
+            .accounts_touched_between(TransactionIndex(2), TransactionIndex(2))
+            .is_empty());
+    }
+    #[test]
+    fn accounts_with_balance_at() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        let usd = "USD";
+        (0..3).for_each(|index| {
+            book.insert_transaction(TransactionIndex(index), "");
+            book.insert_move(
+                TransactionIndex(index),
+                MoveIndex(0),
+                wallet_key,
+                bank_key,
+                test_sum!(index as u64 + 1, usd),
+                "",
+            );
+        });
+        let actual = book
+            .accounts_with_balance_at::<i128>(TransactionIndex(1))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                (
+                    wallet_key,
+                    &"wallet",
+                    TestBalance::default() - &test_sum!(3, usd)
+                ),
+                (
+                    bank_key,
+                    &"bank",
+                    TestBalance::default() + &test_sum!(3, usd)
+                ),
+            ],
+        );
+    }
+    #[test]
+    fn balance_matrix() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd; 4, thb),
+            "",
+        );
+        let actual = book.balance_matrix(TransactionIndex(0));
+        assert_eq!(
+            actual.into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    wallet_key,
+                    TestBalance::default() - &test_sum!(3, usd; 4, thb)
+                ),
+                (
+                    bank_key,
+                    TestBalance::default() + &test_sum!(3, usd; 4, thb)
+                ),
+            ],
+        );
+    }
+    #[test]
+    fn balances_at_transaction_match_account_balances() {
+        let book = TestBook::synthetic(5, 20, 3);
+        (0..20).map(TransactionIndex).for_each(|transaction_index| {
+            let balances =
+                book.balances_at_transaction::<i128>(transaction_index);
+            assert_eq!(balances.len(), 5);
+            book.accounts().for_each(|(account_key, _)| {
+                assert_eq!(
+                    balances[&account_key],
+                    book.account_balance_at_transaction(
+                        account_key,
+                        transaction_index,
+                    ),
+                );
+            });
+        });
+    }
+    #[test]
+    fn account_balance_between() {
+        let book = TestBook::synthetic(5, 20, 3);
+        book.accounts().for_each(|(account_key, _)| {
+            (1..20).for_each(|from| {
+                (from..20).for_each(|to| {
+                    let between: TestBalance = book.account_balance_between(
+                        account_key,
+                        TransactionIndex(from),
+                        TransactionIndex(to),
+                    );
+                    let at_to: TestBalance = book
+                        .account_balance_at_transaction(
+                            account_key,
+                            TransactionIndex(to),
+                        );
+                    let at_before_from: TestBalance = book
+                        .account_balance_at_transaction(
+                            account_key,
+                            TransactionIndex(from - 1),
+                        );
+                    // The difference keeps units whose amounts cancel out.
+                    assert!(between.approx_eq(&(at_to - &at_before_from), 0));
+                });
+            });
+            assert_eq!(
+                book.account_balance_between::<i128>(
+                    account_key,
+                    TransactionIndex(0),
+                    TransactionIndex(19),
+                ),
+                book.account_balance_at_transaction(
+                    account_key,
+                    TransactionIndex(19),
+                ),
+            );
+        });
+    }
+    #[test]
+    #[should_panic(expected = "from (is 1) should be <= to (is 0)")]
+    fn account_balance_between_panic_from_after_to() {
+        let book = TestBook::synthetic(2, 2, 1);
+        let account_key = book.accounts().next().unwrap().0;
+        book.account_balance_between::<i128>(
+            account_key,
+            TransactionIndex(1),
+            TransactionIndex(0),
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 2) should be < len (is 2)"
+    )]
+    fn account_balance_between_panic_out_of_bounds() {
+        let book = TestBook::synthetic(2, 2, 1);
+        let account_key = book.accounts().next().unwrap().0;
+        book.account_balance_between::<i128>(
+            account_key,
+            TransactionIndex(0),
+            TransactionIndex(2),
+        );
+    }
+    #[test]
+    fn total_balance() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        book.insert_transaction_with_moves(
+            TransactionIndex(0),
+            "",
+            vec![
+                (
+                    account_keys[0],
+                    account_keys[1],
+                    test_sum!(3, usd; 4, thb),
+                    "",
+                ),
+                (
+                    account_keys[1],
+                    account_keys[2],
+                    test_sum!(2, thb; 7, ils),
+                    "",
+                ),
+            ],
+        );
+        book.insert_transaction_with_moves(
+            TransactionIndex(1),
+            "",
+            vec![(account_keys[2], account_keys[0], test_sum!(9, usd), "")],
+        );
+        let total: TestBalance = book.total_balance(TransactionIndex(1));
+        assert!(!total.is_empty());
+        assert!(total.is_zero());
+        let total: TestBalance = book.total_balance(TransactionIndex(0));
+        assert!(total.is_zero());
+        book.accounts.remove(account_keys[2]);
+        let total: TestBalance = book.total_balance(TransactionIndex(1));
+        assert!(!total.is_zero());
+        assert_eq!(
+            total,
+            vec![(usd, 9), (thb, -2), (ils, -7)]
+                .into_iter()
+                .collect::<TestBalance>(),
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn total_balance_out_of_bounds() {
+        TestBook::default().total_balance::<i128>(TransactionIndex(0));
+    }
+    #[test]
+    fn balances_at_transaction_net_zero() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        let usd = "USD";
+        let thb = "THB";
+        (0..4).for_each(|index| {
+            book.insert_transaction(TransactionIndex(index), "");
+            book.insert_move(
+                TransactionIndex(index),
+                MoveIndex(0),
+                account_keys[index % 3],
+                account_keys[(index + 1) % 3],
+                test_sum!(index as u64 + 1, usd; 2, thb),
+                "",
+            );
+        });
+        (0..4).for_each(|index| {
+            let mut net = TestBalance::default();
+            book.balances_at_transaction::<i128>(TransactionIndex(index))
+                .values()
+                .for_each(|balance| {
+                    balance.amounts().for_each(|(unit, amount)| {
+                        net += &(*unit, *amount);
+                    })
+                });
+            assert!(net.amounts().all(|(_unit, amount)| *amount == 0));
+        });
+    }
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn balances_at_transaction_parallel() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 7]);
+        let units = ["USD", "THB", "EUR"];
+        (0..2000).for_each(|index| {
+            book.insert_transaction(TransactionIndex(index), "");
+            (0..3).for_each(|move_index| {
+                let debit = account_keys[(index + move_index) % 7];
+                let credit = account_keys[(index * 3 + move_index + 1) % 7];
+                let unit = units[index % 3];
+                if debit != credit {
+                    book.insert_move(
+                        TransactionIndex(index),
+                        MoveIndex(0),
+                        debit,
+                        credit,
+                        test_sum!((index * move_index) as u64 % 97, unit),
+                        "",
+                    );
+                }
+            });
+        });
+        [0, 1, 999, 1999].iter().for_each(|&index| {
+            assert_eq!(
+                book.balances_at_transaction_parallel::<i128>(
+                    TransactionIndex(index)
+                ),
+                book.balances_at_transaction::<i128>(TransactionIndex(index)),
+            );
+        });
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_balance_history_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.account_balance_history::<i128>(account_key);
+    }
+    #[test]
+    fn account_running_balance() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let idle_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(3, usd; 4, thb),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_transaction(TransactionIndex(2), "");
+        book.insert_move(
+            TransactionIndex(2),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(2),
+            MoveIndex(1),
+            wallet_key,
+            bank_key,
+            test_sum!(2, thb),
+            "",
+        );
+        let actual = book
+            .account_running_balance::<i128>(wallet_key)
+            .collect::<Vec<_>>();
+        let after_first = TestBalance::default() + &test_sum!(3, usd; 4, thb);
+        let expected = vec![
+            (TransactionIndex(0), after_first.clone()),
+            (TransactionIndex(1), after_first.clone()),
+            (
+                TransactionIndex(2),
+                after_first - &test_sum!(1, usd; 2, thb),
+            ),
+        ];
+        assert_eq!(actual, expected);
+        assert!(book
+            .account_running_balance::<i128>(idle_key)
+            .all(|(_, balance)| balance == TestBalance::default()));
+        let mut empty = TestBook::default();
+        let account_key = empty.insert_account("");
+        assert_eq!(
+            empty.account_running_balance::<i128>(account_key).count(),
+            0
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_running_balance_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        let _ = book.account_running_balance::<i128>(account_key);
+    }
+    #[test]
+    fn cached_balances_follow_edits() {
+        fn assert_balances(book: &TestBook, account_keys: &[AccountKey]) {
+            account_keys.iter().for_each(|account_key| {
+                let mut expected = TestBalance::default();
+                let running = book
+                    .account_running_balance::<i128>(account_key)
+                    .collect::<Vec<_>>();
+                assert_eq!(running.len(), book.transactions.len());
+                book.transactions().zip(running).for_each(
+                    |((transaction_index, transaction), running)| {
+                        expected.apply_transaction(transaction, *account_key);
+                        assert_eq!(
+                            book.account_balance_at_transaction::<i128>(
+                                account_key,
+                                transaction_index,
+                            ),
+                            expected,
+                        );
+                        assert_eq!(
+                            running,
+                            (transaction_index, expected.clone())
+                        );
+                    },
+                );
+            });
+        }
+        let usd = "USD";
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_keys[0],
+            account_keys[1],
+            test_sum!(1, usd),
+            "",
+        );
+        assert_balances(&book, &account_keys);
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_keys[1],
+            account_keys[2],
+            test_sum!(2, usd),
+            "",
+        );
+        assert_balances(&book, &account_keys);
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_keys[2],
+            account_keys[0],
+            test_sum!(4, usd),
+            "",
+        );
+        assert_balances(&book, &account_keys);
+        book.set_move_index(TransactionIndex(1), MoveIndex(0), MoveIndex(1));
+        assert_balances(&book, &account_keys);
+        book.set_move_sum(TransactionIndex(1), MoveIndex(1), test_sum!(8, usd));
+        assert_balances(&book, &account_keys);
+        book.set_move_side(
+            TransactionIndex(1),
+            MoveIndex(1),
+            Credit,
+            account_keys[1],
+        );
+        assert_balances(&book, &account_keys);
+        book.remove_move(TransactionIndex(1), MoveIndex(0));
+        assert_balances(&book, &account_keys);
+        book.remove_transaction(TransactionIndex(0));
+        assert_balances(&book, &account_keys);
+        (0..10).for_each(|_| {
+            assert!(book.undo());
+            assert_balances(&book, &account_keys);
+        });
+        assert!(book.transactions.is_empty());
+        while book.redo() {
+            assert_balances(&book, &account_keys);
+        }
+        book.compact_transaction(TransactionIndex(0));
+        assert_balances(&book, &account_keys);
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        book.insert_transaction_with_moves(
+            TransactionIndex(0),
+            "",
+            vec![(account_keys[0], account_keys[1], test_sum!(5, usd), "")],
+        );
+        book.insert_transaction_with_moves(
+            TransactionIndex(1),
+            "",
+            vec![(account_keys[0], account_keys[2], test_sum!(7, usd), "")],
+        );
+        book.clone_transaction(TransactionIndex(1), TransactionIndex(2));
+        assert_balances(&book, &account_keys);
+        assert_eq!(book.dedup_adjacent_transactions(), 1);
+        assert_balances(&book, &account_keys);
+        assert_eq!(
+            book.remove_transactions_matching(|transaction| {
+                transaction
+                    .moves
+                    .iter()
+                    .any(|move_| move_.credit_account_key == account_keys[1])
+            }),
+            1,
+        );
+        assert_balances(&book, &account_keys);
+        assert!(book.try_remove_account(account_keys[1]).is_ok());
+    }
+    #[test]
+    fn cache_stats() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 2]);
+        let usd = "USD";
+        book.insert_transaction_with_moves(
+            TransactionIndex(0),
+            "",
+            vec![(account_keys[0], account_keys[1], test_sum!(5, usd), "")],
+        );
+        assert_eq!(book.cache_stats(), (0, 0));
+        book.account_moves(account_keys[0]).for_each(drop);
+        assert_eq!(book.cache_stats(), (0, 1));
+        book.account_moves(account_keys[0]).for_each(drop);
+        assert_eq!(book.cache_stats(), (1, 1));
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), test_sum!(7, usd));
+        book.account_moves(account_keys[1]).for_each(drop);
+        assert_eq!(book.cache_stats(), (2, 1));
+        book.insert_transaction(TransactionIndex(1), "");
+        book.account_moves(account_keys[1]).for_each(drop);
+        assert_eq!(book.cache_stats(), (2, 2));
+        book.account_moves(account_keys[0]).for_each(drop);
+        assert_eq!(book.cache_stats(), (3, 2));
+    }
+    #[test]
+    fn set_move_side_updates_move_locations() {
+        fn assert_fresh(book: &TestBook, account_keys: &[AccountKey]) {
+            let mut fresh = book.clone();
+            fresh.move_locations.take();
+            account_keys.iter().for_each(|account_key| {
+                assert_eq!(
+                    book.account_move_locations(*account_key),
+                    fresh.account_move_locations(*account_key),
+                );
+                assert_eq!(
+                    book.account_balance_after::<i128>(
+                        account_key,
+                        TransactionIndex(2),
+                    ),
+                    fresh.account_balance_after::<i128>(
+                        account_key,
+                        TransactionIndex(2),
+                    ),
+                );
+            });
+        }
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        let usd = "USD";
+        [
+            vec![
+                (account_keys[0], account_keys[1], test_sum!(1, usd), ""),
+                (account_keys[1], account_keys[2], test_sum!(2, usd), ""),
+            ],
+            vec![(account_keys[2], account_keys[0], test_sum!(4, usd), "")],
+            vec![(account_keys[0], account_keys[1], test_sum!(8, usd), "")],
+        ]
+        .iter()
+        .enumerate()
+        .for_each(|(index, moves)| {
+            book.insert_transaction_with_moves(
+                TransactionIndex(index),
+                "",
+                moves.clone(),
+            );
+        });
+        assert_fresh(&book, &account_keys);
+        let (_, misses) = book.cache_stats();
+        book.set_move_side(
+            TransactionIndex(0),
+            MoveIndex(0),
+            Credit,
+            account_keys[2],
+        );
+        assert_fresh(&book, &account_keys);
+        book.modify_move(TransactionIndex(2), MoveIndex(0), |editor| {
+            editor
+                .set_side(Debit, account_keys[1])
+                .set_side(Credit, account_keys[0]);
+        });
+        assert_fresh(&book, &account_keys);
+        assert!(book.undo());
+        assert_fresh(&book, &account_keys);
+        assert!(book.undo());
+        assert_fresh(&book, &account_keys);
+        assert_eq!(book.cache_stats().1, misses);
+    }
+    #[test]
+    fn account_balance_history() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        [
+            (account_a_key, account_b_key, test_sum!(3, usd)),
+            (account_a_key, account_c_key, test_sum!(5, usd)),
+            (account_b_key, account_a_key, test_sum!(1, usd; 2, thb)),
+            (account_c_key, account_b_key, test_sum!(7, thb)),
+        ]
+        .iter()
+        .enumerate()
+        .for_each(|(index, (debit_key, credit_key, sum))| {
+            book.insert_transaction(TransactionIndex(index), "");
+            book.insert_move(
+                TransactionIndex(index),
+                MoveIndex(0),
+                debit_key,
+                credit_key,
+                sum.clone(),
+                "",
+            );
+        });
+        let history = book.account_balance_history::<i128>(account_b_key);
+        assert_eq!(
+            history
+                .iter()
+                .map(|(transaction_index, _)| transaction_index.0)
+                .collect::<Vec<_>>(),
+            vec![0, 2, 3],
+        );
+        history
+            .into_iter()
+            .for_each(|(transaction_index, balance)| {
+                assert_eq!(
+                    balance,
+                    book.account_balance_after(
+                        account_b_key,
+                        transaction_index
+                    ),
+                );
+            });
+    }
+    #[test]
+    fn map_move_extra() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("a");
+        let account_b_key = book.insert_account("b");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "t");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd),
+            "rent",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_b_key,
+            account_a_key,
+            test_sum!(1, usd),
+            "refund",
+        );
+        let balance = book
+            .account_balance_after::<i128>(account_b_key, TransactionIndex(0));
+        let book = book.map_move_extra(|extra| extra.to_string());
+        assert_eq!(
+            book.transactions[0]
+                .moves
+                .iter()
+                .map(|move_| move_.extra.as_str())
+                .collect::<Vec<_>>(),
+            vec!["rent", "refund"],
+        );
+        assert_eq!(book.transactions[0].extra, "t");
+        assert_eq!(*book.get_account(account_a_key), "a");
+        assert_eq!(
+            book.account_balance_after::<i128>(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+            balance,
+        );
+    }
+    #[test]
+    fn map_extras() {
+        let mut book = TestBook::default();
+        let removed_key = book.insert_account("removed");
+        book.account_keys.remove(removed_key);
+        book.accounts.remove(removed_key);
+        let account_a_key = book.insert_account("a");
+        let account_b_key = book.insert_account("bb");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "t");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd),
+            "rent",
+        );
+        book.insert_transaction(TransactionIndex(1), "uu");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_b_key,
+            account_a_key,
+            test_sum!(1, usd),
+            "refund",
+        );
+        let balances = [account_a_key, account_b_key]
+            .iter()
+            .map(|account_key| {
+                book.account_balance_after::<i128>(
+                    account_key,
+                    TransactionIndex(1),
+                )
+            })
+            .collect::<Vec<_>>();
+        let book = book.map_extras(
+            |extra| extra.len(),
+            |extra| extra.to_uppercase(),
+            |extra| extra.to_string(),
+        );
+        assert_eq!(
+            book.accounts().collect::<Vec<_>>(),
+            vec![(account_a_key, &1), (account_b_key, &2)],
+        );
+        assert_eq!(
+            book.transactions()
+                .map(|(_, transaction)| transaction.extra().as_str())
+                .collect::<Vec<_>>(),
+            vec!["T", "UU"],
+        );
+        assert_eq!(
+            book.transactions()
+                .flat_map(|(_, transaction)| transaction.moves())
+                .map(|(_, move_)| move_.extra().as_str())
+                .collect::<Vec<_>>(),
+            vec!["rent", "refund"],
+        );
+        assert_eq!(
+            [account_a_key, account_b_key]
+                .iter()
+                .map(|account_key| {
+                    book.account_balance_after::<i128>(
+                        account_key,
+                        TransactionIndex(1),
+                    )
+                })
+                .collect::<Vec<_>>(),
+            balances,
+        );
+    }
+    #[test]
+    fn account_statement() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_b_key,
+            account_a_key,
+            test_sum!(10, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_b_key,
+            account_c_key,
+            test_sum!(1, usd),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_a_key,
+            account_c_key,
+            test_sum!(3, usd),
+            "",
+        );
+        let actual = book
+            .account_statement::<i128>(account_a_key)
+            .map(|(transaction_index, move_index, side, sum, balance)| {
+                (
+                    transaction_index.0,
+                    move_index.0,
+                    matches!(side, Debit),
+                    sum.clone(),
+                    balance,
+                )
+            })
+            .collect::<Vec<_>>();
+        let expected = vec![
+            (
+                0,
+                0,
+                false,
+                test_sum!(10, usd),
+                TestBalance::default() + &test_sum!(10, usd),
+            ),
+            (
+                1,
+                0,
+                true,
+                test_sum!(3, usd),
+                TestBalance::default() + &test_sum!(7, usd),
+            ),
+        ];
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn move_count_for_account_panic_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.move_count_for_account(account_key);
+    }
+    #[test]
+    fn try_insert_move() {
+        use super::InsertMoveError;
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let closed_key = book.insert_account("");
+        book.accounts.remove(closed_key);
+        book.insert_transaction(TransactionIndex(0), "");
+        let mut try_insert_move = |t, m, debit, credit| {
+            book.try_insert_move(
+                TransactionIndex(t),
+                MoveIndex(m),
+                debit,
+                credit,
+                test_sum!(),
+                "",
+            )
+        };
+        assert_eq!(
+            try_insert_move(1, 0, debit_key, credit_key),
+            Err(InsertMoveError::TransactionIndexOutOfBounds(
+                TransactionIndex(1)
+            )),
+        );
+        assert_eq!(
+            try_insert_move(0, 1, debit_key, credit_key),
+            Err(InsertMoveError::MoveIndexOutOfBounds(MoveIndex(1))),
+        );
+        assert_eq!(
+            try_insert_move(0, 0, closed_key, credit_key),
+            Err(InsertMoveError::DebitAccountNotFound(closed_key)),
+        );
+        assert_eq!(
+            try_insert_move(0, 0, debit_key, closed_key),
+            Err(InsertMoveError::CreditAccountNotFound(closed_key)),
+        );
+        assert_eq!(
+            try_insert_move(0, 0, debit_key, debit_key),
+            Err(InsertMoveError::DebitEqualsCredit(debit_key)),
+        );
+        assert_eq!(try_insert_move(0, 0, debit_key, credit_key), Ok(()));
+        assert_eq!(book.transactions[0].moves.len(), 1);
+        book.set_reject_empty_sums(true);
+        assert_eq!(
+            book.try_insert_move(
+                TransactionIndex(0),
+                MoveIndex(0),
+                debit_key,
+                credit_key,
+                test_sum!(),
+                "",
+            ),
+            Err(InsertMoveError::EmptySum),
+        );
+        assert_eq!(book.transactions[0].moves.len(), 1);
+    }
+    #[test]
+    fn remove_account() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "",
+        );
+        let error = book.try_remove_account(credit_key).unwrap_err();
+        assert_eq!(error.moves(), [(TransactionIndex(0), MoveIndex(0))]);
+        assert_eq!(error.to_string(), "Account is referenced by 1 moves");
+        assert!(book.accounts.contains_key(credit_key));
+        book.remove_move(TransactionIndex(0), MoveIndex(0));
+        book.remove_account(credit_key);
+        assert!(!book.accounts.contains_key(credit_key));
+        assert_eq!(book.try_remove_account(debit_key), Ok(()));
+        assert_eq!(book.accounts().count(), 0);
+        assert!(book.undo());
+        assert!(book.accounts.contains_key(debit_key));
+    }
+    #[test]
+    #[should_panic(expected = "Account is referenced by 2 moves")]
+    fn remove_account_panic_referenced() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        (0..2).for_each(|move_index| {
+            book.insert_move(
+                TransactionIndex(0),
+                MoveIndex(move_index),
+                debit_key,
+                credit_key,
+                test_sum!(),
+                "",
+            );
+        });
+        book.remove_account(debit_key);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn remove_account_panic_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.remove_account(account_key);
+    }
+    #[test]
+    fn move_count_for_account() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let unused_account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_c_key,
+            account_a_key,
+            test_sum!(),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_b_key,
+            account_a_key,
+            test_sum!(),
+            "",
+        );
+        assert_eq!(book.move_count_for_account(account_a_key), 3);
+        assert_eq!(book.move_count_for_account(account_b_key), 2);
+        assert_eq!(book.move_count_for_account(account_c_key), 1);
+        assert_eq!(book.move_count_for_account(unused_account_key), 0);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn transaction_count_for_account_panic_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.transaction_count_for_account(account_key);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn account_activity_at_transaction_panic_account_not_found() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.accounts.remove(account_key);
+        book.account_activity_at_transaction(account_key, TransactionIndex(0));
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn account_activity_at_transaction_panic_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.account_activity_at_transaction(account_key, TransactionIndex(0));
+    }
+    #[test]
+    fn account_activity_at_transaction() {
+        let mut book = TestBook::default();
+        let account_keys = book.insert_accounts(vec![""; 3]);
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_keys[0],
+            account_keys[1],
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(1),
+            account_keys[1],
+            account_keys[0],
+            test_sum!(3, usd),
+            "",
+        );
+        assert!(!book.account_activity_at_transaction(
+            account_keys[0],
+            TransactionIndex(0)
+        ));
+        assert!(book.account_activity_at_transaction(
+            account_keys[0],
+            TransactionIndex(1)
+        ));
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_keys[0],
+                TransactionIndex(1),
+            ),
+            TestBalance::default() + &test_sum!(0, usd),
+        );
+        assert!(!book.account_activity_at_transaction(
+            account_keys[2],
+            TransactionIndex(1)
+        ));
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_keys[2],
+                TransactionIndex(1),
+            ),
+            TestBalance::default(),
+        );
+    }
+    #[test]
+    fn transaction_count_for_account() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let unused_account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_c_key,
+            account_a_key,
+            test_sum!(),
+            "",
+        );
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_b_key,
+            account_a_key,
+            test_sum!(),
+            "",
+        );
+        assert_eq!(book.transaction_count_for_account(account_a_key), 2);
+        assert_eq!(book.transaction_count_for_account(account_b_key), 2);
+        assert_eq!(book.transaction_count_for_account(account_c_key), 1);
+        assert_eq!(book.transaction_count_for_account(unused_account_key), 0);
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn set_account_panic() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.accounts.remove(account_key);
+        book.set_account(account_key, "!");
+    }
+    #[test]
+    fn set_account() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.set_account(account_key, "!");
+        assert_eq!(*book.accounts.get(account_key).unwrap(), "!");
+    }
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn set_move_extra_panic_transaction_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.set_move_extra(TransactionIndex(0), MoveIndex(0), "");
+    }
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn set_move_extra_panic_move_index_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        book.set_move_extra(TransactionIndex(0), MoveIndex(1), "");
+    }
+    #[test]
+    fn set_move_extra() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "",
+        );
+        book.set_move_extra(TransactionIndex(0), MoveIndex(0), "!");
+        assert_eq!(*book.transactions[0].moves[0].extra(), "!");
+    }
+    #[test]
+    fn move_extra_mut() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "a",
+        );
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let handler_events = events.clone();
+        book.on_change(Box::new(move |event| {
+            handler_events.lock().unwrap().push(*event)
+        }));
+        fn exclaim(extra: &mut &'static str) {
+            *extra = "!";
+        }
+        exclaim(book.move_extra_mut(TransactionIndex(0), MoveIndex(0)));
+        assert_eq!(*book.transactions[0].moves[0].extra(), "!");
+        assert_eq!(
+            *events.lock().unwrap(),
+            [crate::event::BookEvent::MoveExtraSet(
+                TransactionIndex(0),
+                MoveIndex(0)
+            )],
+        );
+        assert!(book.undo());
+        assert_eq!(*book.transactions[0].moves[0].extra(), "a");
+        book.move_extra_mut(TransactionIndex(0), MoveIndex(0));
+        assert!(book.undo());
+        assert_eq!(*book.transactions[0].moves[0].extra(), "a");
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 1) should be < len (is 1)"
+    )]
+    fn move_extra_mut_panic_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        book.move_extra_mut(TransactionIndex(1), MoveIndex(0));
+    }
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn move_extra_mut_panic_move_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        book.move_extra_mut(TransactionIndex(0), MoveIndex(0));
+    }
+    #[test]
+    fn update_moves() {
+        let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "a",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "b",
+        );
+        book.update_moves(TransactionIndex(0), |move_index, extra| {
+            *extra = ["A", "B"][move_index.0];
+        });
+        assert_eq!(*book.transactions[0].moves[0].extra(), "A");
+        assert_eq!(*book.transactions[0].moves[1].extra(), "B");
+        assert!(book.undo());
+        assert_eq!(*book.transactions[0].moves[0].extra(), "a");
+        assert_eq!(*book.transactions[0].moves[1].extra(), "b");
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 1) should be < len (is 1)"
+    )]
+    fn update_moves_panic_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        book.update_moves(TransactionIndex(1), |_, _| {});
+    }
+    #[test]
+    #[should_panic(expected = "removal index (is 0) should be < len (is 0)")]
+    fn remove_transaction_panic_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.remove_transaction(TransactionIndex(0));
+    }
+    #[test]
+    fn remove_transaction() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "a");
+        book.insert_transaction(TransactionIndex(1), "b");
+        book.remove_transaction(TransactionIndex(1));
+        assert_eq!(&book.transactions[0].extra, &"a");
+        book.remove_transaction(TransactionIndex(0));
+        assert!(book.transactions.is_empty());
+    }
+    #[test]
+    fn remove_transactions_matching() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        ["keep a", "remove b", "keep c", "remove d"]
+            .iter()
+            .enumerate()
+            .for_each(|(index, extra)| {
+                book.insert_transaction(TransactionIndex(index), extra);
+                book.insert_move(
+                    TransactionIndex(index),
+                    MoveIndex(0),
+                    account_a_key,
+                    account_b_key,
+                    test_sum!(10u64.pow(index as u32), usd),
+                    "",
+                );
+            });
+        let removed = book.remove_transactions_matching(|transaction| {
+            transaction.extra().starts_with("remove")
+        });
+        assert_eq!(removed, 2);
+        assert_eq!(
+            book.transactions
+                .iter()
+                .map(|transaction| transaction.extra)
+                .collect::<Vec<_>>(),
+            vec!["keep a", "keep c"],
+        );
+        assert_eq!(
+            book.account_balance_at_transaction::<i128>(
+                account_b_key,
+                TransactionIndex(1)
+            ),
+            TestBalance::default() + &test_sum!(101, usd),
+        );
+        assert_eq!(book.remove_transactions_matching(|_| false), 0);
+    }
+    #[test]
+    fn remove_transactions_none_keeps_redo() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "a");
+        book.insert_transaction(TransactionIndex(1), "b");
+        assert!(book.undo());
+        assert_eq!(book.remove_transactions_matching(|_| false), 0);
+        assert_eq!(book.dedup_adjacent_transactions(), 0);
+        assert!(book.redo());
+        assert_eq!(book.transactions.len(), 2);
+        assert!(book.undo());
+        assert!(book.undo());
+        assert!(!book.undo());
+    }
+    #[test]
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn compact_transaction_panic_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.compact_transaction(TransactionIndex(0));
+    }
+    #[test]
+    fn compact_transaction() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(1, usd),
+            "a",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_b_key,
+            account_a_key,
+            test_sum!(2, usd),
+            "b",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(2),
+            account_a_key,
+            account_b_key,
+            test_sum!(4, usd; 8, thb),
+            "c",
+        );
+        let balance_before = book
+            .account_balance_after::<i128>(account_b_key, TransactionIndex(0));
+        book.compact_transaction(TransactionIndex(0));
+        let moves = &book.transactions[0].moves;
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].debit_account_key, account_a_key);
+        assert_eq!(moves[0].credit_account_key, account_b_key);
+        assert_eq!(moves[0].sum, test_sum!(5, usd; 8, thb));
+        assert_eq!(moves[0].extra, "a");
+        assert_eq!(moves[1].debit_account_key, account_b_key);
+        assert_eq!(moves[1].credit_account_key, account_a_key);
+        assert_eq!(moves[1].sum, test_sum!(2, usd));
+        assert_eq!(moves[1].extra, "b");
+        assert_eq!(
+            book.account_balance_after::<i128>(
+                account_b_key,
+                TransactionIndex(0)
+            ),
+            balance_before,
+        );
+    }
+    #[test]
+    fn dedup_adjacent_transactions() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let usd = "USD";
+        ["a", "b", "b", "b", "c", "b"].iter().enumerate().for_each(
+            |(index, extra)| {
+                book.insert_transaction(TransactionIndex(index), extra);
+                book.insert_move(
+                    TransactionIndex(index),
+                    MoveIndex(0),
+                    account_a_key,
+                    account_b_key,
+                    test_sum!(1, usd),
+                    "",
+                );
+            },
+        );
+        assert_eq!(book.dedup_adjacent_transactions(), 2);
+        assert_eq!(
+            book.transactions
+                .iter()
+                .map(|transaction| transaction.extra)
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c", "b"],
         );
+        book.set_move_sum(TransactionIndex(1), MoveIndex(0), test_sum!(2, usd));
+        book.set_transaction_extra(TransactionIndex(0), "b");
+        assert_eq!(book.dedup_adjacent_transactions(), 0);
     }
-}
-#[cfg(test)]
-mod test {
-    use super::{
-        Side::{Credit, Debit},
-        TransactionIndex,
-    };
-    use crate::{
-        test_utils::{TestBalance, TestBook},
-        transaction::MoveIndex,
-    };
     #[test]
-    fn default() {
-        let book = TestBook::default();
-        assert!(book.accounts.is_empty());
-        assert!(book.transactions.is_empty());
+    fn prune_orphan_moves() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        [
+            (account_a_key, account_b_key, "a-b"),
+            (account_c_key, account_a_key, "c-a"),
+            (account_b_key, account_a_key, "b-a"),
+            (account_b_key, account_c_key, "b-c"),
+        ]
+        .iter()
+        .enumerate()
+        .for_each(|(index, (debit, credit, extra))| {
+            book.insert_move(
+                TransactionIndex(0),
+                MoveIndex(index),
+                debit,
+                credit,
+                test_sum!(index as u64 + 1, usd),
+                extra,
+            );
+        });
+        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            account_a_key,
+            account_c_key,
+            test_sum!(5, usd),
+            "a-c",
+        );
+        book.accounts.remove(account_c_key);
+        assert_eq!(book.prune_orphan_moves(), 3);
+        assert_eq!(
+            book.transactions
+                .iter()
+                .flat_map(|transaction| transaction.moves.iter())
+                .map(|move_| move_.extra)
+                .collect::<Vec<_>>(),
+            vec!["a-b", "b-a"],
+        );
+        let balances = book.balances_at_transaction(TransactionIndex(1));
+        assert_eq!(
+            balances[&account_a_key],
+            TestBalance::default() + &test_sum!(2, usd)
+        );
+        assert_eq!(
+            balances[&account_b_key],
+            TestBalance::default() - &test_sum!(2, usd)
+        );
+        assert_eq!(book.prune_orphan_moves(), 0);
     }
     #[test]
-    fn insert_account() {
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn remove_move_panic_transaction_index_out_of_bounds() {
         let mut book = TestBook::default();
-        book.insert_account("");
-        assert_eq!(book.accounts.len(), 1);
+        book.remove_move(TransactionIndex(0), MoveIndex(0));
     }
     #[test]
-    #[should_panic(expected = "insertion index (is 1) should be <= len (is 0)")]
-    fn insert_transaction_panic_index_out_of_bounds() {
+    #[should_panic(expected = "removal index (is 0) should be < len (is 0)")]
+    fn remove_move_panic_move_index_out_of_bounds() {
         let mut book = TestBook::default();
-        book.insert_transaction(TransactionIndex(1), "");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.remove_move(TransactionIndex(0), MoveIndex(0));
     }
     #[test]
-    fn insert_transaction() {
+    fn remove_move() {
         let mut book = TestBook::default();
-        book.insert_transaction(TransactionIndex(0), "a");
-        book.insert_transaction(TransactionIndex(1), "b");
-        book.insert_transaction(TransactionIndex(0), "c");
-        book.insert_transaction(TransactionIndex(2), "d");
-        assert_eq!(
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
+            "a",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
+            "b",
+        );
+        book.remove_move(TransactionIndex(0), MoveIndex(1));
+        assert_eq!(&book.transactions[0].moves[0].extra, &"a");
+        book.remove_move(TransactionIndex(0), MoveIndex(0));
+        assert!(book.transactions[0].moves.is_empty());
+    }
+    #[test]
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn set_move_index_panic_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        book.set_move_index(TransactionIndex(0), MoveIndex(0), MoveIndex(0));
+    }
+    #[test]
+    #[should_panic(expected = "move index (is 1) should be < len (is 1)")]
+    fn set_move_index_panic_from_out_of_bounds() {
+        let mut book = TestBook::default();
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
+            "",
+        );
+        book.set_move_index(TransactionIndex(0), MoveIndex(1), MoveIndex(0));
+    }
+    #[test]
+    #[should_panic(expected = "move index (is 1) should be < len (is 1)")]
+    fn set_move_index_panic_to_out_of_bounds() {
+        let mut book = TestBook::default();
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
+            "",
+        );
+        book.set_move_index(TransactionIndex(0), MoveIndex(0), MoveIndex(1));
+    }
+    #[test]
+    fn move_transaction() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let usd = "USD";
+        ["a", "b", "c"]
+            .iter()
+            .enumerate()
+            .for_each(|(index, extra)| {
+                book.insert_transaction(TransactionIndex(index), extra);
+            });
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(1),
+            MoveIndex(0),
+            bank_key,
+            wallet_key,
+            test_sum!(5, usd),
+            "",
+        );
+        let extras = |book: &TestBook| {
             book.transactions
                 .iter()
-                .map(|transaction| transaction.extra())
-                .collect::<Vec<_>>(),
-            [&"c", &"a", &"d", &"b"],
+                .map(|transaction| transaction.extra)
+                .collect::<Vec<_>>()
+        };
+        let balances = |book: &TestBook, account_key| {
+            (0..3)
+                .map(|index| {
+                    book.account_balance_at_transaction::<i128>(
+                        account_key,
+                        TransactionIndex(index),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            balances(&book, wallet_key),
+            vec![
+                TestBalance::default() - &test_sum!(3, usd),
+                TestBalance::default() + &test_sum!(2, usd),
+                TestBalance::default() + &test_sum!(2, usd),
+            ],
+        );
+        book.move_transaction(TransactionIndex(0), TransactionIndex(2));
+        assert_eq!(extras(&book), vec!["b", "c", "a"]);
+        assert_eq!(
+            balances(&book, wallet_key),
+            vec![
+                TestBalance::default() + &test_sum!(5, usd),
+                TestBalance::default() + &test_sum!(5, usd),
+                TestBalance::default() + &test_sum!(2, usd),
+            ],
+        );
+        assert_eq!(
+            balances(&book, bank_key),
+            vec![
+                TestBalance::default() - &test_sum!(5, usd),
+                TestBalance::default() - &test_sum!(5, usd),
+                TestBalance::default() - &test_sum!(2, usd),
+            ],
         );
+        book.move_transaction(TransactionIndex(2), TransactionIndex(1));
+        assert_eq!(extras(&book), vec!["b", "a", "c"]);
+        assert!(book.undo());
+        assert_eq!(extras(&book), vec!["b", "c", "a"]);
+        assert!(book.undo());
+        assert_eq!(extras(&book), vec!["a", "b", "c"]);
     }
     #[test]
-    #[should_panic(expected = "insertion index (is 1) should be <= len (is 0)")]
-    fn insert_move_panic_index_out_of_bounds() {
+    #[should_panic(
+        expected = "transaction index (is 1) should be < len (is 1)"
+    )]
+    fn move_transaction_panic_out_of_bounds() {
         let mut book = TestBook::default();
-        let debit_key = book.insert_account("");
-        let credit_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.move_transaction(TransactionIndex(0), TransactionIndex(1));
+    }
+    #[test]
+    fn set_move_index() {
+        let mut book = TestBook::default();
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        ["a", "b", "c"]
+            .iter()
+            .enumerate()
+            .for_each(|(index, extra)| {
+                book.insert_move(
+                    TransactionIndex(0),
+                    MoveIndex(index),
+                    debit_account_key,
+                    credit_account_key,
+                    test_sum!(),
+                    extra,
+                );
+            });
+        let extras = |book: &TestBook| {
+            book.transactions[0]
+                .moves
+                .iter()
+                .map(|move_| move_.extra)
+                .collect::<Vec<_>>()
+        };
+        book.set_move_index(TransactionIndex(0), MoveIndex(0), MoveIndex(2));
+        assert_eq!(extras(&book), vec!["b", "c", "a"]);
+        book.set_move_index(TransactionIndex(0), MoveIndex(2), MoveIndex(1));
+        assert_eq!(extras(&book), vec!["b", "a", "c"]);
+        book.set_move_index(TransactionIndex(0), MoveIndex(1), MoveIndex(1));
+        assert_eq!(extras(&book), vec!["b", "a", "c"]);
+    }
+    #[test]
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn set_move_side_panic_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.set_move_side(
+            TransactionIndex(0),
+            MoveIndex(0),
+            Debit,
+            account_key,
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn set_move_side_panic_move_out_of_bounds() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.set_move_side(
+            TransactionIndex(0),
+            MoveIndex(0),
+            Debit,
+            account_key,
+        );
+    }
+    #[test]
+    #[should_panic(expected = "No account found for key ")]
+    fn set_move_side_panic_account_not_found() {
+        let mut book = TestBook::default();
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
         book.insert_transaction(TransactionIndex(0), "");
         book.insert_move(
             TransactionIndex(0),
-            MoveIndex(1),
-            debit_key,
-            credit_key,
-            sum!(),
+            MoveIndex(0),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
             "",
         );
+        let other_account_key = book.insert_account("");
+        book.accounts.remove(other_account_key);
+        book.set_move_side(
+            TransactionIndex(0),
+            MoveIndex(0),
+            Debit,
+            other_account_key,
+        );
     }
     #[test]
-    #[should_panic(expected = "No account found for key ")]
-    fn insert_move_panic_debit_account_not_found() {
+    #[should_panic(
+        expected = "Provided debit account is same as existing credit account."
+    )]
+    fn set_move_side_panic_provided_debit_same_as_credit() {
         let mut book = TestBook::default();
-        let debit_key = book.insert_account("");
-        book.accounts.remove(debit_key);
-        let credit_key = book.insert_account("");
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
         book.insert_transaction(TransactionIndex(0), "");
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            debit_key,
-            credit_key,
-            sum!(),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
             "",
         );
+        book.set_move_side(
+            TransactionIndex(0),
+            MoveIndex(0),
+            Debit,
+            credit_account_key,
+        );
     }
     #[test]
-    #[should_panic(expected = "No account found for key ")]
-    fn insert_move_panic_credit_account_not_found() {
+    #[should_panic(
+        expected = "Provided credit account is same as existing debit account."
+    )]
+    fn set_move_side_panic_provided_credit_same_as_debit() {
         let mut book = TestBook::default();
-        let debit_key = book.insert_account("");
-        let credit_key = book.insert_account("");
-        book.accounts.remove(credit_key);
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
         book.insert_transaction(TransactionIndex(0), "");
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            debit_key,
-            credit_key,
-            sum!(),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
             "",
         );
+        book.set_move_side(
+            TransactionIndex(0),
+            MoveIndex(0),
+            Credit,
+            debit_account_key,
+        );
     }
     #[test]
-    fn insert_move() {
+    fn set_move_side() {
         let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
         book.insert_transaction(TransactionIndex(0), "");
-        let debit_key = book.insert_account("");
-        let credit_key = book.insert_account("");
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            debit_key,
-            credit_key,
-            sum!(),
-            "a",
+            account_a_key,
+            account_b_key,
+            test_sum!(),
+            "",
         );
-        book.insert_move(
+        book.set_move_side(
             TransactionIndex(0),
             MoveIndex(0),
-            debit_key,
-            credit_key,
-            sum!(),
-            "b",
+            Debit,
+            account_c_key,
         );
-        book.insert_move(
-            TransactionIndex(0),
-            MoveIndex(1),
-            debit_key,
-            credit_key,
-            sum!(),
-            "c",
+        assert_eq!(
+            book.transactions[0].moves[0].debit_account_key,
+            account_c_key
         );
-        book.insert_move(
+        assert_eq!(
+            book.transactions[0].moves[0].credit_account_key,
+            account_b_key
+        );
+        book.set_move_side(
             TransactionIndex(0),
-            MoveIndex(2),
-            debit_key,
-            credit_key,
-            sum!(),
-            "d",
+            MoveIndex(0),
+            Credit,
+            account_a_key,
         );
         assert_eq!(
-            book.transactions[0]
-                .moves
-                .iter()
-                .map(|move_| move_.extra)
-                .collect::<Vec<_>>(),
-            vec!["b", "c", "d", "a"],
+            book.transactions[0].moves[0].debit_account_key,
+            account_c_key
+        );
+        assert_eq!(
+            book.transactions[0].moves[0].credit_account_key,
+            account_a_key
         );
     }
     #[test]
-    fn accounts() {
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn set_move_sum_panic_transaction_out_of_bounds() {
         let mut book = TestBook::default();
-        assert!(book.accounts().next().is_none());
-        let account_a_key = book.insert_account("a");
-        let account_b_key = book.insert_account("b");
-        let expected = vec![(account_a_key, &"a"), (account_b_key, &"b")];
-        let actual = book.accounts().collect::<Vec<_>>();
-        assert_eq!(actual, expected);
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), test_sum!());
     }
     #[test]
-    fn get_account() {
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn set_move_sum_panic_move_out_of_bounds() {
         let mut book = TestBook::default();
-        book.insert_account("");
-        let account_key = book.insert_account("!");
-        book.insert_account("");
-        let account = book.get_account(account_key);
-        assert_eq!(*account, "!");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), test_sum!());
     }
     #[test]
-    #[should_panic(expected = "No account found for key ")]
-    fn assert_has_account() {
+    fn set_move_sum() {
         let mut book = TestBook::default();
-        let account_key = book.insert_account("");
-        book.accounts.remove(account_key);
-        book.assert_has_account(account_key);
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
+            "",
+        );
+        let usd = "USD";
+        book.set_move_sum(
+            TransactionIndex(0),
+            MoveIndex(0),
+            test_sum!(100, usd),
+        );
+        assert_eq!(
+            book.transactions[0].moves[0].sum.0.get(&usd).unwrap(),
+            &100,
+        );
     }
     #[test]
-    #[should_panic(expected = "No account found for key ")]
-    fn account_balance_at_transaction_account_not_found() {
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn move_sum_panic_transaction_out_of_bounds() {
+        let book = TestBook::default();
+        book.move_sum(TransactionIndex(0), MoveIndex(0));
+    }
+    #[test]
+    #[should_panic(
+        expected = "index out of bounds: the len is 0 but the index is 0"
+    )]
+    fn move_sum_panic_move_out_of_bounds() {
         let mut book = TestBook::default();
         book.insert_transaction(TransactionIndex(0), "");
-        let account_key = book.insert_account("");
-        book.accounts.remove(account_key);
-        book.account_balance_at_transaction::<i128>(
-            account_key,
-            TransactionIndex(0),
-        );
+        book.move_sum(TransactionIndex(0), MoveIndex(0));
     }
     #[test]
-    fn account_balance_at_transaction() {
+    fn move_sum() {
         let mut book = TestBook::default();
-        let account_a_key = book.insert_account("");
-        let account_b_key = book.insert_account("");
-        let usd = "USD";
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
         book.insert_transaction(TransactionIndex(0), "");
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            account_a_key,
-            account_b_key,
-            sum!(3, usd),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
             "",
         );
         assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_a_key,
-                TransactionIndex(0)
-            ),
-            TestBalance::default() - &sum!(3, usd),
+            book.move_sum(TransactionIndex(0), MoveIndex(0)),
+            &test_sum!()
+        );
+        let usd = "USD";
+        book.set_move_sum(
+            TransactionIndex(0),
+            MoveIndex(0),
+            test_sum!(100, usd),
         );
         assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_b_key,
-                TransactionIndex(0)
-            ),
-            TestBalance::default() + &sum!(3, usd),
+            book.move_sum(TransactionIndex(0), MoveIndex(0)),
+            &test_sum!(100, usd),
+        );
+    }
+    #[test]
+    fn validate() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let closed_key = book.insert_account("");
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(),
+            "",
         );
+        assert_eq!(book.validate(), Ok(()));
         book.insert_transaction(TransactionIndex(1), "");
         book.insert_move(
             TransactionIndex(1),
             MoveIndex(0),
-            account_a_key,
-            account_b_key,
-            sum!(4, usd),
+            closed_key,
+            bank_key,
+            test_sum!(),
             "",
         );
+        book.accounts.remove(closed_key);
+        book.transactions[0].moves[0].credit_account_key = wallet_key;
         assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_a_key,
-                TransactionIndex(0)
-            ),
-            TestBalance::default() - &sum!(3, usd),
-        );
-        assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_b_key,
-                TransactionIndex(0)
-            ),
-            TestBalance::default() + &sum!(3, usd),
-        );
-        assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_a_key,
-                TransactionIndex(1)
-            ),
-            TestBalance::default() - &sum!(7, usd),
+            book.validate(),
+            Err(vec![
+                ValidationIssue::SelfMove(
+                    TransactionIndex(0),
+                    MoveIndex(0),
+                    wallet_key,
+                ),
+                ValidationIssue::AccountNotFound(
+                    TransactionIndex(1),
+                    MoveIndex(0),
+                    closed_key,
+                ),
+            ]),
         );
-        assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_b_key,
-                TransactionIndex(1)
-            ),
-            TestBalance::default() + &sum!(7, usd),
+    }
+    #[test]
+    fn diff() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        let closed_key = book.insert_account("closed");
+        let usd = "USD";
+        (0..3).for_each(|index| {
+            book.insert_transaction(TransactionIndex(index), "");
+            (0..2).for_each(|move_index| {
+                book.insert_move(
+                    TransactionIndex(index),
+                    MoveIndex(move_index),
+                    wallet_key,
+                    bank_key,
+                    test_sum!(1, usd),
+                    "",
+                );
+            });
+        });
+        let mut other = TestBook {
+            account_keys: book.account_keys.clone(),
+            accounts: book.accounts.clone(),
+            transactions: book.transactions.clone(),
+            ..TestBook::default()
+        };
+        assert!(book.diff(&other).is_empty());
+        other.set_account(bank_key, "savings");
+        other.accounts.remove(closed_key);
+        let card_key = other.insert_account("card");
+        other.set_transaction_extra(TransactionIndex(0), "edited");
+        other.set_move_sum(
+            TransactionIndex(1),
+            MoveIndex(1),
+            test_sum!(2, usd),
         );
-        book.insert_transaction(TransactionIndex(0), "");
-        book.insert_move(
-            TransactionIndex(0),
-            MoveIndex(0),
-            account_a_key,
-            account_b_key,
-            sum!(1, usd),
+        other.remove_move(TransactionIndex(0), MoveIndex(1));
+        other.insert_move(
+            TransactionIndex(1),
+            MoveIndex(2),
+            wallet_key,
+            card_key,
+            test_sum!(1, usd),
             "",
         );
-        assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_a_key,
-                TransactionIndex(0)
+        other.remove_transaction(TransactionIndex(2));
+        let expected = BookDiff {
+            added_accounts: vec![card_key],
+            removed_accounts: vec![closed_key],
+            changed_accounts: vec![bank_key],
+            added_transactions: vec![],
+            removed_transactions: vec![TransactionIndex(2)],
+            changed_transactions: vec![TransactionIndex(0)],
+            added_moves: vec![(TransactionIndex(1), MoveIndex(2))],
+            removed_moves: vec![(TransactionIndex(0), MoveIndex(1))],
+            changed_moves: vec![(TransactionIndex(1), MoveIndex(1))],
+        };
+        assert_eq!(book.diff(&other), expected);
+        let reverse = other.diff(&book);
+        assert_eq!(reverse.added_transactions, vec![TransactionIndex(2)]);
+        assert_eq!(reverse.added_accounts, vec![closed_key]);
+    }
+    #[test]
+    fn apply() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("");
+        let bank_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "a");
+        let result = book.apply(vec![
+            BookOp::InsertTransaction(TransactionIndex(0), "b"),
+            BookOp::InsertMove(
+                TransactionIndex(0),
+                MoveIndex(0),
+                wallet_key,
+                bank_key,
+                test_sum!(3, usd),
+                "",
             ),
-            TestBalance::default() - &sum!(1, usd),
-        );
-        assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_b_key,
-                TransactionIndex(0)
+            BookOp::SetMoveSum(
+                TransactionIndex(1),
+                MoveIndex(0),
+                test_sum!(1, usd),
             ),
-            TestBalance::default() + &sum!(1, usd),
-        );
+            BookOp::SetAccount(bank_key, "bank"),
+        ]);
         assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_a_key,
-                TransactionIndex(1)
-            ),
-            TestBalance::default() - &sum!(4, usd),
+            result,
+            Err((
+                2,
+                BookOpError::MoveIndexOutOfBounds(
+                    TransactionIndex(1),
+                    MoveIndex(0),
+                ),
+            )),
         );
-        assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_b_key,
-                TransactionIndex(1)
+        assert_eq!(book.transactions.len(), 1);
+        assert_eq!(book.transactions[0].extra, "a");
+        assert_eq!(*book.get_account(bank_key), "");
+        assert!(book.undo());
+        assert!(book.transactions.is_empty());
+        assert!(book.redo());
+        let result = book.apply(vec![
+            BookOp::InsertTransaction(TransactionIndex(0), "b"),
+            BookOp::InsertMove(
+                TransactionIndex(0),
+                MoveIndex(0),
+                wallet_key,
+                bank_key,
+                test_sum!(3, usd),
+                "",
             ),
-            TestBalance::default() + &sum!(4, usd),
-        );
-        assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_a_key,
-                TransactionIndex(2)
+            BookOp::SetMoveSum(
+                TransactionIndex(0),
+                MoveIndex(0),
+                test_sum!(1, usd),
             ),
-            TestBalance::default() - &sum!(8, usd),
-        );
+            BookOp::SetAccount(bank_key, "bank"),
+        ]);
+        assert_eq!(result, Ok(()));
+        assert_eq!(book.transactions.len(), 2);
+        assert_eq!(book.transactions[0].extra, "b");
         assert_eq!(
-            book.account_balance_at_transaction::<i128>(
-                account_b_key,
-                TransactionIndex(2)
-            ),
-            TestBalance::default() + &sum!(8, usd),
+            *book.move_sum(TransactionIndex(0), MoveIndex(0)),
+            test_sum!(1, usd)
         );
+        assert_eq!(*book.get_account(bank_key), "bank");
+        assert!(book.undo());
+        assert_eq!(book.transactions.len(), 1);
+        assert_eq!(book.transactions[0].extra, "a");
+        assert_eq!(*book.get_account(bank_key), "");
     }
     #[test]
-    #[should_panic(expected = "No account found for key ")]
-    fn set_account_panic() {
-        let mut book = TestBook::default();
-        let account_key = book.insert_account("");
-        book.accounts.remove(account_key);
-        book.set_account(account_key, "!");
-    }
-    #[test]
-    fn set_account() {
-        let mut book = TestBook::default();
-        let account_key = book.insert_account("");
-        book.set_account(account_key, "!");
-        assert_eq!(*book.accounts.get(account_key).unwrap(), "!");
-    }
-    #[test]
-    #[should_panic(expected = "index out of bounds")]
-    fn set_move_extra_panic_transaction_index_out_of_bounds() {
+    fn apply_errors() {
         let mut book = TestBook::default();
-        book.set_move_extra(TransactionIndex(0), MoveIndex(0), "");
+        let wallet_key = book.insert_account("");
+        let closed_key = book.insert_account("");
+        book.accounts.remove(closed_key);
+        book.insert_transaction(TransactionIndex(0), "");
+        let insert_move = |debit, credit| {
+            BookOp::InsertMove(
+                TransactionIndex(0),
+                MoveIndex(0),
+                debit,
+                credit,
+                test_sum!(),
+                "",
+            )
+        };
+        assert_eq!(
+            book.apply(vec![insert_move(wallet_key, closed_key)]),
+            Err((0, BookOpError::AccountNotFound(closed_key))),
+        );
+        assert_eq!(
+            book.apply(vec![insert_move(wallet_key, wallet_key)]),
+            Err((0, BookOpError::SameAccounts(wallet_key))),
+        );
+        assert_eq!(
+            book.apply(vec![
+                BookOp::RemoveTransaction(TransactionIndex(0)),
+                BookOp::SetTransactionExtra(TransactionIndex(0), ""),
+            ]),
+            Err((
+                1,
+                BookOpError::TransactionIndexOutOfBounds(TransactionIndex(0)),
+            )),
+        );
+        assert_eq!(book.transactions.len(), 1);
+        book.set_reject_empty_sums(true);
+        let closed_key = book.insert_account("");
+        assert_eq!(
+            book.apply(vec![insert_move(wallet_key, closed_key)]),
+            Err((0, BookOpError::EmptySum(TransactionIndex(0), MoveIndex(0)))),
+        );
     }
     #[test]
-    #[should_panic(expected = "index out of bounds")]
-    fn set_move_extra_panic_move_index_out_of_bounds() {
+    fn reject_empty_sums() {
         let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
         book.insert_transaction(TransactionIndex(0), "");
-        book.set_move_extra(TransactionIndex(0), MoveIndex(1), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(),
+            "",
+        );
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), test_sum!());
+        assert_eq!(book.transactions[0].moves.len(), 1);
+        book.set_reject_empty_sums(true);
+        let usd = "USD";
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            debit_key,
+            credit_key,
+            test_sum!(1, usd),
+            "",
+        );
+        assert_eq!(book.transactions[0].moves.len(), 2);
     }
     #[test]
-    fn set_move_extra() {
+    #[should_panic(expected = "Empty sums are rejected.")]
+    fn reject_empty_sums_panic_insert_move() {
         let mut book = TestBook::default();
         let debit_key = book.insert_account("");
         let credit_key = book.insert_account("");
         book.insert_transaction(TransactionIndex(0), "");
+        book.set_reject_empty_sums(true);
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
             debit_key,
             credit_key,
-            sum!(),
+            test_sum!(),
             "",
         );
-        book.set_move_extra(TransactionIndex(0), MoveIndex(0), "!");
-        assert_eq!(*book.transactions[0].moves[0].extra(), "!");
-    }
-    #[test]
-    #[should_panic(expected = "removal index (is 0) should be < len (is 0)")]
-    fn remove_transaction_panic_out_of_bounds() {
-        let mut book = TestBook::default();
-        book.remove_transaction(TransactionIndex(0));
     }
     #[test]
-    fn remove_transaction() {
-        let mut book = TestBook::default();
-        book.insert_transaction(TransactionIndex(0), "a");
-        book.insert_transaction(TransactionIndex(1), "b");
-        book.remove_transaction(TransactionIndex(1));
-        assert_eq!(&book.transactions[0].extra, &"a");
-        book.remove_transaction(TransactionIndex(0));
-        assert!(book.transactions.is_empty());
-    }
-    #[test]
-    #[should_panic(
-        expected = "index out of bounds: the len is 0 but the index is 0"
-    )]
-    fn remove_move_panic_transaction_index_out_of_bounds() {
-        let mut book = TestBook::default();
-        book.remove_move(TransactionIndex(0), MoveIndex(0));
-    }
-    #[test]
-    #[should_panic(expected = "removal index (is 0) should be < len (is 0)")]
-    fn remove_move_panic_move_index_out_of_bounds() {
+    #[should_panic(expected = "Empty sums are rejected.")]
+    fn reject_empty_sums_panic_set_move_sum() {
         let mut book = TestBook::default();
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let usd = "USD";
         book.insert_transaction(TransactionIndex(0), "");
-        book.remove_move(TransactionIndex(0), MoveIndex(0));
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_key,
+            credit_key,
+            test_sum!(1, usd),
+            "",
+        );
+        book.set_reject_empty_sums(true);
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), test_sum!());
     }
     #[test]
-    fn remove_move() {
+    fn units() {
+        use super::InsertMoveError;
         let mut book = TestBook::default();
-        let debit_account_key = book.insert_account("");
-        let credit_account_key = book.insert_account("");
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
         book.insert_transaction(TransactionIndex(0), "");
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            debit_account_key,
-            credit_account_key,
-            sum!(),
-            "a",
+            debit_key,
+            credit_key,
+            test_sum!(1, thb),
+            "",
         );
+        assert_eq!(book.units().count(), 0);
+        book.insert_unit(usd);
+        book.insert_unit(usd);
+        assert_eq!(book.units().collect::<Vec<_>>(), [&usd]);
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(1),
-            debit_account_key,
-            credit_account_key,
-            sum!(),
-            "b",
+            debit_key,
+            credit_key,
+            test_sum!(1, usd),
+            "",
         );
-        book.remove_move(TransactionIndex(0), MoveIndex(1));
-        assert_eq!(&book.transactions[0].moves[0].extra, &"a");
-        book.remove_move(TransactionIndex(0), MoveIndex(0));
-        assert!(book.transactions[0].moves.is_empty());
+        assert_eq!(
+            book.try_insert_move(
+                TransactionIndex(0),
+                MoveIndex(2),
+                debit_key,
+                credit_key,
+                test_sum!(1, usd; 1, thb),
+                "",
+            ),
+            Err(InsertMoveError::UnitNotFound(thb)),
+        );
+        assert_eq!(
+            InsertMoveError::UnitNotFound(thb).to_string(),
+            "Unit \"THB\" is not in the book.",
+        );
+        assert_eq!(
+            book.apply(vec![BookOp::SetMoveSum(
+                TransactionIndex(0),
+                MoveIndex(1),
+                test_sum!(1, thb),
+            )]),
+            Err((
+                0,
+                BookOpError::UnitNotFound(
+                    TransactionIndex(0),
+                    MoveIndex(1),
+                    thb,
+                ),
+            )),
+        );
+        assert_eq!(book.validate(), Ok(()));
+        assert_eq!(book.transactions[0].moves.len(), 2);
     }
     #[test]
-    #[should_panic(
-        expected = "index out of bounds: the len is 0 but the index is 0"
-    )]
-    fn set_move_side_panic_transaction_out_of_bounds() {
+    #[should_panic(expected = "A unit of the sum is not in the book.")]
+    fn units_panic_insert_move() {
         let mut book = TestBook::default();
-        let account_key = book.insert_account("");
-        book.set_move_side(
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_unit(usd);
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            Debit,
-            account_key,
+            debit_key,
+            credit_key,
+            test_sum!(1, thb),
+            "",
         );
     }
     #[test]
-    #[should_panic(
-        expected = "index out of bounds: the len is 0 but the index is 0"
-    )]
-    fn set_move_side_panic_move_out_of_bounds() {
+    #[should_panic(expected = "A unit of the sum is not in the book.")]
+    fn units_panic_set_move_sum() {
         let mut book = TestBook::default();
-        let account_key = book.insert_account("");
+        let debit_key = book.insert_account("");
+        let credit_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_unit(usd);
         book.insert_transaction(TransactionIndex(0), "");
-        book.set_move_side(
+        book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            Debit,
-            account_key,
+            debit_key,
+            credit_key,
+            test_sum!(1, usd),
+            "",
         );
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), test_sum!(1, thb));
     }
-    #[test]
-    #[should_panic(expected = "No account found for key ")]
-    fn set_move_side_panic_account_not_found() {
+    /// A book with a move of a unit that was not in the book when the unit
+    /// of another move was inserted.
+    fn book_with_move_of_unit_not_found() -> TestBook {
         let mut book = TestBook::default();
-        let debit_account_key = book.insert_account("");
-        let credit_account_key = book.insert_account("");
+        let account_keys = book.insert_accounts(vec![""; 2]);
+        let thb = "THB";
         book.insert_transaction(TransactionIndex(0), "");
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            debit_account_key,
-            credit_account_key,
-            sum!(),
+            account_keys[0],
+            account_keys[1],
+            test_sum!(1, thb),
             "",
         );
-        let other_account_key = book.insert_account("");
-        book.accounts.remove(other_account_key);
-        book.set_move_side(
-            TransactionIndex(0),
-            MoveIndex(0),
-            Debit,
-            other_account_key,
-        );
+        book.insert_unit("USD");
+        book
     }
     #[test]
-    #[should_panic(
-        expected = "Provided debit account is same as existing credit account."
-    )]
-    fn set_move_side_panic_provided_debit_same_as_credit() {
+    #[should_panic(expected = "A unit of the sum is not in the book.")]
+    fn units_panic_append() {
         let mut book = TestBook::default();
-        let debit_account_key = book.insert_account("");
-        let credit_account_key = book.insert_account("");
-        book.insert_transaction(TransactionIndex(0), "");
-        book.insert_move(
+        book.insert_unit("USD");
+        book.append(book_with_move_of_unit_not_found());
+    }
+    #[test]
+    #[should_panic(expected = "Empty sums are rejected.")]
+    fn append_panic_empty_sum() {
+        let mut other = TestBook::default();
+        let account_keys = other.insert_accounts(vec![""; 2]);
+        other.insert_transaction(TransactionIndex(0), "");
+        other.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            debit_account_key,
-            credit_account_key,
-            sum!(),
+            account_keys[0],
+            account_keys[1],
+            test_sum!(),
             "",
         );
-        book.set_move_side(
+        let mut book = TestBook::default();
+        book.set_reject_empty_sums(true);
+        book.append(other);
+    }
+    #[test]
+    #[should_panic(expected = "A unit of the sum is not in the book.")]
+    fn units_panic_clone_transaction() {
+        book_with_move_of_unit_not_found()
+            .clone_transaction(TransactionIndex(0), TransactionIndex(1));
+    }
+    #[test]
+    #[should_panic(expected = "A unit of the sum is not in the book.")]
+    fn units_panic_reverse_transaction() {
+        book_with_move_of_unit_not_found().reverse_transaction(
             TransactionIndex(0),
-            MoveIndex(0),
-            Debit,
-            credit_account_key,
+            TransactionIndex(1),
+            "",
         );
     }
     #[test]
-    #[should_panic(
-        expected = "Provided credit account is same as existing debit account."
-    )]
-    fn set_move_side_panic_provided_credit_same_as_debit() {
-        let mut book = TestBook::default();
-        let debit_account_key = book.insert_account("");
-        let credit_account_key = book.insert_account("");
-        book.insert_transaction(TransactionIndex(0), "");
-        book.insert_move(
+    #[should_panic(expected = "A unit of the sum is not in the book.")]
+    fn units_panic_reverse_move() {
+        book_with_move_of_unit_not_found().reverse_move(
             TransactionIndex(0),
             MoveIndex(0),
-            debit_account_key,
-            credit_account_key,
-            sum!(),
+            TransactionIndex(0),
+            MoveIndex(1),
             "",
         );
-        book.set_move_side(
+    }
+    #[test]
+    fn units_without_debug() {
+        use super::Book;
+        #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+        struct Opaque;
+        let mut book = Book::<Opaque, u64, (), (), ()>::default();
+        let debit_key = book.insert_account(());
+        let credit_key = book.insert_account(());
+        book.insert_unit(Opaque);
+        book.insert_transaction(TransactionIndex(0), ());
+        let sum = crate::sum![1 => Opaque];
+        book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
-            Credit,
-            debit_account_key,
+            debit_key,
+            credit_key,
+            sum.clone(),
+            (),
         );
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), sum);
+        assert_eq!(book.transactions[0].moves.len(), 1);
     }
     #[test]
-    fn set_move_side() {
+    fn on_change() {
         let mut book = TestBook::default();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let handler_events = events.clone();
+        book.on_change(Box::new(move |event| {
+            handler_events.lock().unwrap().push(*event)
+        }));
         let account_a_key = book.insert_account("");
         let account_b_key = book.insert_account("");
         let account_c_key = book.insert_account("");
+        let usd = "USD";
+        book.set_account(account_c_key, "c");
         book.insert_transaction(TransactionIndex(0), "");
         book.insert_move(
             TransactionIndex(0),
             MoveIndex(0),
             account_a_key,
             account_b_key,
-            sum!(),
+            test_sum!(1, usd),
             "",
         );
-        book.set_move_side(
+        book.insert_move(
             TransactionIndex(0),
-            MoveIndex(0),
-            Debit,
-            account_c_key,
-        );
-        assert_eq!(
-            book.transactions[0].moves[0].debit_account_key,
-            account_c_key
-        );
-        assert_eq!(
-            book.transactions[0].moves[0].credit_account_key,
-            account_b_key
+            MoveIndex(1),
+            account_a_key,
+            account_b_key,
+            test_sum!(2, usd),
+            "",
         );
+        book.set_move_index(TransactionIndex(0), MoveIndex(1), MoveIndex(0));
+        book.set_move_sum(TransactionIndex(0), MoveIndex(0), test_sum!(3, usd));
+        book.set_move_extra(TransactionIndex(0), MoveIndex(0), "!");
         book.set_move_side(
             TransactionIndex(0),
-            MoveIndex(0),
+            MoveIndex(1),
             Credit,
-            account_a_key,
-        );
-        assert_eq!(
-            book.transactions[0].moves[0].debit_account_key,
-            account_c_key
+            account_c_key,
         );
+        book.compact_transaction(TransactionIndex(0));
+        book.remove_move(TransactionIndex(0), MoveIndex(1));
+        book.set_transaction_extra(TransactionIndex(0), "a");
+        book.clone_transaction(TransactionIndex(0), TransactionIndex(1));
+        book.insert_transaction(TransactionIndex(2), "b");
+        book.remove_transaction(TransactionIndex(2));
+        book.insert_transaction(TransactionIndex(2), "c");
+        book.insert_transaction(TransactionIndex(3), "c");
+        book.dedup_adjacent_transactions();
+        book.remove_transactions_matching(|_| true);
+        use crate::event::BookEvent::*;
         assert_eq!(
-            book.transactions[0].moves[0].credit_account_key,
-            account_a_key
+            *events.lock().unwrap(),
+            vec![
+                AccountInserted(account_a_key),
+                AccountInserted(account_b_key),
+                AccountInserted(account_c_key),
+                AccountSet(account_c_key),
+                TransactionInserted(TransactionIndex(0)),
+                MoveInserted(TransactionIndex(0), MoveIndex(0)),
+                MoveInserted(TransactionIndex(0), MoveIndex(1)),
+                MoveIndexSet(TransactionIndex(0), MoveIndex(1), MoveIndex(0)),
+                MoveSumSet(TransactionIndex(0), MoveIndex(0)),
+                MoveExtraSet(TransactionIndex(0), MoveIndex(0)),
+                MoveSideSet(TransactionIndex(0), MoveIndex(1), Credit),
+                MovesSet(TransactionIndex(0)),
+                MoveRemoved(TransactionIndex(0), MoveIndex(1)),
+                TransactionExtraSet(TransactionIndex(0)),
+                TransactionInserted(TransactionIndex(1)),
+                TransactionInserted(TransactionIndex(2)),
+                TransactionRemoved(TransactionIndex(2)),
+                TransactionInserted(TransactionIndex(2)),
+                TransactionInserted(TransactionIndex(3)),
+                TransactionRemoved(TransactionIndex(3)),
+                TransactionRemoved(TransactionIndex(1)),
+                TransactionRemoved(TransactionIndex(1)),
+                TransactionRemoved(TransactionIndex(0)),
+            ],
         );
     }
     #[test]
-    #[should_panic(
-        expected = "index out of bounds: the len is 0 but the index is 0"
-    )]
-    fn set_move_sum_panic_transaction_out_of_bounds() {
-        let mut book = TestBook::default();
-        book.set_move_sum(TransactionIndex(0), MoveIndex(0), sum!());
-    }
-    #[test]
-    #[should_panic(
-        expected = "index out of bounds: the len is 0 but the index is 0"
-    )]
-    fn set_move_sum_panic_move_out_of_bounds() {
+    fn undo_redo() {
         let mut book = TestBook::default();
+        assert!(!book.undo());
+        assert!(!book.redo());
+        let account_a_key = book.insert_account("a");
+        let account_b_key = book.insert_account("b");
+        let usd = "USD";
         book.insert_transaction(TransactionIndex(0), "");
-        book.set_move_sum(TransactionIndex(0), MoveIndex(0), sum!());
+        (0..3).for_each(|index| {
+            book.insert_move(
+                TransactionIndex(0),
+                MoveIndex(index),
+                account_a_key,
+                account_b_key,
+                test_sum!(index as u64, usd),
+                "",
+            );
+        });
+        let moves = |book: &TestBook| {
+            book.transactions[0]
+                .moves
+                .iter()
+                .map(|move_| move_.sum.clone())
+                .collect::<Vec<_>>()
+        };
+        (0..3).rev().for_each(|len| {
+            assert!(book.undo());
+            assert_eq!(
+                moves(&book),
+                (0..len).map(|i| test_sum!(i, usd)).collect::<Vec<_>>()
+            );
+        });
+        (1..4).for_each(|len| {
+            assert!(book.redo());
+            assert_eq!(
+                moves(&book),
+                (0..len).map(|i| test_sum!(i, usd)).collect::<Vec<_>>()
+            );
+        });
+        assert!(!book.redo());
+        (0..4).for_each(|_| assert!(book.undo()));
+        assert!(book.transactions.is_empty());
+        assert!(book.undo());
+        assert!(book.undo());
+        assert!(book.accounts().next().is_none());
+        assert!(!book.undo());
+        assert!(book.redo());
+        assert_eq!(
+            book.accounts().collect::<Vec<_>>(),
+            vec![(account_a_key, &"a")],
+        );
+        book.set_account(account_a_key, "!");
+        assert!(!book.redo());
+        assert!(book.undo());
+        assert_eq!(*book.get_account(account_a_key), "a");
     }
     #[test]
-    fn set_move_sum() {
-        let mut book = TestBook::default();
-        let debit_account_key = book.insert_account("");
-        let credit_account_key = book.insert_account("");
-        book.insert_transaction(TransactionIndex(0), "");
-        book.insert_move(
-            TransactionIndex(0),
-            MoveIndex(0),
-            debit_account_key,
-            credit_account_key,
-            sum!(),
-            "",
+    fn undo_redo_each_change() {
+        type Snapshot = (
+            Vec<(AccountKey, &'static str)>,
+            Vec<Transaction<&'static str, u64, &'static str, &'static str>>,
         );
+        fn snapshot(book: &TestBook) -> Snapshot {
+            (
+                book.accounts().map(|(key, extra)| (key, *extra)).collect(),
+                book.transactions.clone(),
+            )
+        }
+        let mut book = TestBook::default();
+        let mut snapshots = vec![snapshot(&book)];
+        let account_a_key = book.insert_account("a");
+        snapshots.push(snapshot(&book));
+        let account_b_key = book.insert_account("b");
+        snapshots.push(snapshot(&book));
+        let account_c_key = book.insert_account("c");
+        snapshots.push(snapshot(&book));
         let usd = "USD";
-        book.set_move_sum(TransactionIndex(0), MoveIndex(0), sum!(100, usd));
-        assert_eq!(
-            book.transactions[0].moves[0].sum.0.get(&usd).unwrap(),
-            &100,
-        );
+        type Change<'a> = Box<dyn Fn(&mut TestBook) + 'a>;
+        let changes: Vec<Change> = vec![
+            Box::new(|book| book.set_account(account_c_key, "C")),
+            Box::new(|book| book.insert_transaction(TransactionIndex(0), "t")),
+            Box::new(|book| {
+                book.insert_move(
+                    TransactionIndex(0),
+                    MoveIndex(0),
+                    account_a_key,
+                    account_b_key,
+                    test_sum!(1, usd),
+                    "m",
+                )
+            }),
+            Box::new(|book| {
+                book.insert_move(
+                    TransactionIndex(0),
+                    MoveIndex(1),
+                    account_a_key,
+                    account_b_key,
+                    test_sum!(2, usd),
+                    "n",
+                )
+            }),
+            Box::new(|book| {
+                book.set_move_index(
+                    TransactionIndex(0),
+                    MoveIndex(1),
+                    MoveIndex(0),
+                )
+            }),
+            Box::new(|book| {
+                book.set_move_sum(
+                    TransactionIndex(0),
+                    MoveIndex(0),
+                    test_sum!(4, usd),
+                )
+            }),
+            Box::new(|book| {
+                book.set_move_extra(TransactionIndex(0), MoveIndex(0), "!")
+            }),
+            Box::new(|book| {
+                book.set_move_side(
+                    TransactionIndex(0),
+                    MoveIndex(0),
+                    Credit,
+                    account_c_key,
+                )
+            }),
+            Box::new(|book| {
+                book.set_move_side(
+                    TransactionIndex(0),
+                    MoveIndex(0),
+                    Credit,
+                    account_b_key,
+                )
+            }),
+            Box::new(|book| book.compact_transaction(TransactionIndex(0))),
+            Box::new(|book| {
+                book.set_transaction_extra(TransactionIndex(0), "u")
+            }),
+            Box::new(|book| {
+                book.clone_transaction(
+                    TransactionIndex(0),
+                    TransactionIndex(1),
+                );
+            }),
+            Box::new(|book| book.insert_transaction(TransactionIndex(2), "v")),
+            Box::new(|book| {
+                book.dedup_adjacent_transactions();
+            }),
+            Box::new(|book| {
+                book.remove_move(TransactionIndex(0), MoveIndex(0))
+            }),
+            Box::new(|book| book.remove_transaction(TransactionIndex(1))),
+            Box::new(|book| {
+                book.remove_transactions_matching(|_| true);
+            }),
+        ];
+        changes.iter().for_each(|change| {
+            change(&mut book);
+            snapshots.push(snapshot(&book));
+        });
+        snapshots.iter().rev().skip(1).for_each(|expected| {
+            assert!(book.undo());
+            assert!(snapshot(&book) == *expected);
+        });
+        assert!(!book.undo());
+        snapshots.iter().skip(1).for_each(|expected| {
+            assert!(book.redo());
+            assert!(snapshot(&book) == *expected);
+        });
+        assert!(!book.redo());
     }
 }
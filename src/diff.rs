@@ -0,0 +1,37 @@
+use crate::{
+    book::{AccountKey, TransactionIndex},
+    transaction::MoveIndex,
+};
+/// The differences between two books, as produced by
+/// [Book::diff](crate::Book::diff).
+///
+/// Transactions and moves are compared by index. Added items are those of
+/// the other book and removed items are those of the book that was
+/// diffed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookDiff {
+    /// Accounts that are only in the other book.
+    pub added_accounts: Vec<AccountKey>,
+    /// Accounts that are only in the book.
+    pub removed_accounts: Vec<AccountKey>,
+    /// Accounts whose extra data differs.
+    pub changed_accounts: Vec<AccountKey>,
+    /// Transactions that are only in the other book.
+    pub added_transactions: Vec<TransactionIndex>,
+    /// Transactions that are only in the book.
+    pub removed_transactions: Vec<TransactionIndex>,
+    /// Transactions in both books whose extra data differs.
+    pub changed_transactions: Vec<TransactionIndex>,
+    /// Moves of transactions in both books that are only in the other book.
+    pub added_moves: Vec<(TransactionIndex, MoveIndex)>,
+    /// Moves of transactions in both books that are only in the book.
+    pub removed_moves: Vec<(TransactionIndex, MoveIndex)>,
+    /// Moves in both books that differ.
+    pub changed_moves: Vec<(TransactionIndex, MoveIndex)>,
+}
+impl BookDiff {
+    /// Whether the books are the same.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{
+    book::{AccountKey, TransactionIndex},
+    move_::{Move, Side},
+    sum::Sum,
+    transaction::{MoveIndex, Transaction},
+};
+/// A reversible change to a book.
+///
+/// Applying an edit results in the edit that reverses it.
+pub(crate) enum Edit<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+    InsertAccount(AccountKey, AccountExtra),
+    RemoveAccount(AccountKey),
+    SetAccount(AccountKey, AccountExtra),
+    InsertTransaction(
+        TransactionIndex,
+        Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>,
+    ),
+    RemoveTransaction(TransactionIndex),
+    SetTransactionExtra(TransactionIndex, TransactionExtra),
+    SetTransactionIndex(TransactionIndex, TransactionIndex),
+    SetMoves(TransactionIndex, Vec<Move<Unit, SumNumber, MoveExtra>>),
+    InsertMove(
+        TransactionIndex,
+        MoveIndex,
+        Move<Unit, SumNumber, MoveExtra>,
+    ),
+    RemoveMove(TransactionIndex, MoveIndex),
+    SetMoveIndex(TransactionIndex, MoveIndex, MoveIndex),
+    /// Moves a move from one location to another, which is its index after
+    /// it is removed from the first.
+    RelocateMove((TransactionIndex, MoveIndex), (TransactionIndex, MoveIndex)),
+    SetMoveExtra(TransactionIndex, MoveIndex, MoveExtra),
+    SetMoveSum(TransactionIndex, MoveIndex, Sum<Unit, SumNumber>),
+    SetMoveSide(TransactionIndex, MoveIndex, Side, AccountKey),
+    /// Edits that are applied in order.
+    Batch(Vec<Self>),
+}
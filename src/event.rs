@@ -0,0 +1,43 @@
+use crate::{
+    book::{AccountKey, TransactionIndex},
+    move_::Side,
+    transaction::MoveIndex,
+};
+/// Describes a change to a [Book](crate::Book).
+///
+/// Indices are those at the time of the change. Several transactions
+/// removed at once are reported from the last to the first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookEvent {
+    /// An account was inserted.
+    AccountInserted(AccountKey),
+    /// An account was removed.
+    AccountRemoved(AccountKey),
+    /// An account was set.
+    AccountSet(AccountKey),
+    /// A transaction was inserted.
+    TransactionInserted(TransactionIndex),
+    /// The extra data of a transaction was set.
+    TransactionExtraSet(TransactionIndex),
+    /// A transaction was removed.
+    TransactionRemoved(TransactionIndex),
+    /// A transaction was relocated from one index to another.
+    TransactionIndexSet(TransactionIndex, TransactionIndex),
+    /// The moves of a transaction were replaced, as by
+    /// [Book::compact_transaction](crate::Book::compact_transaction).
+    MovesSet(TransactionIndex),
+    /// A move was inserted.
+    MoveInserted(TransactionIndex, MoveIndex),
+    /// A move was removed.
+    MoveRemoved(TransactionIndex, MoveIndex),
+    /// A move was relocated from one index to another in its transaction.
+    MoveIndexSet(TransactionIndex, MoveIndex, MoveIndex),
+    /// The extra data of a move was set.
+    MoveExtraSet(TransactionIndex, MoveIndex),
+    /// The sum of a move was set.
+    MoveSumSet(TransactionIndex, MoveIndex),
+    /// The account of one of the sides of a move was set.
+    MoveSideSet(TransactionIndex, MoveIndex, Side),
+}
+/// A handler of changes to a [Book](crate::Book).
+pub type BookEventHandler = Box<dyn FnMut(&BookEvent) + Send + Sync>;
@@ -0,0 +1,8 @@
+/// Formats amounts of units, such as for [Balance::format_with](crate::Balance::format_with).
+///
+/// Implement this to control presentation, such as currency symbols and
+/// thousands separators.
+pub trait FormatAmount<Unit, Number> {
+    /// Formats an amount of a unit.
+    fn format(&self, unit: &Unit, amount: &Number) -> String;
+}
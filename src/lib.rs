@@ -1,10 +1,10 @@
 #![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
 //#![deny(missing_docs)]
-#![deny(broken_intra_doc_links)]
+#![deny(rustdoc::broken_intra_doc_links)]
 //#![deny(private_intra_doc_links)]
 //#![deny(missing_crate_level_docs)]
 //#![deny(private_doc_tests)]
-#![deny(invalid_codeblock_attributes)]
+#![deny(rustdoc::invalid_codeblock_attributes)]
 #![doc(test(attr(deny(warnings))))]
 #![deny(clippy::all)]
 //! ![GitHub Workflow Status (branch)][ci]
@@ -19,6 +19,12 @@
 //! - Use your own number types
 //! - Arbitrary extra data
 //! - [Introduction documentation][mod@introduction]
+//! - Optional [serde] support via the `serde` feature
+//! - Optional parallel balance calculation via the `rayon` feature
+//! - Optional checked arithmetic via the `num-traits` feature
+//! - Optional CSV export via the `csv` feature
+//! - Optional generation of synthetic books for benchmarks via the `testing`
+//!   feature
 //!
 //! ## Non-features
 //!
@@ -27,10 +33,6 @@
 //! - Account types (asset/liability/income/expense/etc.)
 //! - Reports
 //!
-//! ## Todo
-//! - Cache balance calculations
-//! - Serialization
-//!
 //! ## Introduction
 //!
 //! Read [the entire introduction][mod@introduction].
@@ -56,6 +58,7 @@
 //!
 //! [ci]: https://img.shields.io/github/workflow/status/mightyiam/bookkeeping/Rust/master?logo=github
 //! [bookkeeping]: https://en.wikipedia.org/wiki/Bookkeeping
+//! [serde]: https://serde.rs
 #[macro_use]
 mod test_utils;
 macro_rules! introduction {
@@ -65,15 +68,43 @@ macro_rules! introduction {
     };
 }
 introduction!(include_str!("../introduction.md"));
+#[cfg(feature = "serde")]
+pub mod amount_as_string;
 mod balance;
 mod book;
+mod diff;
+mod edit;
+mod event;
+mod format;
+mod macros;
 mod move_;
+mod op;
+#[cfg(feature = "serde")]
+pub mod serde_entries;
+#[cfg(feature = "serde")]
+pub mod serde_nonzero;
+mod snapshot;
 mod sum;
 mod transaction;
+#[cfg(feature = "serde")]
+mod unit_amounts;
+mod validation;
+#[cfg(feature = "num-traits")]
+pub use crate::book::BalanceOverflowError;
 pub use crate::{
     balance::Balance,
-    book::{AccountKey, Book, TransactionIndex},
-    move_::{Move, Side},
+    book::{
+        AccountKey, Accounts, Book, InsertMoveError, MoveEditor,
+        NegativeBalanceError, RemoveAccountError, TransactionBuilder,
+        TransactionIndex, Transactions,
+    },
+    diff::BookDiff,
+    event::{BookEvent, BookEventHandler},
+    format::FormatAmount,
+    move_::{Move, ParseSideError, Side},
+    op::{BookOp, BookOpError},
+    snapshot::BookSnapshot,
     sum::Sum,
-    transaction::{MoveIndex, Transaction},
+    transaction::{MoveIndex, Moves, Transaction},
+    validation::ValidationIssue,
 };
@@ -0,0 +1,65 @@
+/// Creates a [Sum](crate::Sum) of amounts of units.
+///
+/// Each entry is of an amount and a unit. Units are moved, so they need not
+/// be `Copy`.
+///
+/// ```
+/// # use bookkeeping::sum;
+/// let usd = "USD".to_string();
+/// let thb = "THB".to_string();
+/// let sum = sum![3 => usd, 5 => thb];
+/// assert_eq!(sum.to_string(), "5 THB, 3 USD");
+/// let empty: bookkeeping::Sum<String, u64> = sum![];
+/// assert_eq!(empty.amounts().count(), 0);
+/// ```
+#[macro_export]
+macro_rules! sum {
+    ($($amount:expr => $unit:expr),* $(,)?) => {
+        <$crate::Sum<_, _> as ::std::iter::FromIterator<_>>::from_iter(
+            ::std::vec![$(($unit, $amount)),*],
+        )
+    };
+}
+/// Creates a [Balance](crate::Balance) of amounts of units.
+///
+/// Each entry is of an amount and a unit. Units are moved, so they need not
+/// be `Copy`.
+///
+/// ```
+/// # use bookkeeping::balance;
+/// let usd = "USD".to_string();
+/// let thb = "THB".to_string();
+/// let balance = balance![-3 => usd, 5 => thb];
+/// assert_eq!(balance.to_string(), "5 THB, -3 USD");
+/// ```
+#[macro_export]
+macro_rules! balance {
+    ($($amount:expr => $unit:expr),* $(,)?) => {
+        <$crate::Balance<_, _> as ::std::iter::FromIterator<_>>::from_iter(
+            ::std::vec![$(($unit, $amount)),*],
+        )
+    };
+}
+#[cfg(test)]
+mod test {
+    use crate::{Balance, Sum};
+    use maplit::btreemap;
+    #[test]
+    fn sum() {
+        let usd = "USD".to_string();
+        let thb = "THB".to_string();
+        let actual: Sum<String, u64> = crate::sum![];
+        assert_eq!(actual, Sum(btreemap! {}));
+        let actual = crate::sum![3 => usd.clone(), 5 => thb.clone(), 4 => usd,];
+        assert_eq!(actual, Sum(btreemap! { "USD".to_string() => 4, thb => 5 }));
+    }
+    #[test]
+    fn balance() {
+        let usd = "USD".to_string();
+        let thb = "THB".to_string();
+        let actual: Balance<String, i64> = crate::balance![];
+        assert_eq!(actual, Balance(btreemap! {}));
+        let actual = crate::balance![-3 => usd.clone(), 5 => thb.clone()];
+        assert_eq!(actual, Balance(btreemap! { usd => -3, thb => 5 }));
+    }
+}
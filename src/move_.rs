@@ -1,12 +1,58 @@
 use crate::{book::AccountKey, sum::Sum};
+use std::{error::Error, fmt, str::FromStr};
 /// Represents a side of a [Move].
+///
+/// Displays as `debit` or `credit` and parses from these, case-insensitively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Side {
     #[allow(missing_docs)]
     Debit,
     #[allow(missing_docs)]
     Credit,
 }
+impl Side {
+    /// Returns `0` for [Side::Debit] and `1` for [Side::Credit],
+    /// for indexing per-side data stored in a `[T; 2]`.
+    pub fn index(self) -> usize {
+        match self {
+            Side::Debit => 0,
+            Side::Credit => 1,
+        }
+    }
+}
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Debit => "debit",
+            Side::Credit => "credit",
+        })
+    }
+}
+impl FromStr for Side {
+    type Err = ParseSideError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("debit") {
+            Ok(Side::Debit)
+        } else if s.eq_ignore_ascii_case("credit") {
+            Ok(Side::Credit)
+        } else {
+            Err(ParseSideError(s.to_string()))
+        }
+    }
+}
+/// The error of parsing a [Side] from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSideError(String);
+impl fmt::Display for ParseSideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is neither \"debit\" nor \"credit\"", self.0)
+    }
+}
+impl Error for ParseSideError {}
 /// Represents a move of a [Sum] from one account to another.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Move<Unit, Number, Extra>
 where
     Unit: Ord,
@@ -44,32 +90,74 @@ where
             Side::Credit => self.credit_account_key,
         }
     }
+    /// Gets the account key of the debit side of a move.
+    pub fn debit_account_key(&self) -> AccountKey {
+        self.debit_account_key
+    }
+    /// Gets the account key of the credit side of a move.
+    pub fn credit_account_key(&self) -> AccountKey {
+        self.credit_account_key
+    }
+    /// Gets the account keys of both sides of a move, debit first.
+    pub fn account_keys(&self) -> (AccountKey, AccountKey) {
+        (self.debit_account_key, self.credit_account_key)
+    }
+    /// Gets the side that an account is on, if any.
+    pub(crate) fn account_side(&self, account_key: AccountKey) -> Option<Side> {
+        if self.debit_account_key == account_key {
+            Some(Side::Debit)
+        } else if self.credit_account_key == account_key {
+            Some(Side::Credit)
+        } else {
+            None
+        }
+    }
     /// Gets the sum of a move.
     pub fn sum(&self) -> &Sum<Unit, Number> {
         &self.sum
     }
+    /// Gets the amount of a unit in the sum of the move, or the default
+    /// amount if the unit is absent.
+    pub fn amount_or_default(&self, unit: &Unit) -> Number
+    where
+        Number: Default + Clone,
+    {
+        self.sum.unit_amount(unit).cloned().unwrap_or_default()
+    }
     /// Gets the extra data of the move.
     pub fn extra(&self) -> &Extra {
         &self.extra
     }
+    /// Converts the extra data of the move.
+    pub fn map_extra<B>(
+        self,
+        f: impl FnOnce(Extra) -> B,
+    ) -> Move<Unit, Number, B> {
+        Move {
+            extra: f(self.extra),
+            debit_account_key: self.debit_account_key,
+            credit_account_key: self.credit_account_key,
+            sum: self.sum,
+        }
+    }
 }
 #[cfg(test)]
 mod test {
-    use super::{Move, Side};
+    use super::{Move, ParseSideError, Side};
     use crate::test_utils::TestBook;
     #[test]
     #[should_panic(expected = "Debit and credit accounts are the same.")]
     fn new_panic_debit_and_credit_accounts_are_the_same() {
         let mut book = TestBook::default();
         let account_key = book.insert_account("");
-        Move::new(account_key, account_key, sum!(), ());
+        Move::new(account_key, account_key, test_sum!(), ());
     }
     #[test]
     fn new() {
         let mut book = TestBook::default();
         let debit_account_key = book.insert_account("");
         let credit_account_key = book.insert_account("");
-        let sum = sum!();
+        let sum = test_sum!();
         let move_ =
             Move::new(debit_account_key, credit_account_key, sum.clone(), ());
         assert_eq!(move_.debit_account_key, debit_account_key);
@@ -82,28 +170,152 @@ mod test {
         let debit_account_key = book.insert_account("");
         let credit_account_key = book.insert_account("");
         let move_ =
-            Move::new(debit_account_key, credit_account_key, sum!(), "");
+            Move::new(debit_account_key, credit_account_key, test_sum!(), "");
         assert_eq!(move_.side_key(Side::Debit), debit_account_key);
         assert_eq!(move_.side_key(Side::Credit), credit_account_key);
     }
     #[test]
+    fn account_keys() {
+        let mut book = TestBook::default();
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        let move_ =
+            Move::new(debit_account_key, credit_account_key, test_sum!(), "");
+        assert_eq!(move_.debit_account_key(), debit_account_key);
+        assert_eq!(move_.credit_account_key(), credit_account_key);
+        assert_eq!(
+            move_.account_keys(),
+            (debit_account_key, credit_account_key),
+        );
+    }
+    #[test]
     fn sum() {
         let mut book = TestBook::default();
         let debit_account_key = book.insert_account("");
         let credit_account_key = book.insert_account("");
         let thb = "THB";
         let ils = "ILS";
-        let sum = sum!(100, thb; 200, ils);
+        let sum = test_sum!(100, thb; 200, ils);
         let move_ =
             Move::new(debit_account_key, credit_account_key, sum.clone(), "");
         assert_eq!(*move_.sum(), sum);
     }
     #[test]
+    fn amount_or_default() {
+        let mut book = TestBook::default();
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        let thb = "THB";
+        let ils = "ILS";
+        let move_ = Move::new(
+            debit_account_key,
+            credit_account_key,
+            test_sum!(100, thb),
+            "",
+        );
+        assert_eq!(move_.amount_or_default(&thb), 100);
+        assert_eq!(move_.amount_or_default(&ils), 0);
+    }
+    #[test]
     fn extra() {
         let mut book = TestBook::default();
         let debit_account_key = book.insert_account("");
         let credit_account_key = book.insert_account("");
-        let move_ = Move::new(debit_account_key, credit_account_key, sum!(), 5);
+        let move_ =
+            Move::new(debit_account_key, credit_account_key, test_sum!(), 5);
         assert_eq!(*move_.extra(), 5);
     }
+    #[test]
+    fn map_extra() {
+        let mut book = TestBook::default();
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        let usd = "USD";
+        let move_ = Move::new(
+            debit_account_key,
+            credit_account_key,
+            test_sum!(1, usd),
+            "rent",
+        )
+        .map_extra(|extra| extra.to_uppercase());
+        assert_eq!(move_.extra, "RENT".to_string());
+        assert_eq!(move_.debit_account_key, debit_account_key);
+        assert_eq!(move_.credit_account_key, credit_account_key);
+        assert_eq!(move_.sum, test_sum!(1, usd));
+    }
+    #[test]
+    fn eq() {
+        let mut book = TestBook::default();
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        let move_ =
+            || Move::new(account_a_key, account_b_key, test_sum!(1, usd), "");
+        assert!(move_() == move_());
+        assert!(
+            move_()
+                != Move::new(
+                    account_c_key,
+                    account_b_key,
+                    test_sum!(1, usd),
+                    ""
+                )
+        );
+        assert!(
+            move_()
+                != Move::new(
+                    account_a_key,
+                    account_c_key,
+                    test_sum!(1, usd),
+                    ""
+                )
+        );
+        assert!(
+            move_()
+                != Move::new(
+                    account_a_key,
+                    account_b_key,
+                    test_sum!(2, usd),
+                    ""
+                )
+        );
+        assert!(
+            move_()
+                != Move::new(
+                    account_a_key,
+                    account_b_key,
+                    test_sum!(1, usd),
+                    "!"
+                )
+        );
+    }
+    #[test]
+    fn side_from_str() {
+        assert!(matches!("debit".parse(), Ok(Side::Debit)));
+        assert!(matches!("Debit".parse(), Ok(Side::Debit)));
+        assert!(matches!("credit".parse(), Ok(Side::Credit)));
+        assert!(matches!("CREDIT".parse(), Ok(Side::Credit)));
+        let error = "debt".parse::<Side>().err().unwrap();
+        assert_eq!(error, ParseSideError("debt".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "\"debt\" is neither \"debit\" nor \"credit\""
+        );
+    }
+    #[test]
+    fn side_index() {
+        let mut account_keys = ["a", "b"];
+        assert_eq!(account_keys[Side::Debit.index()], "a");
+        assert_eq!(account_keys[Side::Credit.index()], "b");
+        account_keys[Side::Credit.index()] = "c";
+        assert_eq!(account_keys, ["a", "c"]);
+    }
+    #[test]
+    fn side_display() {
+        assert_eq!(Side::Debit.to_string(), "debit");
+        assert_eq!(Side::Credit.to_string(), "credit");
+        assert!(matches!(Side::Debit.to_string().parse(), Ok(Side::Debit)));
+        assert!(matches!(Side::Credit.to_string().parse(), Ok(Side::Credit)));
+    }
 }
@@ -0,0 +1,154 @@
+use crate::{
+    book::{AccountKey, TransactionIndex},
+    edit::Edit,
+    move_::Move,
+    sum::Sum,
+    transaction::{MoveIndex, Transaction},
+};
+use std::{error::Error, fmt};
+/// A change to a [Book](crate::Book), for applying several changes at once
+/// using [Book::apply](crate::Book::apply).
+pub enum BookOp<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+    /// Sets an existing account.
+    SetAccount(AccountKey, AccountExtra),
+    /// Creates a transaction and inserts it at an index.
+    InsertTransaction(TransactionIndex, TransactionExtra),
+    /// Removes an existing transaction.
+    RemoveTransaction(TransactionIndex),
+    /// Sets the extra data of an existing transaction.
+    SetTransactionExtra(TransactionIndex, TransactionExtra),
+    /// Creates a new move, from the debit account to the credit account,
+    /// and inserts it into a transaction at an index.
+    InsertMove(
+        TransactionIndex,
+        MoveIndex,
+        AccountKey,
+        AccountKey,
+        Sum<Unit, SumNumber>,
+        MoveExtra,
+    ),
+    /// Removes an existing move.
+    RemoveMove(TransactionIndex, MoveIndex),
+    /// Sets the extra data of an existing move.
+    SetMoveExtra(TransactionIndex, MoveIndex, MoveExtra),
+    /// Sets the sum of an existing move.
+    SetMoveSum(TransactionIndex, MoveIndex, Sum<Unit, SumNumber>),
+}
+impl<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+    BookOp<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+    pub(crate) fn into_edit(
+        self,
+    ) -> Edit<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra> {
+        match self {
+            BookOp::SetAccount(account_key, extra) => {
+                Edit::SetAccount(account_key, extra)
+            }
+            BookOp::InsertTransaction(transaction_index, extra) => {
+                Edit::InsertTransaction(
+                    transaction_index,
+                    Transaction {
+                        extra,
+                        moves: Vec::new(),
+                    },
+                )
+            }
+            BookOp::RemoveTransaction(transaction_index) => {
+                Edit::RemoveTransaction(transaction_index)
+            }
+            BookOp::SetTransactionExtra(transaction_index, extra) => {
+                Edit::SetTransactionExtra(transaction_index, extra)
+            }
+            BookOp::InsertMove(
+                transaction_index,
+                move_index,
+                debit_account_key,
+                credit_account_key,
+                sum,
+                extra,
+            ) => Edit::InsertMove(
+                transaction_index,
+                move_index,
+                Move::new(debit_account_key, credit_account_key, sum, extra),
+            ),
+            BookOp::RemoveMove(transaction_index, move_index) => {
+                Edit::RemoveMove(transaction_index, move_index)
+            }
+            BookOp::SetMoveExtra(transaction_index, move_index, extra) => {
+                Edit::SetMoveExtra(transaction_index, move_index, extra)
+            }
+            BookOp::SetMoveSum(transaction_index, move_index, sum) => {
+                Edit::SetMoveSum(transaction_index, move_index, sum)
+            }
+        }
+    }
+}
+/// The reason that a [BookOp] cannot be applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookOpError<Unit> {
+    /// The account is not in the book.
+    AccountNotFound(AccountKey),
+    /// The transaction index is out of bounds.
+    TransactionIndexOutOfBounds(TransactionIndex),
+    /// The move index is out of bounds in its transaction.
+    MoveIndexOutOfBounds(TransactionIndex, MoveIndex),
+    /// The debit and credit accounts are the same.
+    SameAccounts(AccountKey),
+    /// A unit of the sum of the move is not in the book, while other units
+    /// are.
+    UnitNotFound(TransactionIndex, MoveIndex, Unit),
+    /// The sum of the move is empty and empty sums are rejected.
+    EmptySum(TransactionIndex, MoveIndex),
+}
+impl<Unit> fmt::Display for BookOpError<Unit>
+where
+    Unit: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookOpError::AccountNotFound(account_key) => {
+                write!(f, "No account found for key {}", account_key)
+            }
+            BookOpError::TransactionIndexOutOfBounds(transaction_index) => {
+                write!(
+                    f,
+                    "transaction index (is {}) out of bounds",
+                    transaction_index.0,
+                )
+            }
+            BookOpError::MoveIndexOutOfBounds(
+                transaction_index,
+                move_index,
+            ) => {
+                write!(
+                    f,
+                    "move index (is {}) out of bounds in transaction {}",
+                    move_index.0, transaction_index.0,
+                )
+            }
+            BookOpError::SameAccounts(account_key) => write!(
+                f,
+                "Debit and credit accounts are the same ({}).",
+                account_key,
+            ),
+            BookOpError::UnitNotFound(transaction_index, move_index, unit) => {
+                write!(
+                    f,
+                    "Unit {:?} of move {} in transaction {} is not in the book",
+                    unit, move_index.0, transaction_index.0,
+                )
+            }
+            BookOpError::EmptySum(transaction_index, move_index) => write!(
+                f,
+                "Empty sum of move {} in transaction {}",
+                move_index.0, transaction_index.0,
+            ),
+        }
+    }
+}
+impl<Unit> Error for BookOpError<Unit> where Unit: fmt::Debug {}
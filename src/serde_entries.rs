@@ -0,0 +1,139 @@
+//! Serializes a [Sum](crate::Sum) or a [Balance](crate::Balance) as an
+//! array of `{"unit": …, "amount": …}` entries.
+//!
+//! By default, sums and balances serialize as maps from units to amounts.
+//! Some formats, such as JSON, only support string map keys, so a `Unit`
+//! such as a struct would fail to serialize as a map key.
+//! This representation supports any serializable `Unit`:
+//!
+//! ```
+//! # use bookkeeping::Sum;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord)]
+//! struct Currency {
+//!     code: String,
+//! }
+//! #[derive(Serialize, Deserialize)]
+//! struct Payment {
+//!     #[serde(with = "bookkeeping::serde_entries")]
+//!     sum: Sum<Currency, u64>,
+//! }
+//! let mut sum = Sum::default();
+//! sum.set_amount_for_unit(5, Currency { code: "USD".to_string() });
+//! let json = serde_json::to_string(&Payment { sum }).unwrap();
+//! assert_eq!(
+//!     json,
+//!     r#"{"sum":[{"unit":{"code":"USD"},"amount":5}]}"#,
+//! );
+//! ```
+use crate::unit_amounts::UnitAmounts;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[derive(Serialize, Deserialize)]
+struct Entry<Unit, Number> {
+    unit: Unit,
+    amount: Number,
+}
+/// Serializes as an array of entries.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: UnitAmounts,
+    T::Unit: Serialize,
+    T::Number: Serialize,
+    S: Serializer,
+{
+    serializer.collect_seq(
+        value
+            .unit_amounts()
+            .iter()
+            .map(|(unit, amount)| Entry { unit, amount }),
+    )
+}
+/// Deserializes from an array of entries.
+///
+/// Later entries of the same unit replace earlier ones.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: UnitAmounts,
+    T::Unit: Deserialize<'de>,
+    T::Number: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let entries = Vec::<Entry<T::Unit, T::Number>>::deserialize(deserializer)?;
+    Ok(T::from_unit_amounts(
+        entries
+            .into_iter()
+            .map(|Entry { unit, amount }| (unit, amount))
+            .collect(),
+    ))
+}
+#[cfg(test)]
+mod test {
+    use crate::{test_utils::TestBalance, Balance, Sum};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    #[derive(
+        Serialize,
+        Deserialize,
+        Debug,
+        Clone,
+        Default,
+        PartialEq,
+        Eq,
+        PartialOrd,
+        Ord,
+    )]
+    struct Currency {
+        code: String,
+    }
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        #[serde(with = "super")]
+        sum: Sum<Currency, u64>,
+        #[serde(with = "super")]
+        balance: Balance<Currency, i128>,
+    }
+    #[test]
+    fn serialize_deserialize() {
+        let usd = Currency {
+            code: "USD".to_string(),
+        };
+        let thb = Currency {
+            code: "THB".to_string(),
+        };
+        let mut sum = Sum::default();
+        sum.set_amount_for_unit(3, usd.clone());
+        sum.set_amount_for_unit(4, thb.clone());
+        let balance = Balance::default() - &sum;
+        let record = Record { sum, balance };
+        let actual = serde_json::to_value(&record).unwrap();
+        let expected = json!({
+            "sum": [
+                { "unit": { "code": "THB" }, "amount": 4 },
+                { "unit": { "code": "USD" }, "amount": 3 },
+            ],
+            "balance": [
+                { "unit": { "code": "THB" }, "amount": -4 },
+                { "unit": { "code": "USD" }, "amount": -3 },
+            ],
+        });
+        assert_eq!(actual, expected);
+        let json = serde_json::to_string(&record).unwrap();
+        let deserialized: Record = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, record);
+    }
+    #[test]
+    fn serialize_as_map() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = test_sum!(3, usd; 4, thb);
+        let balance = TestBalance::default() + &sum;
+        assert_eq!(
+            serde_json::to_value(&sum).unwrap(),
+            json!({ "THB": 4, "USD": 3 }),
+        );
+        assert_eq!(
+            serde_json::to_value(&balance).unwrap(),
+            json!({ "THB": 4, "USD": 3 }),
+        );
+    }
+}
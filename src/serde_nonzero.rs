@@ -0,0 +1,93 @@
+//! Serializes a [Sum](crate::Sum) or a [Balance](crate::Balance) as a map
+//! from units to amounts, omitting amounts that equal the default, such as
+//! zero.
+//!
+//! ```
+//! # use bookkeeping::Sum;
+//! # use serde::Serialize;
+//! #[derive(Serialize)]
+//! struct Snapshot {
+//!     #[serde(with = "bookkeeping::serde_nonzero")]
+//!     sum: Sum<&'static str, u64>,
+//! }
+//! let mut sum = Sum::default();
+//! sum.set_amount_for_unit(5, "USD");
+//! sum.set_amount_for_unit(0, "THB");
+//! let json = serde_json::to_string(&Snapshot { sum }).unwrap();
+//! assert_eq!(json, r#"{"sum":{"USD":5}}"#);
+//! ```
+use crate::unit_amounts::UnitAmounts;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+/// Serializes as a map of the amounts that are not the default.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: UnitAmounts,
+    T::Unit: Serialize,
+    T::Number: Serialize + Default + PartialEq,
+    S: Serializer,
+{
+    let default = T::Number::default();
+    serializer.collect_map(
+        value
+            .unit_amounts()
+            .iter()
+            .filter(|(_unit, amount)| **amount != default),
+    )
+}
+/// Deserializes from a map, omitting amounts that are the default.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: UnitAmounts,
+    T::Unit: Deserialize<'de>,
+    T::Number: Deserialize<'de> + Default + PartialEq,
+    D: Deserializer<'de>,
+{
+    let mut map = BTreeMap::<T::Unit, T::Number>::deserialize(deserializer)?;
+    let default = T::Number::default();
+    map.retain(|_unit, amount| *amount != default);
+    Ok(T::from_unit_amounts(map))
+}
+#[cfg(test)]
+mod test {
+    use crate::{Balance, Sum};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        #[serde(with = "super")]
+        sum: Sum<String, u64>,
+        #[serde(with = "super")]
+        balance: Balance<String, i128>,
+    }
+    #[test]
+    fn serialize_deserialize() {
+        let usd = "USD".to_string();
+        let thb = "THB".to_string();
+        let ils = "ILS".to_string();
+        let mut sum = Sum::default();
+        sum.set_amount_for_unit(3, usd.clone());
+        sum.set_amount_for_unit(0, thb.clone());
+        let mut balance = Balance::default() + &sum;
+        let mut zeroing = Sum::default();
+        zeroing.set_amount_for_unit(2, ils.clone());
+        balance += &zeroing;
+        balance -= &zeroing;
+        let record = Record { sum, balance };
+        let actual = serde_json::to_value(&record).unwrap();
+        let expected = json!({
+            "sum": { "USD": 3 },
+            "balance": { "USD": 3 },
+        });
+        assert_eq!(actual, expected);
+        let deserialized: Record = serde_json::from_value(json!({
+            "sum": { "USD": 3, "THB": 0 },
+            "balance": { "USD": 3, "ILS": 0 },
+        }))
+        .unwrap();
+        assert_eq!(deserialized.sum.amounts().count(), 1);
+        assert_eq!(deserialized.sum.amounts().next(), Some((&usd, &3)));
+        assert_eq!(deserialized.balance.unit_amount(&usd), Some(&3));
+        assert_eq!(deserialized.balance.unit_amount(&ils), None);
+    }
+}
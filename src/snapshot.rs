@@ -0,0 +1,151 @@
+use crate::{
+    balance::Balance,
+    book::{AccountKey, Accounts, TransactionIndex, Transactions},
+    transaction::Transaction,
+};
+use slotmap::SecondaryMap;
+use std::{
+    borrow::Borrow,
+    ops::{Add, Sub},
+};
+/// An immutable copy of the accounts and transactions of a book, for
+/// read-only queries, as produced by [Book::snapshot](crate::Book::snapshot).
+///
+/// It is [Send] and [Sync] when its type parameters are, so it can be shared
+/// across threads in an [Arc](std::sync::Arc).
+pub struct BookSnapshot<
+    Unit,
+    SumNumber,
+    AccountExtra,
+    TransactionExtra,
+    MoveExtra,
+> where
+    Unit: Ord,
+{
+    pub(crate) accounts: SecondaryMap<AccountKey, AccountExtra>,
+    pub(crate) transactions:
+        Vec<Transaction<Unit, SumNumber, TransactionExtra, MoveExtra>>,
+}
+impl<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+    BookSnapshot<Unit, SumNumber, AccountExtra, TransactionExtra, MoveExtra>
+where
+    Unit: Ord,
+{
+    /// Gets an iterator of existing accounts in order of creation.
+    pub fn accounts(&self) -> Accounts<'_, AccountExtra> {
+        Accounts {
+            iter: self.accounts.iter(),
+        }
+    }
+    /// Gets an iterator of existing transactions in their order.
+    pub fn transactions(
+        &self,
+    ) -> Transactions<'_, Unit, SumNumber, TransactionExtra, MoveExtra> {
+        Transactions {
+            iter: self.transactions.iter().enumerate(),
+        }
+    }
+    /// Calculates the balance of an account at a provided transaction.
+    ///
+    /// Same as [Book::account_balance_at_transaction](
+    /// crate::Book::account_balance_at_transaction).
+    ///
+    /// ## Panics
+    ///
+    /// - `account_key` is not in the snapshot.
+    /// - `transaction_index` out of bounds.
+    pub fn account_balance_at_transaction<BalanceNumber>(
+        &self,
+        account_key: impl Borrow<AccountKey>,
+        transaction_index: TransactionIndex,
+    ) -> Balance<Unit, BalanceNumber>
+    where
+        Unit: Ord + Clone,
+        BalanceNumber: Default
+            + Sub<Output = BalanceNumber>
+            + Add<Output = BalanceNumber>
+            + Clone,
+        SumNumber: Clone + Into<BalanceNumber>,
+    {
+        let account_key = *account_key.borrow();
+        assert!(
+            self.accounts.contains_key(account_key),
+            "No account found for key {:?}",
+            account_key,
+        );
+        assert!(
+            transaction_index.0 < self.transactions.len(),
+            "transaction index (is {}) should be < len (is {})",
+            transaction_index.0,
+            self.transactions.len(),
+        );
+        let mut balance = Balance::default();
+        self.transactions[..=transaction_index.0].iter().for_each(
+            |transaction| balance.apply_transaction(transaction, account_key),
+        );
+        balance
+    }
+}
+#[cfg(test)]
+mod test {
+    use crate::{
+        book::TransactionIndex,
+        test_utils::{TestBalance, TestBook},
+        transaction::MoveIndex,
+    };
+    use std::thread;
+    #[test]
+    fn snapshot() {
+        let mut book = TestBook::default();
+        let wallet_key = book.insert_account("wallet");
+        let bank_key = book.insert_account("bank");
+        let usd = "USD";
+        book.insert_transaction(TransactionIndex(0), "");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            wallet_key,
+            bank_key,
+            test_sum!(3, usd),
+            "",
+        );
+        let snapshot = book.snapshot();
+        book.set_transaction_extra(TransactionIndex(0), "changed");
+        let thread_snapshot = snapshot.clone();
+        let balance = thread::spawn(move || {
+            thread_snapshot.account_balance_at_transaction::<i128>(
+                bank_key,
+                TransactionIndex(0),
+            )
+        })
+        .join()
+        .unwrap();
+        assert_eq!(balance, TestBalance::default() + &test_sum!(3, usd));
+        assert_eq!(
+            snapshot
+                .accounts()
+                .map(|(_, extra)| *extra)
+                .collect::<Vec<_>>(),
+            ["wallet", "bank"],
+        );
+        assert_eq!(
+            snapshot
+                .transactions()
+                .map(|(_, transaction)| *transaction.extra())
+                .collect::<Vec<_>>(),
+            [""],
+        );
+    }
+    #[test]
+    #[should_panic(
+        expected = "transaction index (is 0) should be < len (is 0)"
+    )]
+    fn snapshot_account_balance_at_transaction_out_of_bounds() {
+        let mut book = TestBook::default();
+        let account_key = book.insert_account("");
+        book.snapshot().account_balance_at_transaction::<i128>(
+            account_key,
+            TransactionIndex(0),
+        );
+    }
+}
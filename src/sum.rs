@@ -1,6 +1,18 @@
-use std::{collections::BTreeMap, fmt};
+use crate::{balance::Balance, move_::Side};
+use std::{
+    cmp::Ordering,
+    collections::{btree_map, BTreeMap, HashMap},
+    fmt,
+    hash::Hash,
+    iter::FromIterator,
+    ops::{Add, AddAssign, Index, Mul, Neg, Sub, SubAssign},
+};
 /// Represents amounts of any number of units.
-#[derive(Clone, PartialEq, Default)]
+///
+/// With the `serde` feature, serializes as a map from units to amounts.
+/// See the `serde_entries` module for an alternative representation.
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sum<Unit, Number>(pub(crate) BTreeMap<Unit, Number>)
 where
     Unit: Ord;
@@ -9,6 +21,9 @@ where
     Unit: Ord,
 {
     /// Sets the amount of a unit in a sum.
+    ///
+    /// The unit is kept even if the amount is the default, such as zero.
+    /// See [Sum::normalize].
     pub fn set_amount_for_unit(&mut self, amount: Number, unit_: Unit) {
         self.0.insert(unit_, amount);
     }
@@ -16,9 +31,569 @@ where
     pub fn amounts(&self) -> impl Iterator<Item = (&Unit, &Number)> {
         self.0.iter()
     }
+    /// Gets the amount of a unit, if it is in the sum.
     pub fn unit_amount(&self, unit: &Unit) -> Option<&Number> {
         self.0.get(unit)
     }
+    /// Removes a unit from the sum, returning its amount if it was in it.
+    pub fn remove_unit(&mut self, unit: &Unit) -> Option<Number> {
+        self.0.remove(unit)
+    }
+    /// Gets the number of units in the sum.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    /// Whether the sum has no units.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// Whether a unit is in the sum.
+    pub fn contains_unit(&self, unit: &Unit) -> bool {
+        self.0.contains_key(unit)
+    }
+    /// Copies the amounts of all units into a [HashMap].
+    pub fn to_hashmap(&self) -> HashMap<Unit, Number>
+    where
+        Unit: Clone + Hash,
+        Number: Clone,
+    {
+        self.0.clone().into_iter().collect()
+    }
+    /// Converts the amounts of all units into a single unit.
+    ///
+    /// Each amount is converted into `N` using `combine` and multiplied by
+    /// the rate of its unit, as provided by `rate`. The rate of `target` is
+    /// also obtained from `rate`.
+    ///
+    /// Returns `None` if `rate` provides no rate for some unit.
+    pub fn convert_to<N>(
+        &self,
+        target: Unit,
+        rate: impl Fn(&Unit) -> Option<N>,
+        combine: impl Fn(&Number) -> N,
+    ) -> Option<Sum<Unit, N>>
+    where
+        N: Default + Add<Output = N> + Mul<Output = N>,
+    {
+        let total = self
+            .0
+            .iter()
+            .try_fold(N::default(), |total, (unit, amount)| {
+                Some(total + combine(amount) * rate(unit)?)
+            })?;
+        let mut sum = Sum(BTreeMap::new());
+        sum.set_amount_for_unit(total, target);
+        Some(sum)
+    }
+    /// Subtracts another sum per unit, clamping each result at the default
+    /// amount, such as zero.
+    ///
+    /// Units of either sum are included. Missing amounts are the default.
+    pub fn saturating_sub(&self, other: &Self) -> Self
+    where
+        Unit: Clone,
+        Number: Ord + Sub<Output = Number> + Default + Clone,
+    {
+        let mut difference = self.clone();
+        other.0.keys().for_each(|unit| {
+            difference.0.entry(unit.clone()).or_default();
+        });
+        difference.0.iter_mut().for_each(|(unit, amount)| {
+            if let Some(other_amount) = other.0.get(unit) {
+                *amount = if *amount > *other_amount {
+                    amount.clone() - other_amount.clone()
+                } else {
+                    Number::default()
+                };
+            }
+        });
+        difference
+    }
+    /// Subtracts another sum per unit, or returns `None` if any of the
+    /// results overflows.
+    ///
+    /// Units of either sum are included. Missing amounts are the default.
+    /// Requires the `num-traits` feature.
+    #[cfg(feature = "num-traits")]
+    pub fn checked_sub(&self, other: &Self) -> Option<Self>
+    where
+        Unit: Clone,
+        Number: num_traits::CheckedSub + Default + Clone,
+    {
+        let mut difference = self.clone();
+        other.0.keys().for_each(|unit| {
+            difference.0.entry(unit.clone()).or_default();
+        });
+        difference
+            .0
+            .iter_mut()
+            .try_for_each(|(unit, amount)| {
+                if let Some(other_amount) = other.0.get(unit) {
+                    *amount = amount.checked_sub(other_amount)?;
+                }
+                Some(())
+            })
+            .map(|()| difference)
+    }
+    /// Adds the amounts of another sum, each multiplied by a weight.
+    ///
+    /// Missing amounts are the default.
+    pub fn weighted_add(&mut self, other: &Self, weight: Number)
+    where
+        Unit: Clone,
+        Number: Add<Output = Number> + Mul<Output = Number> + Clone + Default,
+    {
+        other.0.iter().for_each(|(unit, amount)| {
+            let entry = self.0.entry(unit.clone()).or_default();
+            *entry = entry.clone() + amount.clone() * weight.clone();
+        });
+    }
+    /// Multiplies the amount of each unit by a factor.
+    pub fn scale(&self, factor: Number) -> Self
+    where
+        Unit: Clone,
+        Number: Mul<Output = Number> + Clone,
+    {
+        Sum(self
+            .0
+            .iter()
+            .map(|(unit, amount)| {
+                (unit.clone(), amount.clone() * factor.clone())
+            })
+            .collect())
+    }
+    /// Compares to another sum, allowing the amounts of each unit to differ
+    /// by up to `epsilon`.
+    ///
+    /// Missing amounts are the default, such as zero.
+    pub fn approx_eq(&self, other: &Self, epsilon: Number) -> bool
+    where
+        Number: Sub<Output = Number>
+            + Neg<Output = Number>
+            + PartialOrd
+            + Clone
+            + Default,
+    {
+        amounts_approx_eq(&self.0, &other.0, epsilon)
+    }
+    /// Converts the units of the sum.
+    ///
+    /// Amounts of units that convert to the same unit are added.
+    pub fn map_units<U2, F>(self, mut f: F) -> Sum<U2, Number>
+    where
+        U2: Ord,
+        F: FnMut(Unit) -> U2,
+        Number: Add<Output = Number> + Default,
+    {
+        let mut map = BTreeMap::new();
+        self.0.into_iter().for_each(|(unit, amount)| {
+            let entry: &mut Number = map.entry(f(unit)).or_default();
+            *entry = std::mem::take(entry) + amount;
+        });
+        Sum(map)
+    }
+    /// Gets the absolute amount of each unit, using a provided function.
+    pub fn abs_with(&self, abs: impl Fn(&Number) -> Number) -> Self
+    where
+        Unit: Clone,
+    {
+        Sum(self
+            .0
+            .iter()
+            .map(|(unit, amount)| (unit.clone(), abs(amount)))
+            .collect())
+    }
+    /// Removes the units of default amounts, such as zero.
+    ///
+    /// Equality and hashing consider units of default amounts, so sums are
+    /// only comparable this way once normalized.
+    pub fn normalize(&mut self)
+    where
+        Number: Default + PartialEq,
+    {
+        let zero = Number::default();
+        self.0.retain(|_, amount| *amount != zero);
+    }
+    /// Gets a copy of the sum without the units of default amounts, such as
+    /// zero.
+    ///
+    /// Same as [Sum::normalize], without changing the sum.
+    pub fn normalized(&self) -> Self
+    where
+        Unit: Clone,
+        Number: Default + PartialEq + Clone,
+    {
+        let mut normalized = self.clone();
+        normalized.normalize();
+        normalized
+    }
+    /// Whether the amounts of all units are the default, such as zero.
+    ///
+    /// An empty sum is zero.
+    pub fn is_zero(&self) -> bool
+    where
+        Number: Default + PartialEq,
+    {
+        let zero = Number::default();
+        self.0.values().all(|amount| *amount == zero)
+    }
+    /// Inserts the units that are missing from the sum, with default
+    /// amounts, such as zero.
+    ///
+    /// Existing amounts are left as they are.
+    pub fn ensure_units(&mut self, units: impl IntoIterator<Item = Unit>)
+    where
+        Number: Default,
+    {
+        units.into_iter().for_each(|unit| {
+            self.0.entry(unit).or_default();
+        });
+    }
+    /// Renders like [Display](fmt::Display), with each amount right-aligned
+    /// to a width.
+    pub fn fmt_aligned(&self, amount_width: usize) -> String
+    where
+        Unit: fmt::Display,
+        Number: fmt::Display,
+    {
+        render_amounts(&self.0, amount_width)
+    }
+    /// Gets an iterator of the units that are in both sums, in order.
+    pub fn shared_units<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> impl Iterator<Item = &'a Unit> {
+        self.0.keys().filter(move |unit| other.0.contains_key(unit))
+    }
+    /// Gets an iterator of the units that are in this sum but not in
+    /// another, in order.
+    pub fn only_in_self_units<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> impl Iterator<Item = &'a Unit> {
+        self.0
+            .keys()
+            .filter(move |unit| !other.0.contains_key(unit))
+    }
+    /// Splits the sum into a sum per category of units.
+    pub fn group_by<K, F>(&self, classify: F) -> BTreeMap<K, Self>
+    where
+        K: Ord,
+        F: Fn(&Unit) -> K,
+        Unit: Clone,
+        Number: Clone,
+    {
+        let mut groups = BTreeMap::new();
+        self.0.iter().for_each(|(unit, amount)| {
+            groups
+                .entry(classify(unit))
+                .or_insert_with(|| Sum(BTreeMap::new()))
+                .set_amount_for_unit(amount.clone(), unit.clone());
+        });
+        groups
+    }
+    /// Gets the unit of the largest amount and the amount.
+    ///
+    /// Of equal amounts, the first in unit order is chosen.
+    pub fn max_amount(&self) -> Option<(&Unit, &Number)>
+    where
+        Number: Ord,
+    {
+        self.0.iter().reduce(|max, unit_amount| {
+            if unit_amount.1 > max.1 {
+                unit_amount
+            } else {
+                max
+            }
+        })
+    }
+    /// Gets the unit of the smallest amount and the amount.
+    ///
+    /// Of equal amounts, the first in unit order is chosen.
+    pub fn min_amount(&self) -> Option<(&Unit, &Number)>
+    where
+        Number: Ord,
+    {
+        self.0.iter().reduce(|min, unit_amount| {
+            if unit_amount.1 < min.1 {
+                unit_amount
+            } else {
+                min
+            }
+        })
+    }
+    /// Converts into a balance of the sum on a side of a move.
+    ///
+    /// Amounts are negated on the debit side and kept on the credit side,
+    /// as in [Book::account_balance_at_transaction](crate::Book::account_balance_at_transaction).
+    pub fn signed_for(self, side: Side) -> Balance<Unit, Number>
+    where
+        Number: Neg<Output = Number>,
+    {
+        Balance(match side {
+            Side::Debit => self
+                .0
+                .into_iter()
+                .map(|(unit, amount)| (unit, -amount))
+                .collect(),
+            Side::Credit => self.0,
+        })
+    }
+    /// Gets the unit and amount of a sum of exactly one unit.
+    ///
+    /// Otherwise, the sum is returned as the error.
+    pub fn into_single(self) -> Result<(Unit, Number), Self> {
+        if self.0.len() == 1 {
+            Ok(self.0.into_iter().next().unwrap())
+        } else {
+            Err(self)
+        }
+    }
+}
+pub(crate) fn amounts_approx_eq<Unit, Number>(
+    a: &BTreeMap<Unit, Number>,
+    b: &BTreeMap<Unit, Number>,
+    epsilon: Number,
+) -> bool
+where
+    Unit: Ord,
+    Number: Sub<Output = Number>
+        + Neg<Output = Number>
+        + PartialOrd
+        + Clone
+        + Default,
+{
+    let zero = Number::default();
+    let within = |a: &Number, b: &Number| {
+        let difference = a.clone() - b.clone();
+        difference <= epsilon && difference >= -epsilon.clone()
+    };
+    a.iter()
+        .all(|(unit, amount)| within(amount, b.get(unit).unwrap_or(&zero)))
+        && b.iter()
+            .filter(|(unit, _)| !a.contains_key(unit))
+            .all(|(_, amount)| within(&zero, amount))
+}
+/// Renders amounts as `<amount> <unit>`, in order of unit, separated by
+/// commas, with each amount right-aligned to a width.
+pub(crate) fn render_amounts<Unit, Number>(
+    amounts: &BTreeMap<Unit, Number>,
+    amount_width: usize,
+) -> String
+where
+    Unit: fmt::Display,
+    Number: fmt::Display,
+{
+    amounts
+        .iter()
+        .map(|(unit, amount)| {
+            format!("{:>width$} {}", amount, unit, width = amount_width)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+/// Collects units and amounts. Of repeated units, the last amount is kept.
+impl<Unit, Number> FromIterator<(Unit, Number)> for Sum<Unit, Number>
+where
+    Unit: Ord,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (Unit, Number)>,
+    {
+        Sum(iter.into_iter().collect())
+    }
+}
+impl<Unit, Number> Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Clone,
+{
+    fn apply_sum_operation(
+        &mut self,
+        other: &Self,
+        operation: impl Fn(Number, Number) -> Number,
+    ) {
+        other.0.iter().for_each(|(unit, amount)| {
+            let entry = self.0.entry(unit.clone()).or_default();
+            *entry = operation(std::mem::take(entry), amount.clone());
+        });
+    }
+}
+/// Adds the amounts of each unit. Missing amounts are the default.
+impl<Unit, Number> AddAssign<&Sum<Unit, Number>> for Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Add<Output = Number> + Clone,
+{
+    fn add_assign(&mut self, other: &Self) {
+        self.apply_sum_operation(other, |amount, other_amount| {
+            amount + other_amount
+        });
+    }
+}
+/// Subtracts the amounts of each unit. Missing amounts are the default.
+///
+/// Units of amounts that become the default are kept.
+impl<Unit, Number> SubAssign<&Sum<Unit, Number>> for Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Sub<Output = Number> + Clone,
+{
+    fn sub_assign(&mut self, other: &Self) {
+        self.apply_sum_operation(other, |amount, other_amount| {
+            amount - other_amount
+        });
+    }
+}
+impl<Unit, Number> Add<&Sum<Unit, Number>> for Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Add<Output = Number> + Clone,
+{
+    type Output = Self;
+    fn add(mut self, other: &Self) -> Self::Output {
+        self += other;
+        self
+    }
+}
+impl<Unit, Number> Sub<&Sum<Unit, Number>> for Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Sub<Output = Number> + Clone,
+{
+    type Output = Self;
+    fn sub(mut self, other: &Self) -> Self::Output {
+        self -= other;
+        self
+    }
+}
+impl<Unit, Number> AddAssign for Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Add<Output = Number> + Clone,
+{
+    fn add_assign(&mut self, other: Self) {
+        *self += &other;
+    }
+}
+impl<Unit, Number> SubAssign for Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Sub<Output = Number> + Clone,
+{
+    fn sub_assign(&mut self, other: Self) {
+        *self -= &other;
+    }
+}
+impl<Unit, Number> Add for Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Add<Output = Number> + Clone,
+{
+    type Output = Self;
+    fn add(self, other: Self) -> Self::Output {
+        self + &other
+    }
+}
+impl<Unit, Number> Sub for Sum<Unit, Number>
+where
+    Unit: Ord + Clone,
+    Number: Default + Sub<Output = Number> + Clone,
+{
+    type Output = Self;
+    fn sub(self, other: Self) -> Self::Output {
+        self - &other
+    }
+}
+/// Yields the units and amounts in order of unit.
+impl<Unit, Number> IntoIterator for Sum<Unit, Number>
+where
+    Unit: Ord,
+{
+    type Item = (Unit, Number);
+    type IntoIter = btree_map::IntoIter<Unit, Number>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+/// Yields the units and amounts in order of unit.
+impl<'a, Unit, Number> IntoIterator for &'a Sum<Unit, Number>
+where
+    Unit: Ord,
+{
+    type Item = (&'a Unit, &'a Number);
+    type IntoIter = btree_map::Iter<'a, Unit, Number>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+/// Gets the amount of a unit.
+///
+/// ## Panics
+///
+/// - `unit` is not in the sum.
+impl<Unit, Number> Index<&Unit> for Sum<Unit, Number>
+where
+    Unit: Ord,
+{
+    type Output = Number;
+    fn index(&self, unit: &Unit) -> &Number {
+        self.0.get(unit).expect("No amount found for unit.")
+    }
+}
+/// Compares per unit, where missing amounts are the default.
+///
+/// A sum is less than another if none of its amounts are greater and some
+/// are less. Sums that have both lesser and greater amounts are
+/// incomparable. So are unequal sums that differ only in default amounts.
+impl<Unit, Number> PartialOrd for Sum<Unit, Number>
+where
+    Unit: Ord,
+    Number: PartialOrd + Default,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let zero = Number::default();
+        let ordering = self
+            .0
+            .iter()
+            .map(|(unit, amount)| (amount, other.0.get(unit).unwrap_or(&zero)))
+            .chain(
+                other
+                    .0
+                    .iter()
+                    .filter(|(unit, _)| !self.0.contains_key(unit))
+                    .map(|(_, amount)| (&zero, amount)),
+            )
+            .try_fold(Ordering::Equal, |ordering, (a, b)| {
+                match (ordering, a.partial_cmp(b)?) {
+                    (ordering, Ordering::Equal) => Some(ordering),
+                    (Ordering::Equal, unit_ordering) => Some(unit_ordering),
+                    (ordering, unit_ordering) if ordering == unit_ordering => {
+                        Some(ordering)
+                    }
+                    _ => None,
+                }
+            })?;
+        if ordering == Ordering::Equal && self != other {
+            None
+        } else {
+            Some(ordering)
+        }
+    }
+}
+/// Displays as `<amount> <unit>` per unit, in order of unit, separated by
+/// commas.
+///
+/// The width, fill and alignment of the formatter apply to the whole.
+impl<Unit, Number> fmt::Display for Sum<Unit, Number>
+where
+    Unit: Ord + fmt::Display,
+    Number: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&render_amounts(&self.0, 0))
+    }
 }
 impl<Unit, Number> fmt::Debug for Sum<Unit, Number>
 where
@@ -34,7 +609,9 @@ where
 #[cfg(test)]
 mod test {
     use super::Sum;
+    use crate::{balance::Balance, move_::Side};
     use maplit::btreemap;
+    use std::{cmp::Ordering, collections::HashSet};
     #[test]
     fn default() {
         let actual = Sum::<&str, usize>::default();
@@ -45,7 +622,7 @@ mod test {
     fn from_entries() {
         let thb = "THB";
         let usd = "USD";
-        let actual = sum!(100, thb; 200, usd);
+        let actual = test_sum!(100, thb; 200, usd);
         let expected = Sum(btreemap! {
             thb => 100,
             usd => 200,
@@ -70,21 +647,392 @@ mod test {
         assert_eq!(actual, &amount);
     }
     #[test]
+    fn remove_unit() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut sum = test_sum!(3, usd; 4, thb);
+        assert_eq!(sum.remove_unit(&usd), Some(3));
+        assert_eq!(sum.remove_unit(&usd), None);
+        assert_eq!(sum, test_sum!(4, thb));
+    }
+    #[test]
+    fn contains_unit() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = test_sum!(3, usd);
+        assert!(sum.contains_unit(&usd));
+        assert!(!sum.contains_unit(&thb));
+    }
+    #[test]
+    fn to_hashmap() {
+        let usd = "USD";
+        let thb = "THB";
+        let actual = test_sum!(3, usd; 4, thb).to_hashmap();
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual.get(&usd), Some(&3));
+        assert_eq!(actual.get(&thb), Some(&4));
+    }
+    #[test]
+    fn convert_to() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let rate = |unit: &&str| match *unit {
+            "USD" => Some(100),
+            "THB" => Some(3),
+            _ => None,
+        };
+        let sum = test_sum!(2, usd; 300, thb);
+        let actual = sum.convert_to(usd, rate, |amount| *amount);
+        assert_eq!(actual, Some(test_sum!(1100, usd)));
+        let sum = test_sum!(2, usd; 1, ils);
+        let actual = sum.convert_to(usd, rate, |amount| *amount);
+        assert_eq!(actual, None);
+    }
+    #[test]
+    fn saturating_sub() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let actual = test_sum!(5, usd; 3, thb)
+            .saturating_sub(&test_sum!(2, usd; 7, thb; 1, ils));
+        let expected = test_sum!(3, usd; 0, thb; 0, ils);
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn approx_eq() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = Sum(btreemap! { usd => 1.0, thb => 2.0 });
+        assert!(
+            sum.approx_eq(&Sum(btreemap! { usd => 1.05, thb => 1.98 }), 0.1)
+        );
+        assert!(!sum.approx_eq(&Sum(btreemap! { usd => 1.2, thb => 2.0 }), 0.1));
+        assert!(!sum.approx_eq(&Sum(btreemap! { usd => 1.0 }), 0.1));
+        assert!(Sum(btreemap! { usd => 0.05 }).approx_eq(&Sum::default(), 0.1));
+        assert!(!Sum::default().approx_eq(&Sum(btreemap! { usd => 0.5 }), 0.1));
+    }
+    #[test]
+    fn ensure_units() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let mut actual = test_sum!(3, usd);
+        actual.ensure_units(vec![thb, usd, ils]);
+        let expected = test_sum!(3, usd; 0, thb; 0, ils);
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn add() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let a = test_sum!(5, usd; 3, thb);
+        let b = test_sum!(2, usd; 1, ils);
+        let expected = test_sum!(7, usd; 3, thb; 1, ils);
+        assert_eq!(a.clone() + &b, expected);
+        assert_eq!(a.clone() + b.clone(), expected);
+        let mut actual = a.clone();
+        actual += &b;
+        assert_eq!(actual, expected);
+        let mut actual = a;
+        actual += b;
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn sub() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let a = Sum(btreemap! { usd => 5, thb => 3 });
+        let b = Sum(btreemap! { usd => 5, ils => 1 });
+        let expected = Sum(btreemap! { usd => 0, thb => 3, ils => -1 });
+        assert_eq!(a.clone() - &b, expected);
+        assert_eq!(a.clone() - b.clone(), expected);
+        let mut actual = a.clone();
+        actual -= &b;
+        assert_eq!(actual, expected);
+        let mut actual = a;
+        actual -= b;
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn scale() {
+        let usd = "USD";
+        let thb = "THB";
+        let actual = test_sum!(5, usd; 3, thb).scale(3);
+        let expected = test_sum!(15, usd; 9, thb);
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn from_iter() {
+        let usd = "USD";
+        let thb = "THB";
+        let actual = vec![(usd, 1), (thb, 2), (usd, 3)]
+            .into_iter()
+            .collect::<Sum<_, _>>();
+        assert_eq!(actual, Sum(btreemap! { usd => 3, thb => 2 }));
+    }
+    #[test]
+    fn into_iter() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = test_sum!(3, usd; 4, thb);
+        let mut borrowed = Vec::new();
+        for (unit, amount) in &sum {
+            borrowed.push((unit, amount));
+        }
+        assert_eq!(borrowed, vec![(&thb, &4), (&usd, &3)]);
+        let owned = sum.into_iter().collect::<Vec<_>>();
+        assert_eq!(owned, vec![(thb, 4), (usd, 3)]);
+    }
+    #[test]
+    fn index() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = test_sum!(3, usd; 4, thb);
+        assert_eq!(sum[&usd], 3);
+        assert_eq!(sum[&thb], 4);
+    }
+    #[test]
+    #[should_panic(expected = "No amount found for unit.")]
+    fn index_panic_unit_not_found() {
+        let usd = "USD";
+        let thb = "THB";
+        let _ = test_sum!(3, usd)[&thb];
+    }
+    #[test]
+    fn map_units() {
+        let usd = "USD";
+        let dollar = "$";
+        let thb = "THB";
+        let sum = test_sum!(3, usd; 4, dollar; 5, thb);
+        let actual =
+            sum.map_units(|unit| if unit == dollar { usd } else { unit });
+        assert_eq!(actual, test_sum!(7, usd; 5, thb));
+        let actual = test_sum!(1, usd).map_units(|unit| unit.len());
+        assert_eq!(actual, Sum(btreemap! { 3 => 1 }));
+    }
+    #[test]
+    fn abs_with() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = Sum(btreemap! { usd => -3_i32, thb => 2 });
+        assert_eq!(
+            sum.abs_with(|amount| amount.abs()),
+            Sum(btreemap! { usd => 3, thb => 2 }),
+        );
+    }
+    #[test]
+    fn normalize() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut sum = test_sum!(0, usd; 2, thb);
+        assert_ne!(sum, test_sum!(2, thb));
+        sum.normalize();
+        assert_eq!(sum, test_sum!(2, thb));
+    }
+    #[test]
+    fn normalized() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = test_sum!(0, usd; 2, thb);
+        assert_eq!(sum.normalized(), test_sum!(2, thb));
+        assert_eq!(sum.normalized(), test_sum!(2, thb; 0, usd).normalized());
+        assert_eq!(sum, test_sum!(0, usd; 2, thb));
+    }
+    #[test]
+    fn len_and_is_empty() {
+        let usd = "USD";
+        let thb = "THB";
+        assert_eq!(test_sum!().len(), 0);
+        assert!(test_sum!().is_empty());
+        assert_eq!(test_sum!(0, usd; 2, thb).len(), 2);
+        assert!(!test_sum!(0, usd).is_empty());
+    }
+    #[test]
+    fn is_zero() {
+        let usd = "USD";
+        let thb = "THB";
+        assert!(test_sum!().is_zero());
+        assert!(test_sum!(0, usd; 0, thb).is_zero());
+        assert!(!test_sum!(0, usd; 2, thb).is_zero());
+    }
+    #[test]
+    fn shared_and_only_in_self_units() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        let eur = "EUR";
+        let a = test_sum!(1, usd; 2, thb; 3, ils);
+        let b = test_sum!(4, thb; 5, eur; 6, usd);
+        assert_eq!(a.shared_units(&b).collect::<Vec<_>>(), vec![&thb, &usd]);
+        assert_eq!(a.only_in_self_units(&b).collect::<Vec<_>>(), vec![&ils]);
+        assert_eq!(b.only_in_self_units(&a).collect::<Vec<_>>(), vec![&eur]);
+    }
+    #[test]
+    fn group_by() {
+        let usd = "USD";
+        let thb = "THB";
+        let btc = "BTC";
+        let sum = test_sum!(1, usd; 2, thb; 3, btc);
+        let actual = sum.group_by(|unit| *unit == btc);
+        let expected = btreemap! {
+            false => test_sum!(1, usd; 2, thb),
+            true => test_sum!(3, btc),
+        };
+        assert_eq!(actual, expected);
+    }
+    #[cfg(feature = "num-traits")]
+    #[test]
+    fn checked_sub() {
+        let usd = "USD";
+        let thb = "THB";
+        let a = Sum(btreemap! { usd => 5_i8, thb => 2 });
+        let b = Sum(btreemap! { usd => 3_i8 });
+        assert_eq!(
+            a.checked_sub(&b),
+            Some(Sum(btreemap! { usd => 2, thb => 2 })),
+        );
+        assert_eq!(
+            b.checked_sub(&a),
+            Some(Sum(btreemap! { usd => -2, thb => -2 }))
+        );
+        let low = Sum(btreemap! { usd => -100_i8 });
+        assert_eq!(low.checked_sub(&Sum(btreemap! { usd => 100 })), None);
+    }
+    #[test]
+    fn weighted_add() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut actual = test_sum!();
+        actual.weighted_add(&test_sum!(10, usd; 20, thb), 3);
+        actual.weighted_add(&test_sum!(100, usd), 2);
+        let expected = test_sum!(230, usd; 60, thb);
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn max_amount() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        assert_eq!(test_sum!().max_amount(), None);
+        assert_eq!(test_sum!(3, usd).max_amount(), Some((&usd, &3)));
+        assert_eq!(
+            test_sum!(3, usd; 5, thb; 1, ils).max_amount(),
+            Some((&thb, &5))
+        );
+        assert_eq!(
+            test_sum!(5, usd; 5, thb; 1, ils).max_amount(),
+            Some((&thb, &5))
+        );
+    }
+    #[test]
+    fn min_amount() {
+        let usd = "USD";
+        let thb = "THB";
+        let ils = "ILS";
+        assert_eq!(test_sum!().min_amount(), None);
+        assert_eq!(test_sum!(3, usd).min_amount(), Some((&usd, &3)));
+        assert_eq!(
+            test_sum!(3, usd; 5, thb; 4, ils).min_amount(),
+            Some((&usd, &3))
+        );
+        assert_eq!(
+            test_sum!(3, usd; 3, thb; 4, ils).min_amount(),
+            Some((&thb, &3))
+        );
+    }
+    #[test]
+    fn signed_for() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = Sum(btreemap! { usd => 3_i128, thb => -4 });
+        assert_eq!(
+            sum.clone().signed_for(Side::Debit),
+            Balance(btreemap! { usd => -3, thb => 4 }),
+        );
+        assert_eq!(
+            sum.signed_for(Side::Credit),
+            Balance(btreemap! { usd => 3, thb => -4 }),
+        );
+    }
+    #[test]
+    fn into_single() {
+        let usd = "USD";
+        let thb = "THB";
+        assert_eq!(test_sum!().into_single(), Err(test_sum!()));
+        assert_eq!(test_sum!(3, usd).into_single(), Ok((usd, 3)));
+        assert_eq!(
+            test_sum!(3, usd; 4, thb).into_single(),
+            Err(test_sum!(3, usd; 4, thb)),
+        );
+    }
+    #[test]
     fn amounts() {
         let thb = "THB";
         let usd = "USD";
-        let sum = sum!(3, thb; 10, usd);
+        let sum = test_sum!(3, thb; 10, usd);
         let actual = sum.amounts().collect::<Vec<_>>();
         let expected = vec![(&thb, &3), (&usd, &10)];
         assert_eq!(actual, expected);
     }
     #[test]
+    fn eq_hash() {
+        let usd = "USD";
+        let thb = "THB";
+        let mut set = HashSet::new();
+        set.insert(test_sum!(1, usd; 2, thb));
+        assert!(set.contains(&test_sum!(2, thb; 1, usd)));
+        assert!(!set.contains(&test_sum!(1, usd)));
+        assert!(!set.insert(test_sum!(1, usd; 2, thb)));
+    }
+    #[test]
+    fn partial_cmp() {
+        let usd = "USD";
+        let thb = "THB";
+        let budget = test_sum!(5, usd; 3, thb);
+        assert_eq!(
+            test_sum!(6, usd; 3, thb).partial_cmp(&budget),
+            Some(Ordering::Greater),
+        );
+        assert_eq!(
+            test_sum!(5, usd).partial_cmp(&budget),
+            Some(Ordering::Less)
+        );
+        assert!(test_sum!(4, usd; 3, thb) <= budget);
+        assert_eq!(
+            test_sum!(3, thb; 5, usd).partial_cmp(&budget),
+            Some(Ordering::Equal),
+        );
+        assert_eq!(test_sum!(6, usd; 2, thb).partial_cmp(&budget), None);
+        assert_eq!(test_sum!(7, thb).partial_cmp(&budget), None);
+        assert_eq!(test_sum!(0, usd).partial_cmp(&test_sum!()), None);
+    }
+    #[test]
+    fn fmt_display() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = test_sum!(3, usd; 45, thb);
+        assert_eq!(format!("{}", sum), "45 THB, 3 USD");
+        assert_eq!(format!("{:>16}", sum), "   45 THB, 3 USD");
+        assert_eq!(format!("{:*<15}", sum), "45 THB, 3 USD**");
+        assert_eq!(format!("{}", Sum::<&str, u8>::default()), "");
+    }
+    #[test]
+    fn fmt_aligned() {
+        let usd = "USD";
+        let thb = "THB";
+        let sum = test_sum!(3, usd; 45, thb);
+        assert_eq!(sum.fmt_aligned(4), "  45 THB,    3 USD");
+    }
+    #[test]
     fn fmt_debug() {
         let usd = "USD";
         let amount_usd = 76;
         let thb = "THB";
         let amount_thb = 45;
-        let sum = sum!(amount_usd, usd; amount_thb, thb);
+        let sum = test_sum!(amount_usd, usd; amount_thb, thb);
         let actual = format!("{:?}", sum);
         let expected = format!(
             "Sum({{{:?}: {:?}, {:?}: {:?}}})",
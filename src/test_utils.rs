@@ -7,7 +7,7 @@ pub(crate) type TestBook = crate::book::Book<
     &'static str,
 >;
 #[cfg(test)]
-macro_rules! sum {
+macro_rules! test_sum {
     () => { crate::sum::Sum::<&'static str, u64>::default() };
     ($($amount:expr, $unit:ident);*) => {{
         let mut sum = crate::sum::Sum::<&str, u64>::default();
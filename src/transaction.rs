@@ -1,5 +1,17 @@
-use crate::move_::Move;
+use crate::{book::AccountKey, move_::Move, sum::Sum};
+use std::{
+    collections::{BTreeMap, HashSet},
+    iter::{self, Enumerate},
+    ops::Add,
+    slice,
+};
 /// Represents a transaction.
+///
+/// A transaction is always balanced, because each of its moves subtracts
+/// its sum from one account and adds the same sum to another account.
+/// Therefore, there is no API for checking whether a transaction is balanced.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transaction<Unit, SumNumber, Extra, MoveExtra>
 where
     Unit: Ord,
@@ -8,32 +20,134 @@ where
     pub(crate) moves: Vec<Move<Unit, SumNumber, MoveExtra>>,
 }
 /// Used to index moves in a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MoveIndex(pub usize);
+/// Iterates over the moves of a transaction in their order.
+///
+/// Obtained in [Transaction::moves].
+pub struct Moves<'a, Unit, SumNumber, MoveExtra>
+where
+    Unit: Ord,
+{
+    iter: Enumerate<slice::Iter<'a, Move<Unit, SumNumber, MoveExtra>>>,
+}
+impl<'a, Unit, SumNumber, MoveExtra> Iterator
+    for Moves<'a, Unit, SumNumber, MoveExtra>
+where
+    Unit: Ord,
+{
+    type Item = (MoveIndex, &'a Move<Unit, SumNumber, MoveExtra>);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|(index, move_)| (MoveIndex(index), move_))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
 impl<Unit, SumNumber, Extra, MoveExtra>
     Transaction<Unit, SumNumber, Extra, MoveExtra>
 where
     Unit: Ord,
 {
     /// Gets an iterator of existing moves in their order.
-    pub fn moves(
-        &self,
-    ) -> impl Iterator<Item = (MoveIndex, &Move<Unit, SumNumber, MoveExtra>)>
+    pub fn moves(&self) -> Moves<'_, Unit, SumNumber, MoveExtra> {
+        Moves {
+            iter: self.moves.iter().enumerate(),
+        }
+    }
+    /// Gets the number of moves.
+    pub fn move_count(&self) -> usize {
+        self.moves.len()
+    }
+    /// Gets an iterator of the distinct accounts that are on either side of
+    /// any of the moves, in order of first appearance.
+    pub fn accounts(&self) -> impl Iterator<Item = AccountKey> {
+        let mut seen = HashSet::new();
+        self.moves
+            .iter()
+            .flat_map(|move_| {
+                iter::once(move_.debit_account_key)
+                    .chain(iter::once(move_.credit_account_key))
+            })
+            .filter(|account_key| seen.insert(*account_key))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+    /// Gets the total of the sums of the moves, as debited.
+    ///
+    /// Always equals [Transaction::credit_sum].
+    pub fn debit_sum(&self) -> Sum<Unit, SumNumber>
+    where
+        Unit: Clone,
+        SumNumber: Add<Output = SumNumber> + Default + Clone,
+    {
+        self.moves_sum()
+    }
+    /// Gets the total of the sums of the moves, as credited.
+    ///
+    /// Always equals [Transaction::debit_sum].
+    pub fn credit_sum(&self) -> Sum<Unit, SumNumber>
+    where
+        Unit: Clone,
+        SumNumber: Add<Output = SumNumber> + Default + Clone,
+    {
+        self.moves_sum()
+    }
+    fn moves_sum(&self) -> Sum<Unit, SumNumber>
+    where
+        Unit: Clone,
+        SumNumber: Add<Output = SumNumber> + Default + Clone,
     {
+        let mut total = Sum(BTreeMap::new());
         self.moves
             .iter()
-            .enumerate()
-            .map(|(index, move_)| (MoveIndex(index), move_))
+            .flat_map(|move_| move_.sum.amounts())
+            .for_each(|(unit, amount)| {
+                let entry: &mut SumNumber =
+                    total.0.entry(unit.clone()).or_default();
+                *entry = std::mem::take(entry) + amount.clone();
+            });
+        total
     }
     /// Gets the extra data of the transaction.
     pub fn extra(&self) -> &Extra {
         &self.extra
     }
+    /// Converts the extra data of the transaction.
+    pub fn map_extra<B>(
+        self,
+        f: impl FnOnce(Extra) -> B,
+    ) -> Transaction<Unit, SumNumber, B, MoveExtra> {
+        Transaction {
+            extra: f(self.extra),
+            moves: self.moves,
+        }
+    }
+    /// Converts the extra data of each of the moves.
+    pub fn map_move_extra<B>(
+        self,
+        mut f: impl FnMut(MoveExtra) -> B,
+    ) -> Transaction<Unit, SumNumber, Extra, B> {
+        Transaction {
+            extra: self.extra,
+            moves: self
+                .moves
+                .into_iter()
+                .map(|move_| move_.map_extra(&mut f))
+                .collect(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::{MoveIndex, Transaction};
-    use crate::{book::TransactionIndex, test_utils::TestBook};
+    use crate::{
+        book::TransactionIndex,
+        test_utils::{TestBalance, TestBook},
+    };
     #[test]
     fn moves() {
         let mut book = TestBook::default();
@@ -45,7 +159,7 @@ mod test {
             MoveIndex(0),
             debit_account_key,
             credit_account_key,
-            sum!(),
+            test_sum!(),
             "a",
         );
         book.insert_move(
@@ -53,7 +167,7 @@ mod test {
             MoveIndex(1),
             debit_account_key,
             credit_account_key,
-            sum!(),
+            test_sum!(),
             "b",
         );
         book.insert_move(
@@ -61,7 +175,7 @@ mod test {
             MoveIndex(0),
             debit_account_key,
             credit_account_key,
-            sum!(),
+            test_sum!(),
             "c",
         );
         book.insert_move(
@@ -69,7 +183,7 @@ mod test {
             MoveIndex(2),
             debit_account_key,
             credit_account_key,
-            sum!(),
+            test_sum!(),
             "d",
         );
         assert_eq!(
@@ -82,6 +196,110 @@ mod test {
                 .collect::<Vec<_>>(),
             vec![&"c", &"a", &"d", &"b"],
         );
+        assert_eq!(
+            book.get_transaction(TransactionIndex(0))
+                .unwrap()
+                .move_count(),
+            4,
+        );
+    }
+    #[test]
+    fn accounts() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_b_key,
+            account_a_key,
+            test_sum!(),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_a_key,
+            account_c_key,
+            test_sum!(),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(2),
+            account_c_key,
+            account_b_key,
+            test_sum!(),
+            "",
+        );
+        let (_, transaction) = book.transactions().next().unwrap();
+        assert_eq!(
+            transaction.accounts().collect::<Vec<_>>(),
+            vec![account_b_key, account_a_key, account_c_key],
+        );
+    }
+    #[test]
+    fn balanced() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd; 2, thb),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_b_key,
+            account_c_key,
+            test_sum!(1, usd),
+            "",
+        );
+        let (_, transaction) = book.transactions().next().unwrap();
+        let mut net = TestBalance::default();
+        transaction.accounts().for_each(|account_key| {
+            net.apply_transaction(transaction, account_key);
+        });
+        assert!(net.amounts().all(|(_unit, amount)| *amount == 0));
+    }
+    #[test]
+    fn debit_sum_and_credit_sum() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let account_a_key = book.insert_account("");
+        let account_b_key = book.insert_account("");
+        let account_c_key = book.insert_account("");
+        let usd = "USD";
+        let thb = "THB";
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            account_a_key,
+            account_b_key,
+            test_sum!(3, usd; 2, thb),
+            "",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            account_c_key,
+            account_a_key,
+            test_sum!(1, usd),
+            "",
+        );
+        let (_, transaction) = book.transactions().next().unwrap();
+        assert_eq!(transaction.debit_sum(), test_sum!(4, usd; 2, thb));
+        assert_eq!(transaction.credit_sum(), test_sum!(4, usd; 2, thb));
     }
     #[test]
     fn extra() {
@@ -91,4 +309,64 @@ mod test {
         };
         assert_eq!(transaction.extra(), &"deposit",);
     }
+    #[test]
+    fn map_extra() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "deposit");
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(),
+            "a",
+        );
+        let (_, transaction) = book.transactions().next().unwrap();
+        let mapped = transaction
+            .clone()
+            .map_extra(|extra| extra.to_string() + "!");
+        assert_eq!(mapped.extra, "deposit!".to_string());
+        assert!(mapped.moves == transaction.moves);
+        let mapped = transaction.clone().map_move_extra(|extra| extra.len());
+        assert_eq!(mapped.extra, "deposit");
+        assert_eq!(mapped.moves[0].extra, 1);
+        assert_eq!(mapped.moves[0].sum, transaction.moves[0].sum);
+    }
+    #[test]
+    fn clone_eq() {
+        let mut book = TestBook::default();
+        book.insert_transaction(TransactionIndex(0), "");
+        let debit_account_key = book.insert_account("");
+        let credit_account_key = book.insert_account("");
+        let usd = "USD";
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            debit_account_key,
+            credit_account_key,
+            test_sum!(1, usd),
+            "a",
+        );
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(1),
+            credit_account_key,
+            debit_account_key,
+            test_sum!(2, usd),
+            "b",
+        );
+        let (_, transaction) = book.transactions().next().unwrap();
+        let mut clone = transaction.clone();
+        assert!(clone == *transaction);
+        clone.extra = "!";
+        assert!(clone != *transaction);
+        let mut clone = transaction.clone();
+        clone.moves[1].extra = "!";
+        assert!(clone != *transaction);
+        let mut clone = transaction.clone();
+        clone.moves.pop();
+        assert!(clone != *transaction);
+    }
 }
@@ -0,0 +1,39 @@
+use crate::{balance::Balance, sum::Sum};
+use std::collections::BTreeMap;
+/// Implemented by types that map units to amounts.
+pub trait UnitAmounts {
+    #[allow(missing_docs)]
+    type Unit: Ord;
+    #[allow(missing_docs)]
+    type Number;
+    #[allow(missing_docs)]
+    fn unit_amounts(&self) -> &BTreeMap<Self::Unit, Self::Number>;
+    #[allow(missing_docs)]
+    fn from_unit_amounts(map: BTreeMap<Self::Unit, Self::Number>) -> Self;
+}
+impl<Unit, Number> UnitAmounts for Sum<Unit, Number>
+where
+    Unit: Ord,
+{
+    type Unit = Unit;
+    type Number = Number;
+    fn unit_amounts(&self) -> &BTreeMap<Unit, Number> {
+        &self.0
+    }
+    fn from_unit_amounts(map: BTreeMap<Unit, Number>) -> Self {
+        Self(map)
+    }
+}
+impl<Unit, Number> UnitAmounts for Balance<Unit, Number>
+where
+    Unit: Ord,
+{
+    type Unit = Unit;
+    type Number = Number;
+    fn unit_amounts(&self) -> &BTreeMap<Unit, Number> {
+        &self.0
+    }
+    fn from_unit_amounts(map: BTreeMap<Unit, Number>) -> Self {
+        Self(map)
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{
+    book::{AccountKey, TransactionIndex},
+    transaction::MoveIndex,
+};
+use std::fmt;
+/// A problem with the integrity of a [Book](crate::Book), as reported by
+/// [Book::validate](crate::Book::validate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// The debit and credit accounts of a move are the same account.
+    SelfMove(TransactionIndex, MoveIndex, AccountKey),
+    /// A move refers to an account that is not in the book.
+    AccountNotFound(TransactionIndex, MoveIndex, AccountKey),
+}
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::SelfMove(
+                transaction_index,
+                move_index,
+                account_key,
+            ) => write!(
+                f,
+                "move {} of transaction {} is from {} to itself",
+                move_index.0, transaction_index.0, account_key,
+            ),
+            ValidationIssue::AccountNotFound(
+                transaction_index,
+                move_index,
+                account_key,
+            ) => write!(
+                f,
+                "move {} of transaction {} refers to missing account {}",
+                move_index.0, transaction_index.0, account_key,
+            ),
+        }
+    }
+}
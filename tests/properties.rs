@@ -0,0 +1,223 @@
+use bookkeeping::*;
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+type TestBook = Book<&'static str, u64, (), (), ()>;
+type TestBalance = Balance<&'static str, i128>;
+const UNITS: [&str; 3] = ["USD", "THB", "ILS"];
+/// An edit to a book.
+///
+/// Indices are reduced modulo the relevant length when applied, so that
+/// every generated edit applies and shrinks towards the first items.
+#[derive(Debug, Clone)]
+enum Op {
+    InsertTransaction(usize),
+    RemoveTransaction(usize),
+    InsertMove(usize, usize, usize, usize, Vec<(usize, u64)>),
+    RemoveMove(usize, usize),
+    SetMoveSum(usize, usize, Vec<(usize, u64)>),
+    /// Removes the transactions whose number of moves is of a parity.
+    RemoveTransactionsMatching(bool),
+    DedupAdjacentTransactions,
+    Undo,
+}
+fn sum_strategy() -> impl Strategy<Value = Vec<(usize, u64)>> {
+    prop::collection::vec((0..UNITS.len(), 0..1000_u64), 0..=UNITS.len())
+}
+fn op_strategy() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => any::<usize>().prop_map(Op::InsertTransaction),
+        1 => any::<usize>().prop_map(Op::RemoveTransaction),
+        6 => (
+            any::<usize>(),
+            any::<usize>(),
+            any::<usize>(),
+            any::<usize>(),
+            sum_strategy(),
+        )
+            .prop_map(|(t, m, debit, offset, sum)| {
+                Op::InsertMove(t, m, debit, offset, sum)
+            }),
+        1 => (any::<usize>(), any::<usize>())
+            .prop_map(|(t, m)| Op::RemoveMove(t, m)),
+        2 => (any::<usize>(), any::<usize>(), sum_strategy())
+            .prop_map(|(t, m, sum)| Op::SetMoveSum(t, m, sum)),
+        1 => any::<bool>().prop_map(Op::RemoveTransactionsMatching),
+        1 => Just(Op::DedupAdjacentTransactions),
+        1 => Just(Op::Undo),
+    ]
+}
+fn to_sum(amounts: &[(usize, u64)]) -> Sum<&'static str, u64> {
+    amounts
+        .iter()
+        .map(|(unit, amount)| (UNITS[*unit], *amount))
+        .collect()
+}
+/// Applies an edit, unless it does not apply to the book, in which case it
+/// is skipped. Removals that remove nothing are not undoable.
+///
+/// Undo is skipped once only the insertion of the accounts is left to undo.
+fn apply(
+    book: &mut TestBook,
+    account_keys: &[AccountKey],
+    undoable: &mut usize,
+    op: &Op,
+) {
+    let transaction_count = book.transactions().len();
+    let move_count = |book: &TestBook, t: usize| {
+        book.get_transaction(TransactionIndex(t % transaction_count))
+            .unwrap()
+            .moves()
+            .count()
+    };
+    match op {
+        Op::InsertTransaction(t) => {
+            book.insert_transaction(
+                TransactionIndex(t % (transaction_count + 1)),
+                (),
+            );
+        }
+        Op::RemoveTransaction(t) if transaction_count > 0 => {
+            book.remove_transaction(TransactionIndex(t % transaction_count));
+        }
+        Op::InsertMove(t, m, debit, offset, sum) if transaction_count > 0 => {
+            let debit = debit % account_keys.len();
+            let credit = (debit + 1 + offset % (account_keys.len() - 1))
+                % account_keys.len();
+            book.insert_move(
+                TransactionIndex(t % transaction_count),
+                MoveIndex(m % (move_count(book, *t) + 1)),
+                account_keys[debit],
+                account_keys[credit],
+                to_sum(sum),
+                (),
+            );
+        }
+        Op::RemoveMove(t, m)
+            if transaction_count > 0 && move_count(book, *t) > 0 =>
+        {
+            book.remove_move(
+                TransactionIndex(t % transaction_count),
+                MoveIndex(m % move_count(book, *t)),
+            );
+        }
+        Op::SetMoveSum(t, m, sum)
+            if transaction_count > 0 && move_count(book, *t) > 0 =>
+        {
+            book.set_move_sum(
+                TransactionIndex(t % transaction_count),
+                MoveIndex(m % move_count(book, *t)),
+                to_sum(sum),
+            );
+        }
+        Op::RemoveTransactionsMatching(odd) => {
+            let removed = book.remove_transactions_matching(|transaction| {
+                (transaction.moves().count() % 2 == 1) == *odd
+            });
+            if removed == 0 {
+                return;
+            }
+        }
+        Op::DedupAdjacentTransactions => {
+            if book.dedup_adjacent_transactions() == 0 {
+                return;
+            }
+        }
+        Op::Undo if *undoable > 0 => {
+            book.undo();
+            *undoable -= 1;
+            return;
+        }
+        _ => return,
+    }
+    *undoable += 1;
+}
+/// Applies edits to a book with a number of accounts, checking a property
+/// after each, so that balances are also calculated in between edits.
+fn check_after_each_op(
+    account_count: usize,
+    ops: &[Op],
+    check: impl Fn(&TestBook, &[AccountKey]) -> Result<(), TestCaseError>,
+) -> Result<(), TestCaseError> {
+    let mut book = TestBook::default();
+    let account_keys: Vec<_> = (0..account_count)
+        .map(|_| book.insert_account(()))
+        .collect();
+    let mut undoable = 0;
+    ops.iter().try_for_each(|op| {
+        apply(&mut book, &account_keys, &mut undoable, op);
+        check(&book, &account_keys)
+    })
+}
+fn naive_balance(
+    book: &TestBook,
+    account_key: AccountKey,
+    transaction_index: TransactionIndex,
+) -> TestBalance {
+    let mut balance = TestBalance::default();
+    book.transactions()
+        .take(transaction_index.0 + 1)
+        .flat_map(|(_, transaction)| transaction.moves())
+        .for_each(|(_, move_)| {
+            if move_.side_key(Side::Debit) == account_key {
+                balance -= move_.sum();
+            }
+            if move_.side_key(Side::Credit) == account_key {
+                balance += move_.sum();
+            }
+        });
+    balance
+}
+fn balances_total_zero(
+    book: &TestBook,
+    account_keys: &[AccountKey],
+) -> Result<(), TestCaseError> {
+    for (transaction_index, _) in book.transactions() {
+        let mut totals = BTreeMap::new();
+        for account_key in account_keys {
+            let balance: TestBalance = book
+                .account_balance_at_transaction(account_key, transaction_index);
+            for (unit, amount) in &balance {
+                *totals.entry(*unit).or_insert(0) += amount;
+            }
+        }
+        prop_assert!(
+            totals.values().all(|total| *total == 0),
+            "totals at {:?}: {:?}",
+            transaction_index,
+            totals,
+        );
+    }
+    Ok(())
+}
+fn balances_match_naive(
+    book: &TestBook,
+    account_keys: &[AccountKey],
+) -> Result<(), TestCaseError> {
+    for (transaction_index, _) in book.transactions() {
+        for account_key in account_keys {
+            let balance: TestBalance = book
+                .account_balance_at_transaction(account_key, transaction_index);
+            prop_assert_eq!(
+                balance,
+                naive_balance(book, *account_key, transaction_index),
+            );
+        }
+    }
+    Ok(())
+}
+proptest! {
+    #[test]
+    fn balances_of_all_accounts_total_zero(
+        account_count in 2..6_usize,
+        ops in prop::collection::vec(op_strategy(), 0..60),
+    ) {
+        check_after_each_op(account_count, &ops, balances_total_zero)?;
+    }
+    #[test]
+    fn account_balance_at_transaction_matches_naive(
+        account_count in 2..6_usize,
+        ops in prop::collection::vec(op_strategy(), 0..60),
+    ) {
+        check_after_each_op(account_count, &ops, balances_match_naive)?;
+    }
+}
@@ -5,31 +5,294 @@ fn balance() {
     type TestBalance = Balance<(), ()>;
     TestBalance::amounts;
     TestBalance::unit_amount;
+    Balance::<(), i8>::get_or_default;
+    TestBalance::to_hashmap;
+    TestBalance::clear;
+    let _ = |balance: Balance<(), i8>| balance.map_units(|_| 0);
+    Balance::<(), i8>::scale::<i8>;
+    Balance::<(), i8>::normalize;
+    Balance::<(), i8>::columns;
+    Balance::<&str, i8>::fmt_aligned;
+    <Balance<&str, i8> as ToString>::to_string;
+    <TestBalance as IntoIterator>::into_iter;
+    <TestBalance as std::iter::FromIterator<((), ())>>::from_iter::<Vec<_>>;
+    <&TestBalance as IntoIterator>::into_iter;
+    struct Formatter;
+    impl FormatAmount<(), i8> for Formatter {
+        fn format(&self, _unit: &(), _amount: &i8) -> String {
+            String::new()
+        }
+    }
+    let _ = |balance: Balance<(), i8>| balance.format_with(&Formatter);
+    Balance::<(), f64>::approx_eq;
+    Balance::<(), i8>::diff;
+    <Balance<(), i8> as std::ops::Sub<&Balance<(), i8>>>::sub;
+    <Balance<(), i8> as std::ops::Neg>::neg;
+    Balance::<(), i8>::is_empty;
+    Balance::<(), i8>::len;
+    Balance::<(), i8>::is_zero;
+    let _ = |balance: Balance<(), i8>| {
+        balance.total_in((), |_| Some(1), |amount| *amount)
+    };
+    Balance::<(), i16>::apply_transaction::<u8, (), ()>;
+}
+#[test]
+fn account_key() {
+    <AccountKey as ToString>::to_string;
 }
 #[test]
 fn book() {
     type TestBook = bookkeeping::Book<(), u8, (), (), ()>;
     TestBook::default;
+    #[cfg(feature = "serde")]
+    serde_json::to_string::<TestBook>;
+    #[cfg(feature = "serde")]
+    serde_json::from_str::<TestBook>;
     TestBook::insert_account;
+    TestBook::append;
+    TestBook::remove_account;
+    TestBook::try_remove_account;
+    #[cfg(feature = "testing")]
+    TestBook::synthetic;
+    #[cfg(feature = "testing")]
+    TestBook::cache_stats;
+    let _ = |book: &mut TestBook| book.insert_accounts(vec![()]);
     TestBook::insert_transaction;
-    TestBook::insert_move;
+    TestBook::clone_transaction;
+    TestBook::reverse_transaction;
+    let _ = |book: &mut TestBook, key: AccountKey| {
+        book.insert_transaction_with_moves(
+            TransactionIndex(0),
+            (),
+            vec![(key, key, Sum::default(), ())],
+        )
+    };
+    let _ = |book: &mut TestBook, key: AccountKey| {
+        book.transfer_fan_out(TransactionIndex(0), key, vec![], (), |_| ())
+    };
+    let _ = |book: TestBook| book.with_transaction((), |_| {});
+    let _ = |book: &mut TestBook, key: &AccountKey| {
+        book.insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            *key,
+            key,
+            Sum::default(),
+            (),
+        )
+    };
+    let _ = |book: &mut TestBook, key: &AccountKey| {
+        book.try_insert_move(
+            TransactionIndex(0),
+            MoveIndex(0),
+            *key,
+            key,
+            Sum::default(),
+            (),
+        )
+    };
+    TestBook::insert_move_sided;
+    TestBook::reverse_move;
+    TestBook::reserve_moves;
+    TestBook::shrink_to_fit;
     TestBook::get_account;
+    TestBook::try_get_account;
+    TestBook::get_transaction;
+    TestBook::get_move;
+    let _ = |book: &TestBook, key: AccountKey| book.map_account(key, |_| ());
     TestBook::accounts;
+    TestBook::accounts_enumerated;
+    TestBook::nth_account_key;
+    TestBook::account_ordinal;
     TestBook::transactions;
+    let _ = |book: &TestBook| book.transactions_where(|_| true).count();
+    let _ = |book: &TestBook| book.group_transactions_by(|_| 0);
+    let _ = |book: &TestBook| book.min_transaction_by(|_| 0);
+    let _ = |book: &TestBook| book.max_transaction_by(|_| 0);
+    let _ = |book: &TestBook| book.moves().count();
+    let _ = |book: &TestBook, key: AccountKey| book.account_moves(key).count();
+    let _ = |book: &TestBook| book.moves_for_unit(&()).count();
     TestBook::set_account;
     TestBook::set_transaction_extra;
     TestBook::set_move_extra;
-    TestBook::account_balance_at_transaction::<i16>;
+    TestBook::move_extra_mut;
+    let _ =
+        |book: &mut TestBook| book.update_moves(TransactionIndex(0), |_, _| {});
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_balance_seeded(key, TransactionIndex(0), 0_i16)
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_balance_at_transaction::<i16>(key, TransactionIndex(0))
+    };
+    #[cfg(feature = "num-traits")]
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.checked_account_balance_at_transaction::<i16>(
+            key,
+            TransactionIndex(0),
+        )
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_balance_at_transaction_counted::<i16>(
+            key,
+            TransactionIndex(0),
+        )
+    };
+    let _ =
+        |book: &TestBook, key: AccountKey, balance: &mut Balance<(), i16>| {
+            book.account_balance_at_transaction_into(
+                key,
+                TransactionIndex(0),
+                balance,
+            )
+        };
+    TestBook::flow_between::<i16>;
+    TestBook::unit_balance_history_for_accounts::<i16>;
+    TestBook::balances_at_transaction::<i16>;
+    TestBook::total_balance::<i16>;
+    TestBook::balance_matrix::<i16>;
+    let _ = |book: &TestBook| {
+        book.accounts_with_balance_at::<i16>(TransactionIndex(0))
+            .count()
+    };
+    TestBook::accounts_touched_between;
+    TestBook::reconcile::<i16>;
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.balance(key, TransactionIndex(0))
+    };
+    TestBook::account_balance_fingerprint;
+    #[cfg(feature = "rayon")]
+    TestBook::balances_at_transaction_parallel::<i16>;
+    TestBook::accounts_sorted_by_unit_balance::<i16>;
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_unit_balance_at_transaction::<i16>(
+            key,
+            &(),
+            TransactionIndex(0),
+        )
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_unit_balances_at_transaction::<i16>(
+            key,
+            &[],
+            TransactionIndex(0),
+        )
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_turnover::<i16>(key, TransactionIndex(0))
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_sum_at_transaction(key, TransactionIndex(0))
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_balance_samples::<i16>(key, &[])
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_balance_after::<i16>(key, TransactionIndex(0))
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_balance_before::<i16>(key, TransactionIndex(0))
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_balance_between::<i16>(
+            key,
+            TransactionIndex(0),
+            TransactionIndex(0),
+        )
+    };
+    TestBook::account_statement::<i16>;
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_balance_history::<i16>(key)
+    };
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_running_balance::<i16>(key).count()
+    };
+    let _ =
+        |book: &Book<&str, u8, (), (), ()>| book.to_dot(|_, _| String::new());
+    let _ = |book: &Book<&str, u8, (), &str, ()>| {
+        book.export_journal(|_| String::new())
+    };
+    #[cfg(feature = "csv")]
+    let _ = |book: &Book<&str, u8, (), (), ()>| {
+        book.write_csv(Vec::new(), |_| String::new(), |_| String::new())
+    };
     TestBook::remove_move;
+    TestBook::dedup_adjacent_transactions;
+    TestBook::prune_orphan_moves;
+    TestBook::compact_transaction;
+    let _ = |book: &mut TestBook| book.remove_transactions_matching(|_| true);
+    TestBook::move_count_for_account;
+    TestBook::transaction_count_for_account;
+    let _ = |book: &TestBook, key: AccountKey| {
+        book.account_activity_at_transaction(key, TransactionIndex(0))
+    };
+    TestBook::set_move_index;
+    TestBook::relocate_move;
+    TestBook::move_transaction;
+    TestBook::move_sum;
     TestBook::set_move_sum;
-    TestBook::set_move_side;
+    TestBook::set_reject_empty_sums;
+    TestBook::insert_unit;
+    let _ = |book: &mut TestBook| {
+        book.insert_transaction_sorted_by((), |extra| *extra)
+    };
+    TestBook::units;
+    let _ = |book: &mut TestBook, key: AccountKey| {
+        book.set_move_side(TransactionIndex(0), MoveIndex(0), Side::Debit, key)
+    };
+    TestBook::validate;
+    TestBook::diff;
+    <TestBook as Clone>::clone;
+    <TestBook as PartialEq>::eq;
+    <TestBook as std::fmt::Debug>::fmt;
+    TestBook::snapshot;
+    TestBook::account_count;
+    TestBook::transaction_count;
+    TestBook::apply;
+    TestBook::on_change;
+    TestBook::undo;
+    TestBook::redo;
+}
+#[test]
+fn transaction_builder() {
+    type TestBook = bookkeeping::Book<(), u8, (), (), ()>;
+    let _ = |book: TestBook, key: AccountKey| {
+        book.with_transaction((), |transaction| {
+            transaction.push_move(key, key, Sum::default(), ());
+        })
+    };
+}
+#[test]
+fn move_editor() {
+    type TestBook = bookkeeping::Book<(), u8, (), (), ()>;
+    let _ = |book: &mut TestBook, key: AccountKey| {
+        book.modify_move(TransactionIndex(0), MoveIndex(0), |editor| {
+            editor.move_();
+            editor
+                .set_side(Side::Debit, key)
+                .set_sum(Sum::default())
+                .set_extra(());
+        })
+    };
 }
 #[test]
 fn move_() {
     type TestMove = Move<(), (), ()>;
     TestMove::side_key;
+    TestMove::debit_account_key;
+    TestMove::credit_account_key;
+    TestMove::account_keys;
     TestMove::sum;
+    Move::<(), u8, ()>::amount_or_default;
     TestMove::extra;
+    let _ = |move_: TestMove| move_.map_extra(|_| 0);
+    <TestMove as std::fmt::Debug>::fmt;
+}
+#[test]
+fn side() {
+    Side::index;
+    <Side as std::str::FromStr>::from_str;
+    <Side as ToString>::to_string;
+    <ParseSideError as ToString>::to_string;
 }
 #[test]
 fn sum() {
@@ -37,10 +300,152 @@ fn sum() {
     TestSum::default;
     TestSum::set_amount_for_unit;
     TestSum::amounts;
+    TestSum::unit_amount;
+    TestSum::remove_unit;
+    TestSum::contains_unit;
+    TestSum::len;
+    TestSum::is_empty;
+    <TestSum as std::ops::Index<&()>>::index;
+    <TestSum as PartialOrd>::partial_cmp;
+    TestSum::into_single;
+    <TestSum as std::iter::FromIterator<((), u64)>>::from_iter::<Vec<_>>;
+    <TestSum as IntoIterator>::into_iter;
+    <&TestSum as IntoIterator>::into_iter;
+    Sum::<(), i64>::signed_for;
+    TestSum::to_hashmap;
+    TestSum::saturating_sub;
+    #[cfg(feature = "num-traits")]
+    TestSum::checked_sub;
+    TestSum::weighted_add;
+    TestSum::scale;
+    <TestSum as std::ops::Add<&TestSum>>::add;
+    <TestSum as std::ops::Sub<&TestSum>>::sub;
+    <TestSum as std::ops::Add>::add;
+    <TestSum as std::ops::Sub>::sub;
+    <TestSum as std::ops::AddAssign<&TestSum>>::add_assign;
+    <TestSum as std::ops::SubAssign<&TestSum>>::sub_assign;
+    <TestSum as std::ops::AddAssign>::add_assign;
+    <TestSum as std::ops::SubAssign>::sub_assign;
+    let _ = |sum: TestSum| sum.map_units(|_| 0);
+    let _ = |sum: TestSum| sum.group_by(|_| 0);
+    TestSum::normalize;
+    TestSum::normalized;
+    TestSum::is_zero;
+    let _ = |sum: &mut TestSum| sum.ensure_units(vec![()]);
+    let _ = |a: &TestSum, b: &TestSum| a.shared_units(b).count();
+    let _ = |a: &TestSum, b: &TestSum| a.only_in_self_units(b).count();
+    Sum::<&str, u8>::fmt_aligned;
+    <Sum<&str, u8> as ToString>::to_string;
+    let _ = |sum: TestSum| sum.abs_with(|amount| *amount);
+    Sum::<(), f64>::approx_eq;
+    TestSum::max_amount;
+    TestSum::min_amount;
+    let _ = |sum: TestSum| sum.convert_to((), |_| Some(1), |amount| *amount);
 }
 #[test]
 fn transaction() {
     type TestTransaction = Transaction<(), (), (), ()>;
+    <TestTransaction as std::fmt::Debug>::fmt;
     TestTransaction::moves;
+    TestTransaction::move_count;
     TestTransaction::extra;
+    TestTransaction::accounts;
+    Transaction::<(), u8, (), ()>::debit_sum;
+    Transaction::<(), u8, (), ()>::credit_sum;
+    let _ = |transaction: TestTransaction| transaction.map_extra(|_| 0);
+    let _ = |transaction: TestTransaction| transaction.map_move_extra(|_| 0);
+}
+#[test]
+fn book_op() {
+    type TestBookOp = BookOp<(), u8, (), (), ()>;
+    let _ = TestBookOp::SetAccount;
+    let _ = TestBookOp::InsertTransaction;
+    let _ = TestBookOp::RemoveTransaction;
+    let _ = TestBookOp::SetTransactionExtra;
+    let _ = TestBookOp::InsertMove;
+    let _ = TestBookOp::RemoveMove;
+    let _ = TestBookOp::SetMoveExtra;
+    let _ = TestBookOp::SetMoveSum;
+    let _ = BookOpError::<()>::AccountNotFound;
+    let _ = BookOpError::<()>::TransactionIndexOutOfBounds;
+    let _ = BookOpError::<()>::MoveIndexOutOfBounds;
+    let _ = BookOpError::<()>::SameAccounts;
+    let _ = BookOpError::<()>::UnitNotFound;
+    let _ = BookOpError::<()>::EmptySum;
+    <BookOpError<()> as ToString>::to_string;
+}
+#[test]
+fn book_diff() {
+    BookDiff::default;
+    BookDiff::is_empty;
+    let _ = |diff: BookDiff| {
+        (
+            diff.added_accounts,
+            diff.removed_accounts,
+            diff.changed_accounts,
+            diff.added_transactions,
+            diff.removed_transactions,
+            diff.changed_transactions,
+            diff.added_moves,
+            diff.removed_moves,
+            diff.changed_moves,
+        )
+    };
+}
+#[test]
+fn book_snapshot() {
+    type TestSnapshot = BookSnapshot<(), u8, (), (), ()>;
+    TestSnapshot::accounts;
+    TestSnapshot::transactions;
+    let _ = |snapshot: &TestSnapshot, key: AccountKey| {
+        snapshot.account_balance_at_transaction::<i16>(key, TransactionIndex(0))
+    };
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<TestSnapshot>();
+}
+#[test]
+fn validation_issue() {
+    let _ = ValidationIssue::SelfMove;
+    let _ = ValidationIssue::AccountNotFound;
+    <ValidationIssue as ToString>::to_string;
+}
+#[test]
+fn negative_balance_error() {
+    NegativeBalanceError::<()>::units;
+    <NegativeBalanceError<()> as ToString>::to_string;
+}
+#[cfg(feature = "num-traits")]
+#[test]
+fn balance_overflow_error() {
+    BalanceOverflowError::<()>::unit;
+    BalanceOverflowError::<()>::transaction_index;
+    <BalanceOverflowError<()> as ToString>::to_string;
+}
+#[test]
+fn insert_move_error() {
+    let _ = InsertMoveError::<()>::TransactionIndexOutOfBounds;
+    let _ = InsertMoveError::<()>::MoveIndexOutOfBounds;
+    let _ = InsertMoveError::<()>::DebitAccountNotFound;
+    let _ = InsertMoveError::<()>::CreditAccountNotFound;
+    let _ = InsertMoveError::<()>::DebitEqualsCredit;
+    let _ = InsertMoveError::<()>::UnitNotFound;
+    let _ = InsertMoveError::<()>::EmptySum;
+    <InsertMoveError<()> as ToString>::to_string;
+}
+#[test]
+fn remove_account_error() {
+    RemoveAccountError::moves;
+    <RemoveAccountError as ToString>::to_string;
+}
+#[test]
+fn iterators() {
+    let _ = |accounts: Accounts<()>| accounts.count();
+    let _ =
+        |transactions: Transactions<(), u8, (), ()>| transactions.rev().len();
+    let _ = |moves: Moves<(), u8, ()>| moves.count();
+}
+#[test]
+fn macros() {
+    let _: Sum<(), u8> = sum![1 => ()];
+    let _: Balance<(), i8> = balance![-1 => ()];
 }